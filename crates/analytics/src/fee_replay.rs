@@ -0,0 +1,607 @@
+use std::collections::{BTreeMap, HashMap};
+
+use alloy::primitives::{I256, U256};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use stillwater_models::{LiquidityEvent, Position, Swap};
+
+use crate::full_math::{mul_div, mul_div_rounding_up};
+use crate::sqrt_price_math::{get_amount0_delta, get_amount1_delta};
+use crate::tick_math::{get_sqrt_price_at_tick, get_tick_at_sqrt_price};
+
+/// Swap fees are in hundredths of a bip
+const FEE_DENOMINATOR: u32 = 1_000_000;
+
+/// A position as the PoolManager identifies it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PositionKey {
+    /// PoolManager-level owner (the PositionManager for NFT positions)
+    pub owner: String,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub salt: String,
+}
+
+impl PositionKey {
+    pub fn from_position(position: &Position) -> Self {
+        Self {
+            owner: position.manager.clone(),
+            tick_lower: position.tick_lower,
+            tick_upper: position.tick_upper,
+            salt: position.salt.clone(),
+        }
+    }
+
+    pub fn from_event(event: &LiquidityEvent) -> Self {
+        Self {
+            owner: event.owner.clone(),
+            tick_lower: event.tick_lower,
+            tick_upper: event.tick_upper,
+            salt: event.salt.clone(),
+        }
+    }
+}
+
+/// Fees attributed to a position by a replay, in raw token0/token1 amounts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplayedFees {
+    /// Position liquidity after the last replayed event
+    pub liquidity: u128,
+    /// Fees earned over the whole replay
+    pub earned: (U256, U256),
+    /// Fees earned since the position was last modified
+    ///
+    /// v4 credits accrued fees to the owner on every liquidity change, including the
+    /// zero-delta ones used to collect.
+    pub uncollected: (U256, U256),
+}
+
+/// Replays a pool's swap stream and attributes swap fees to tracked positions
+///
+/// The pool's active liquidity is rebuilt from all of its liquidity events, so every event
+/// in the pool must be applied, not only those of tracked positions. Each swap's price
+/// path is split at the initialized ticks and tracked range boundaries it crosses; in each
+/// segment the active liquidity takes the input SqrtPriceMath gives for it, plus the fee
+/// on top, and positions in range over the segment share that fee by
+/// `position_liquidity / active_liquidity`. Protocol fees are not deducted.
+#[derive(Debug, Clone, Default)]
+pub struct FeeReplay {
+    sqrt_price_x96: Option<U256>,
+    pool_fee: Option<u32>,
+    /// Liquidity added when the price crosses each tick upwards
+    liquidity_net: BTreeMap<i32, i128>,
+    positions: HashMap<PositionKey, ReplayedFees>,
+    skipped_swaps: usize,
+}
+
+impl FeeReplay {
+    /// Start a replay at the pool price before the first swap, if known
+    pub fn new(sqrt_price_x96: Option<U256>) -> Self {
+        Self { sqrt_price_x96, ..Self::default() }
+    }
+
+    /// Charge swaps stored without a fee the pool's static fee
+    pub fn with_pool_fee(mut self, fee: u32) -> Self {
+        self.pool_fee = Some(fee);
+        self
+    }
+
+    /// Track a position, starting with no liquidity
+    pub fn track(&mut self, key: PositionKey) {
+        self.positions.entry(key).or_default();
+    }
+
+    /// Fees attributed to a tracked position
+    pub fn fees(&self, key: &PositionKey) -> Option<&ReplayedFees> {
+        self.positions.get(key)
+    }
+
+    /// Swaps whose fees could not be attributed to tracked positions with liquidity (no
+    /// price before or after them, no known fee, or less active liquidity than the tracked
+    /// positions hold, which means pool liquidity events are missing)
+    pub fn skipped_swaps(&self) -> usize {
+        self.skipped_swaps
+    }
+
+    /// Apply a liquidity change to the pool, and to its position if tracked
+    pub fn apply_liquidity_event(&mut self, event: &LiquidityEvent) {
+        if let Ok(delta) = i128::try_from(event.liquidity_delta) {
+            for (tick, net) in [(event.tick_lower, delta), (event.tick_upper, -delta)] {
+                let entry = self.liquidity_net.entry(tick).or_default();
+                *entry = entry.saturating_add(net);
+                if *entry == 0 {
+                    self.liquidity_net.remove(&tick);
+                }
+            }
+        }
+
+        let Some(fees) = self.positions.get_mut(&PositionKey::from_event(event)) else {
+            return;
+        };
+
+        let delta = event.liquidity_delta;
+        let magnitude = u128::try_from(delta.unsigned_abs()).unwrap_or(u128::MAX);
+        fees.liquidity = if delta.is_negative() {
+            fees.liquidity.saturating_sub(magnitude)
+        } else {
+            fees.liquidity.saturating_add(magnitude)
+        };
+        fees.uncollected = (U256::ZERO, U256::ZERO);
+    }
+
+    /// Attribute a swap's fee and move the pool price to its post-swap price
+    pub fn apply_swap(&mut self, swap: &Swap) {
+        let before = self.sqrt_price_x96;
+        if let Some(after) = swap.sqrt_price_x96 {
+            self.sqrt_price_x96 = Some(after);
+        }
+
+        let shares = match (before, swap.sqrt_price_x96) {
+            (Some(before), Some(after)) => self.fee_shares(swap, before, after),
+            _ if self.positions.values().all(|fees| fees.liquidity == 0) => return,
+            _ => Err(anyhow!("No price before swap {}", swap.tx_hash)),
+        };
+        let Ok((zero_for_one, shares)) = shares else {
+            self.skipped_swaps += 1;
+            return;
+        };
+
+        for (key, share) in shares {
+            let fees = self.positions.get_mut(&key).expect("shares are for tracked positions");
+            for amounts in [&mut fees.earned, &mut fees.uncollected] {
+                let amount = if zero_for_one { &mut amounts.0 } else { &mut amounts.1 };
+                *amount = amount.saturating_add(share);
+            }
+        }
+    }
+
+    /// Replay a page of swaps and the liquidity events before its last swap
+    ///
+    /// Both must be in chain order. Returns the events after the page, to pass along with
+    /// the next one; an empty page applies all of them.
+    pub fn apply_page<'a>(
+        &mut self,
+        swaps: &[Swap],
+        liquidity_events: &'a [LiquidityEvent],
+    ) -> &'a [LiquidityEvent] {
+        let mut pending = liquidity_events;
+        for swap in swaps {
+            while let Some((event, rest)) = pending.split_first()
+                && liquidity_event_order(event) < swap_order(swap)
+            {
+                self.apply_liquidity_event(event);
+                pending = rest;
+            }
+            self.apply_swap(swap);
+        }
+
+        if swaps.is_empty() {
+            for event in pending {
+                self.apply_liquidity_event(event);
+            }
+            return &[];
+        }
+        pending
+    }
+
+    /// Input token direction and each in-range position's share of a swap's fee
+    ///
+    /// Fails when a position with liquidity in the path cannot be given an exact share.
+    fn fee_shares(
+        &self,
+        swap: &Swap,
+        before: U256,
+        after: U256,
+    ) -> Result<(bool, Vec<(PositionKey, U256)>)> {
+        // Amounts are from the pool's perspective: the input is the positive one
+        let zero_for_one = swap.amount0 > I256::ZERO;
+        let amount_in = if zero_for_one { swap.amount0 } else { swap.amount1 };
+        if amount_in <= I256::ZERO {
+            bail!("Swap {} has no input amount", swap.tx_hash);
+        }
+        let (low, high) = if before > after { (after, before) } else { (before, after) };
+
+        let mut earning = Vec::new();
+        for (key, fees) in &self.positions {
+            if fees.liquidity > 0 {
+                let sqrt_lower = get_sqrt_price_at_tick(key.tick_lower)?;
+                let sqrt_upper = get_sqrt_price_at_tick(key.tick_upper)?;
+                earning.push((key, fees.liquidity, sqrt_lower, sqrt_upper));
+            }
+        }
+        let fee = swap.fee.and_then(|fee| u32::try_from(fee).ok()).or(self.pool_fee);
+        let mut shares: HashMap<&PositionKey, U256> = HashMap::new();
+
+        if low == high {
+            // The price did not move: the whole fee goes to the liquidity in range at it
+            let in_range: Vec<_> = earning
+                .iter()
+                .filter(|(_, _, sqrt_lower, sqrt_upper)| *sqrt_lower <= low && low < *sqrt_upper)
+                .collect();
+            if in_range.is_empty() {
+                return Ok((zero_for_one, Vec::new()));
+            }
+            let fee = fee.ok_or_else(|| anyhow!("Swap {} has no fee", swap.tx_hash))?;
+            let fee_amount = mul_div(
+                amount_in.into_raw(),
+                U256::from(fee),
+                U256::from(FEE_DENOMINATOR),
+            )?;
+            let active = self.active_liquidity(low)?;
+            for (key, liquidity, ..) in in_range {
+                *shares.entry(*key).or_default() += share(swap, fee_amount, *liquidity, active)?;
+            }
+            return Ok((zero_for_one, shares.into_iter().map(|(k, s)| (k.clone(), s)).collect()));
+        }
+
+        // Split the path wherever the active liquidity or a tracked range starts or ends
+        let mut bounds = vec![low, high];
+        let (tick_low, tick_high) = (get_tick_at_sqrt_price(low)?, get_tick_at_sqrt_price(high)?);
+        for tick in self.liquidity_net.range(tick_low..=tick_high).map(|(tick, _)| *tick) {
+            bounds.push(get_sqrt_price_at_tick(tick)?);
+        }
+        for (_, _, sqrt_lower, sqrt_upper) in &earning {
+            bounds.extend([*sqrt_lower, *sqrt_upper]);
+        }
+        bounds.retain(|bound| low <= *bound && *bound <= high);
+        bounds.sort();
+        bounds.dedup();
+
+        for segment in bounds.windows(2) {
+            let (from, to) = (segment[0], segment[1]);
+            let in_range: Vec<_> = earning
+                .iter()
+                .filter(|(_, _, sqrt_lower, sqrt_upper)| *sqrt_lower <= from && to <= *sqrt_upper)
+                .collect();
+            if in_range.is_empty() {
+                continue;
+            }
+
+            let fee = fee.ok_or_else(|| anyhow!("Swap {} has no fee", swap.tx_hash))?;
+            if fee >= FEE_DENOMINATOR {
+                bail!("Swap {} charged a fee of {}", swap.tx_hash, fee);
+            }
+            let active = self.active_liquidity(from)?;
+            let input = if zero_for_one {
+                get_amount0_delta(from, to, active, true)?
+            } else {
+                get_amount1_delta(from, to, active, true)?
+            };
+            // Like SwapMath, the fee is charged on top of the input a step takes
+            let segment_fee = mul_div_rounding_up(
+                input,
+                U256::from(fee),
+                U256::from(FEE_DENOMINATOR - fee),
+            )?;
+            for (key, liquidity, ..) in in_range {
+                *shares.entry(*key).or_default() += share(swap, segment_fee, *liquidity, active)?;
+            }
+        }
+        Ok((zero_for_one, shares.into_iter().map(|(k, s)| (k.clone(), s)).collect()))
+    }
+
+    /// Pool liquidity active just above a price, from the liquidity events applied so far
+    fn active_liquidity(&self, sqrt_price_x96: U256) -> Result<u128> {
+        let tick = get_tick_at_sqrt_price(sqrt_price_x96)?;
+        let active = self
+            .liquidity_net
+            .range(..=tick)
+            .fold(0i128, |active, (_, net)| active.saturating_add(*net));
+        u128::try_from(active).map_err(|_| anyhow!("Negative active liquidity at tick {}", tick))
+    }
+}
+
+/// A position's share of a fee paid to `active` liquidity
+fn share(swap: &Swap, fee_amount: U256, liquidity: u128, active: u128) -> Result<U256> {
+    if liquidity > active {
+        bail!("Swap {} has less active liquidity than a tracked position", swap.tx_hash);
+    }
+    mul_div(fee_amount, U256::from(liquidity), U256::from(active))
+}
+
+/// Chain order of a swap: timestamp, block number and log index
+fn swap_order(swap: &Swap) -> (DateTime<Utc>, Option<i64>, Option<i64>) {
+    (swap.timestamp, swap.block_number, Some(swap.log_index))
+}
+
+/// Chain order of a liquidity event: timestamp, block number and log index
+fn liquidity_event_order(event: &LiquidityEvent) -> (DateTime<Utc>, Option<i64>, Option<i64>) {
+    (event.timestamp, event.block_number, event.log_index())
+}
+
+/// Replay swaps and liquidity events in chain order, attributing fees to `positions`
+///
+/// Events are ordered by timestamp, block number and log index; `sqrt_price_x96` is the
+/// pool price before the first event. `liquidity_events` must cover every position in the
+/// pool (see [`FeeReplay`]).
+pub fn replay_fees(
+    sqrt_price_x96: Option<U256>,
+    swaps: &[Swap],
+    liquidity_events: &[LiquidityEvent],
+    positions: impl IntoIterator<Item = PositionKey>,
+) -> FeeReplay {
+    let mut replay = FeeReplay::new(sqrt_price_x96);
+    for key in positions {
+        replay.track(key);
+    }
+
+    let mut swaps = swaps.to_vec();
+    swaps.sort_by_key(swap_order);
+    let mut liquidity_events = liquidity_events.to_vec();
+    liquidity_events.sort_by_key(liquidity_event_order);
+
+    let pending = replay.apply_page(&swaps, &liquidity_events);
+    replay.apply_page(&[], pending);
+    replay
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    const OWNER: &str = "0xmanager";
+    const ONE: u128 = 1_000_000_000_000_000_000;
+
+    fn key(tick_lower: i32, tick_upper: i32) -> PositionKey {
+        PositionKey { owner: OWNER.to_string(), tick_lower, tick_upper, salt: "0x0".to_string() }
+    }
+
+    fn sqrt_price(tick: i32) -> U256 {
+        get_sqrt_price_at_tick(tick).unwrap()
+    }
+
+    /// A 0.3% swap moving the price from tick `from` to `to` through `active` liquidity, at
+    /// `block`/`log_index`
+    fn swap(block: i64, log_index: i64, (from, to): (i32, i32), active: u128) -> Swap {
+        let (before, after) = (sqrt_price(from), sqrt_price(to));
+        let zero_for_one = to < from;
+        let amount0 = get_amount0_delta(before, after, active, zero_for_one).unwrap();
+        let amount1 = get_amount1_delta(before, after, active, !zero_for_one).unwrap();
+        // The input is grossed up by the fee, the output is paid out
+        let gross = |net: U256| {
+            let amount = mul_div_rounding_up(net, U256::from(1_000_000u32), U256::from(997_000u32));
+            I256::try_from(amount.unwrap()).unwrap()
+        };
+        let paid = |amount: U256| -I256::try_from(amount).unwrap();
+        let (amount0, amount1) = if zero_for_one {
+            (gross(amount0), paid(amount1))
+        } else {
+            (paid(amount0), gross(amount1))
+        };
+
+        Swap {
+            id: 0,
+            tx_hash: format!("0xswap{}", log_index),
+            log_index,
+            block_number: Some(block),
+            pool_id: "0xpool".to_string(),
+            sender: None,
+            amount0,
+            amount1,
+            sqrt_price_x96: Some(after),
+            tick: Some(to),
+            liquidity: Some(U256::from(active)),
+            fee: Some(3000),
+            timestamp: DateTime::from_timestamp(1_700_000_000 + block * 12, 0).unwrap(),
+        }
+    }
+
+    /// The 0.3% fee `liquidity` charges for moving the price from tick `from` to `to`
+    fn path_fee((from, to): (i32, i32), liquidity: u128) -> U256 {
+        let (before, after) = (sqrt_price(from), sqrt_price(to));
+        let input = if to < from {
+            get_amount0_delta(before, after, liquidity, true).unwrap()
+        } else {
+            get_amount1_delta(before, after, liquidity, true).unwrap()
+        };
+        mul_div_rounding_up(input, U256::from(3000u16), U256::from(997_000u32)).unwrap()
+    }
+
+    fn liquidity_event(
+        block: i64,
+        log_index: i64,
+        key: &PositionKey,
+        delta: i128,
+    ) -> LiquidityEvent {
+        LiquidityEvent {
+            id: 0,
+            event_id: format!("0xtx-{}", log_index),
+            tx_hash: None,
+            block_number: Some(block),
+            pool_id: "0xpool".to_string(),
+            owner: key.owner.clone(),
+            tick_lower: key.tick_lower,
+            tick_upper: key.tick_upper,
+            salt: key.salt.clone(),
+            liquidity_delta: I256::try_from(delta).unwrap(),
+            timestamp: DateTime::from_timestamp(1_700_000_000 + block * 12, 0).unwrap(),
+            nft_id: None,
+        }
+    }
+
+    #[test]
+    fn test_in_range_swap_splits_fee_by_liquidity() {
+        let (position, other) = (key(-600, 600), key(-1200, 1200));
+        let events = [
+            liquidity_event(1, 0, &position, ONE as i128),
+            liquidity_event(1, 1, &other, 3 * ONE as i128),
+        ];
+        // The position holds a quarter of the active liquidity
+        let swaps = [swap(2, 0, (0, -10), 4 * ONE)];
+
+        let replay = replay_fees(Some(sqrt_price(0)), &swaps, &events, [position.clone()]);
+        let fees = replay.fees(&position).unwrap();
+
+        assert_eq!(fees.earned, (path_fee((0, -10), 4 * ONE) / U256::from(4u8), U256::ZERO));
+        assert_eq!(fees.uncollected, fees.earned);
+        assert_eq!(fees.liquidity, ONE);
+        assert_eq!(replay.skipped_swaps(), 0);
+    }
+
+    #[test]
+    fn test_out_of_range_swap_earns_nothing() {
+        let position = key(600, 1200);
+        let events = [liquidity_event(1, 0, &position, ONE as i128)];
+        // Out of range swaps need no liquidity or fee
+        let mut legacy = swap(3, 0, (10, 20), ONE);
+        (legacy.liquidity, legacy.fee) = (None, None);
+        let swaps = [swap(2, 0, (0, 10), ONE), legacy];
+
+        let replay = replay_fees(Some(sqrt_price(0)), &swaps, &events, [position.clone()]);
+        assert_eq!(replay.fees(&position).unwrap().earned, (U256::ZERO, U256::ZERO));
+        assert_eq!(replay.skipped_swaps(), 0);
+    }
+
+    #[test]
+    fn test_swap_crossing_one_boundary() {
+        let (narrow, wide) = (key(0, 600), key(-1200, 1200));
+        let events = [
+            liquidity_event(1, 0, &wide, ONE as i128),
+            liquidity_event(1, 1, &narrow, ONE as i128),
+        ];
+        // Moves up from below the narrow range into it
+        let swaps = [swap(2, 0, (-300, 300), ONE)];
+
+        let replay =
+            replay_fees(Some(sqrt_price(-300)), &swaps, &events, [narrow.clone(), wide.clone()]);
+        let below = path_fee((-300, 0), ONE);
+        let inside = path_fee((0, 300), 2 * ONE) / U256::from(2u8);
+
+        assert_eq!(replay.fees(&narrow).unwrap().earned, (U256::ZERO, inside));
+        assert_eq!(replay.fees(&wide).unwrap().earned, (U256::ZERO, below + inside));
+        assert_eq!(replay.skipped_swaps(), 0);
+    }
+
+    #[test]
+    fn test_swap_crossing_both_boundaries() {
+        let (narrow, wide) = (key(-60, 60), key(-1200, 1200));
+        let events = [
+            liquidity_event(1, 0, &wide, ONE as i128),
+            liquidity_event(1, 1, &narrow, ONE as i128),
+        ];
+        // A subgraph swap (no post-swap liquidity or fee) moving down through the whole
+        // narrow range
+        let mut crossing = swap(2, 0, (300, -300), ONE);
+        (crossing.liquidity, crossing.fee) = (None, None);
+        let swaps = [crossing];
+
+        let mut replay = FeeReplay::new(Some(sqrt_price(300))).with_pool_fee(3000);
+        replay.track(narrow.clone());
+        replay.track(wide.clone());
+        let pending = replay.apply_page(&swaps, &events);
+        replay.apply_page(&[], pending);
+
+        let inside = path_fee((60, -60), 2 * ONE) / U256::from(2u8);
+        let outside = path_fee((300, 60), ONE) + path_fee((-60, -300), ONE);
+        assert_eq!(replay.fees(&narrow).unwrap().earned, (inside, U256::ZERO));
+        assert_eq!(replay.fees(&wide).unwrap().earned, (outside + inside, U256::ZERO));
+        assert_eq!(replay.skipped_swaps(), 0);
+
+        // Without the pool fee to fall back to, the swap's fee is unknown
+        let replay = replay_fees(Some(sqrt_price(300)), &swaps, &events, [narrow.clone()]);
+        assert_eq!(replay.skipped_swaps(), 1);
+    }
+
+    #[test]
+    fn test_missing_pool_liquidity_is_skipped() {
+        let (position, other) = (key(-600, 600), key(-1200, 1200));
+        // Only the other position's withdrawal was indexed, not its deposit
+        let events = [
+            liquidity_event(1, 0, &position, ONE as i128),
+            liquidity_event(1, 1, &other, -(ONE as i128)),
+        ];
+        let swaps = [swap(2, 0, (0, -10), ONE)];
+
+        let replay = replay_fees(Some(sqrt_price(0)), &swaps, &events, [position.clone()]);
+        assert_eq!(replay.skipped_swaps(), 1);
+        assert_eq!(replay.fees(&position).unwrap().earned, (U256::ZERO, U256::ZERO));
+    }
+
+    #[test]
+    fn test_liquidity_changes_and_collects() {
+        let position = key(-600, 600);
+        let events = [
+            liquidity_event(2, 0, &position, ONE as i128),
+            // Zero-delta modification: fees are collected, liquidity is unchanged
+            liquidity_event(4, 0, &position, 0),
+            liquidity_event(6, 0, &position, -(ONE as i128)),
+        ];
+        let swaps = [
+            // Before the position exists
+            swap(1, 0, (0, -1), ONE),
+            swap(3, 0, (-1, -2), ONE),
+            swap(5, 0, (-2, -1), ONE),
+            // After it is withdrawn
+            swap(7, 0, (-1, -2), ONE),
+        ];
+
+        let replay = replay_fees(Some(sqrt_price(0)), &swaps, &events, [position.clone()]);
+        let fees = replay.fees(&position).unwrap();
+
+        assert_eq!(fees.earned, (path_fee((-1, -2), ONE), path_fee((-2, -1), ONE)));
+        assert_eq!(fees.uncollected, (U256::ZERO, U256::ZERO));
+        assert_eq!(fees.liquidity, 0);
+    }
+
+    #[test]
+    fn test_events_replay_in_chain_order() {
+        let position = key(-600, 600);
+        let events = [liquidity_event(1, 5, &position, ONE as i128)];
+        // Given out of order: only the swap logged after the deposit earns fees
+        let swaps = [swap(1, 7, (-1, -2), ONE), swap(1, 3, (0, -1), ONE)];
+
+        let replay = replay_fees(Some(sqrt_price(0)), &swaps, &events, [position.clone()]);
+        assert_eq!(replay.fees(&position).unwrap().earned.0, path_fee((-1, -2), ONE));
+        assert_eq!(replay.skipped_swaps(), 0);
+    }
+
+    #[test]
+    fn test_paged_replay_matches_full_replay() {
+        let position = key(-600, 600);
+        let events = [
+            liquidity_event(2, 0, &position, ONE as i128),
+            liquidity_event(4, 0, &position, 0),
+            liquidity_event(6, 0, &position, (ONE / 2) as i128),
+        ];
+        let swaps = [
+            swap(1, 0, (0, -1), ONE),
+            swap(3, 0, (-1, -2), ONE),
+            swap(5, 0, (-2, -1), ONE),
+            swap(7, 0, (-1, -2), ONE + ONE / 2),
+        ];
+
+        let mut paged = FeeReplay::new(Some(sqrt_price(0)));
+        paged.track(position.clone());
+        let mut pending = &events[..];
+        for page in swaps.chunks(3) {
+            pending = paged.apply_page(page, pending);
+        }
+        paged.apply_page(&[], pending);
+
+        let full = replay_fees(Some(sqrt_price(0)), &swaps, &events, [position.clone()]);
+        assert_eq!(paged.fees(&position), full.fees(&position));
+        assert_eq!(paged.fees(&position).unwrap().liquidity, ONE + ONE / 2);
+    }
+
+    #[test]
+    fn test_swaps_without_state_are_skipped() {
+        let position = key(-600, 600);
+        let events = [liquidity_event(1, 0, &position, ONE as i128)];
+        let mut legacy = swap(3, 0, (-1, -2), ONE);
+        legacy.fee = None;
+        let swaps = [swap(2, 0, (0, -1), ONE), legacy, swap(4, 0, (-2, -3), ONE)];
+
+        // Unknown starting price: the first swap only sets it
+        let replay = replay_fees(None, &swaps, &events, [position.clone()]);
+        assert_eq!(replay.skipped_swaps(), 2);
+        assert_eq!(replay.fees(&position).unwrap().earned.0, path_fee((-2, -3), ONE));
+
+        // Untracked positions are not reported
+        assert!(replay.fees(&key(0, 60)).is_none());
+
+        // Before any position has liquidity, nothing is missed
+        let replay = replay_fees(None, &swaps[..1], &[], [position.clone()]);
+        assert_eq!(replay.skipped_swaps(), 0);
+    }
+}
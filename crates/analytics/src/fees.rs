@@ -0,0 +1,59 @@
+use alloy::primitives::U256;
+use anyhow::Result;
+use stillwater_models::FeeGrowth;
+
+use crate::full_math::mul_div;
+
+/// `2^128`, i.e. 1.0 in Q128.128
+pub const Q128: U256 = U256::from_limbs([0, 0, 1, 0]);
+
+/// Raw token0 and token1 fees owed to `liquidity` since its last checkpoint
+///
+/// `(feeGrowthInside - feeGrowthInsideLast) * liquidity / 2^128`, rounded down, as
+/// `Position.update` credits them.
+pub fn get_fees_owed(
+    liquidity: u128,
+    fee_growth_inside: FeeGrowth,
+    fee_growth_inside_last: FeeGrowth,
+) -> Result<(U256, U256)> {
+    let owed =
+        |inside: U256, last: U256| mul_div(inside.wrapping_sub(last), U256::from(liquidity), Q128);
+
+    Ok((
+        owed(fee_growth_inside.fee_growth0_x128, fee_growth_inside_last.fee_growth0_x128)?,
+        owed(fee_growth_inside.fee_growth1_x128, fee_growth_inside_last.fee_growth1_x128)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn growth(fee_growth0: u64, fee_growth1: u64) -> FeeGrowth {
+        FeeGrowth {
+            fee_growth0_x128: U256::from(fee_growth0) * Q128,
+            fee_growth1_x128: U256::from(fee_growth1) * Q128,
+        }
+    }
+
+    #[test]
+    fn test_fees_owed() {
+        let owed = get_fees_owed(5, growth(12, 4), growth(2, 1)).unwrap();
+        assert_eq!(owed, (U256::from(50u8), U256::from(15u8)));
+
+        // Fractional fees round down
+        let half = FeeGrowth { fee_growth0_x128: Q128 / U256::from(2u8), ..growth(0, 0) };
+        assert_eq!(get_fees_owed(3, half, growth(0, 0)).unwrap().0, U256::from(1u8));
+    }
+
+    #[test]
+    fn test_fees_owed_across_wraparound() {
+        // The checkpoint was taken just below 2^256; growth wrapped past zero since
+        let last = FeeGrowth {
+            fee_growth0_x128: U256::ZERO.wrapping_sub(Q128),
+            fee_growth1_x128: U256::ZERO,
+        };
+        let owed = get_fees_owed(7, growth(2, 0), last).unwrap();
+        assert_eq!(owed, (U256::from(21u8), U256::ZERO));
+    }
+}
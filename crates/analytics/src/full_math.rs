@@ -0,0 +1,78 @@
+use alloy::primitives::{U256, U512};
+use anyhow::{anyhow, bail, Result};
+
+/// `floor(a * b / denominator)` with a 512-bit intermediate product (`FullMath.mulDiv`)
+///
+/// Fails if `denominator` is zero or the result does not fit in 256 bits.
+pub fn mul_div(a: U256, b: U256, denominator: U256) -> Result<U256> {
+    if denominator.is_zero() {
+        bail!("mulDiv by zero");
+    }
+
+    let quotient = U512::from(a) * U512::from(b) / U512::from(denominator);
+    U256::checked_from_limbs_slice(quotient.as_limbs()).ok_or_else(|| anyhow!("mulDiv overflow"))
+}
+
+/// `ceil(a * b / denominator)` (`FullMath.mulDivRoundingUp`)
+pub fn mul_div_rounding_up(a: U256, b: U256, denominator: U256) -> Result<U256> {
+    let result = mul_div(a, b, denominator)?;
+    if a.mul_mod(b, denominator).is_zero() {
+        return Ok(result);
+    }
+    result.checked_add(U256::from(1u8)).ok_or_else(|| anyhow!("mulDivRoundingUp overflow"))
+}
+
+/// `ceil(x / y)`, with `y` non-zero (`UnsafeMath.divRoundingUp`)
+pub fn div_rounding_up(x: U256, y: U256) -> U256 {
+    let quotient = x / y;
+    if (x % y).is_zero() {
+        quotient
+    } else {
+        quotient + U256::from(1u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_div() {
+        let q128 = U256::from(1u8) << 128;
+
+        // Phantom overflow: the product needs 512 bits but the result fits
+        assert_eq!(
+            mul_div(q128, U256::from(35u8) * q128, U256::from(8u8) * q128).unwrap(),
+            U256::from(4375u16) * q128 / U256::from(1000u16)
+        );
+        assert_eq!(mul_div(U256::MAX, U256::MAX, U256::MAX).unwrap(), U256::MAX);
+
+        assert!(mul_div(q128, q128, U256::ZERO).is_err());
+        assert!(mul_div(U256::MAX, U256::MAX, U256::from(1u8)).is_err());
+    }
+
+    #[test]
+    fn test_mul_div_rounding_up() {
+        let q128 = U256::from(1u8) << 128;
+
+        assert_eq!(
+            mul_div_rounding_up(q128, U256::from(50u8), U256::from(100u8)).unwrap(),
+            q128 / U256::from(2u8)
+        );
+        assert_eq!(
+            mul_div_rounding_up(U256::from(7u8), U256::from(3u8), U256::from(2u8)).unwrap(),
+            U256::from(11u8)
+        );
+
+        // The rounded-up result would not fit
+        let max_minus_one = U256::MAX - U256::from(1u8);
+        assert!(mul_div_rounding_up(U256::MAX, max_minus_one, U256::MAX - U256::from(2u8)).is_err());
+    }
+
+    #[test]
+    fn test_div_rounding_up() {
+        assert_eq!(div_rounding_up(U256::from(6u8), U256::from(3u8)), U256::from(2u8));
+        assert_eq!(div_rounding_up(U256::from(7u8), U256::from(3u8)), U256::from(3u8));
+        assert_eq!(div_rounding_up(U256::ZERO, U256::from(3u8)), U256::ZERO);
+    }
+}
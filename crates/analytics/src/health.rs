@@ -1,5 +1,5 @@
 use rust_decimal::Decimal;
-use stillwater_models::{HealthStatus, Position, PositionPnL};
+use stillwater_models::{HealthStatus, Pool, Position, PositionPnL};
 
 use crate::utils::{distance_to_range_edge, is_in_range};
 
@@ -9,6 +9,8 @@ use crate::utils::{distance_to_range_edge, is_in_range};
 /// - Healthy: in range + positive P&L
 /// - Warning: within 10% of range edge
 /// - Critical: out of range OR negative P&L
+///
+/// An unknown net P&L (fees unavailable) only affects the range checks.
 pub fn get_position_health(
     position: &Position,
     current_tick: i32,
@@ -20,7 +22,7 @@ pub fn get_position_health(
     }
 
     // Critical if negative P&L
-    if pnl.net_pnl < Decimal::ZERO {
+    if pnl.net_pnl.is_some_and(|net_pnl| net_pnl < Decimal::ZERO) {
         return HealthStatus::Critical;
     }
 
@@ -46,37 +48,72 @@ pub fn get_health_details(
     let in_range = is_in_range(current_tick, position.tick_lower, position.tick_upper);
     let distance = distance_to_range_edge(current_tick, position.tick_lower, position.tick_upper);
 
+    let net_pnl = pnl.net_pnl.map_or("unknown".to_string(), |net_pnl| net_pnl.to_string());
+
     format!(
         "Status: {:?}, In Range: {}, Distance to Edge: {}, Net P&L: {}",
-        status, in_range, distance, pnl.net_pnl
+        status, in_range, distance, net_pnl
     )
 }
 
+/// Warnings for LPs about what a pool's hooks can do with their funds
+///
+/// Return-delta hooks run custom accounting: they can skim swap output or fees before
+/// they reach LPs, or take a share of liquidity added or removed.
+pub fn get_hook_warnings(pool: &Pool) -> Vec<String> {
+    let Some(permissions) = pool.hook_permissions() else {
+        return Vec::new();
+    };
+
+    let mut warnings = Vec::new();
+    if permissions.returns_swap_delta() {
+        warnings.push(
+            "Hook can modify swap amounts (swap return delta); it may skim fees or output"
+                .to_string(),
+        );
+    }
+    if permissions.returns_liquidity_delta() {
+        warnings.push(
+            "Hook can modify liquidity amounts (liquidity return delta); it may take a share \
+             of deposits or withdrawals"
+                .to_string(),
+        );
+    }
+    warnings
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use alloy::primitives::U256;
     use chrono::Utc;
+    use stillwater_models::{AFTER_REMOVE_LIQUIDITY_RETURNS_DELTA_FLAG, BEFORE_SWAP_FLAG};
 
     fn create_test_position(tick_lower: i32, tick_upper: i32) -> Position {
         Position {
             id: 1,
             nft_id: "1".to_string(),
             owner: "0xtest".to_string(),
+            manager: "0xtest".to_string(),
             pool_id: "0xpool".to_string(),
             tick_lower,
             tick_upper,
+            salt: "0x0".to_string(),
             liquidity: U256::from(1000000u64),
+            closed: false,
             created_at: Utc::now(),
         }
     }
 
     fn create_test_pnl(net_pnl: i64) -> PositionPnL {
         PositionPnL {
-            fees_earned: Decimal::from(100),
+            fees_earned: Some(Decimal::from(100)),
+            fees0: Some(Decimal::from(50)),
+            fees1: Some(Decimal::from(50)),
             impermanent_loss: Decimal::from(20),
+            impermanent_loss_percent: Decimal::from(2),
             gas_spent: Decimal::from(10),
-            net_pnl: Decimal::from(net_pnl),
+            net_pnl: Some(Decimal::from(net_pnl)),
         }
     }
 
@@ -120,6 +157,44 @@ mod tests {
         assert_eq!(health, HealthStatus::Critical);
     }
 
+    #[test]
+    fn test_unknown_pnl_uses_range_only() {
+        let position = create_test_position(-1000, 1000);
+        let pnl = PositionPnL { net_pnl: None, ..create_test_pnl(0) };
+
+        assert_eq!(get_position_health(&position, 0, &pnl), HealthStatus::Healthy);
+        assert!(get_health_details(&position, 0, &pnl).contains("Net P&L: unknown"));
+    }
+
+    fn create_test_pool(hook_flags: Option<i32>) -> Pool {
+        Pool {
+            pool_id: "0xpool".to_string(),
+            token0: "0xtoken0".to_string(),
+            token1: "0xtoken1".to_string(),
+            fee_tier: 3000,
+            tick_spacing: 60,
+            hooks: None,
+            hook_flags,
+            hook_return_delta: false,
+            dynamic_fee: false,
+            created_block: None,
+            initial_sqrt_price_x96: None,
+            initial_tick: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_hook_warnings() {
+        assert!(get_hook_warnings(&create_test_pool(None)).is_empty());
+        assert!(get_hook_warnings(&create_test_pool(Some(BEFORE_SWAP_FLAG.into()))).is_empty());
+
+        let flags = AFTER_REMOVE_LIQUIDITY_RETURNS_DELTA_FLAG.into();
+        let warnings = get_hook_warnings(&create_test_pool(Some(flags)));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("liquidity return delta"));
+    }
+
     #[test]
     fn test_get_health_details() {
         let position = create_test_position(-1000, 1000);
@@ -1,6 +1,12 @@
 pub mod pnl;
 pub mod health;
 pub mod utils;
+pub mod full_math;
+pub mod tick_math;
+pub mod sqrt_price_math;
+pub mod liquidity_amounts;
+pub mod fees;
+pub mod fee_replay;
 
 // Re-export main functions
 pub use pnl::{
@@ -8,11 +14,19 @@ pub use pnl::{
     calculate_impermanent_loss,
     calculate_net_pnl,
     calculate_position_pnl,
+    FeesEarned,
+    ImpermanentLoss,
+    LiquidityChange,
 };
 
+pub use fees::get_fees_owed;
+
+pub use fee_replay::{replay_fees, FeeReplay, PositionKey, ReplayedFees};
+
 pub use health::{
     get_position_health,
     get_health_details,
+    get_hook_warnings,
 };
 
 pub use utils::{
@@ -21,4 +35,33 @@ pub use utils::{
     tick_to_price,
     price_to_tick,
     range_width_percent,
+    adjust_price_for_decimals,
+    sqrt_price_x96_to_price,
+    price_to_sqrt_price_x96,
+    fraction_to_decimal,
+    to_token_amount,
+};
+
+pub use full_math::{mul_div, mul_div_rounding_up};
+
+pub use tick_math::{
+    get_sqrt_price_at_tick,
+    get_tick_at_sqrt_price,
+    MIN_TICK,
+    MAX_TICK,
+    MIN_SQRT_PRICE,
+    MAX_SQRT_PRICE,
+};
+
+pub use sqrt_price_math::{
+    get_amount0_delta,
+    get_amount1_delta,
+    get_next_sqrt_price_from_input,
+    get_next_sqrt_price_from_output,
+};
+
+pub use liquidity_amounts::{
+    get_liquidity_for_amounts,
+    get_amounts_for_liquidity,
+    get_position_amounts,
 };
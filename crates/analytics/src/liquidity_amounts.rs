@@ -0,0 +1,202 @@
+use alloy::primitives::U256;
+use anyhow::{anyhow, Context, Result};
+use stillwater_models::Position;
+
+use crate::full_math::mul_div;
+use crate::sqrt_price_math::{get_amount0_delta, get_amount1_delta, Q96};
+use crate::tick_math::get_sqrt_price_at_tick;
+
+/// Liquidity received for `amount0` of token0 across a price range, rounded down
+///
+/// `LiquidityAmounts.getLiquidityForAmount0`
+pub fn get_liquidity_for_amount0(
+    sqrt_price_a_x96: U256,
+    sqrt_price_b_x96: U256,
+    amount0: U256,
+) -> Result<u128> {
+    let (lower, upper) = sorted(sqrt_price_a_x96, sqrt_price_b_x96);
+    let intermediate = mul_div(lower, upper, Q96)?;
+    to_u128(mul_div(amount0, intermediate, upper - lower)?)
+}
+
+/// Liquidity received for `amount1` of token1 across a price range, rounded down
+///
+/// `LiquidityAmounts.getLiquidityForAmount1`
+pub fn get_liquidity_for_amount1(
+    sqrt_price_a_x96: U256,
+    sqrt_price_b_x96: U256,
+    amount1: U256,
+) -> Result<u128> {
+    let (lower, upper) = sorted(sqrt_price_a_x96, sqrt_price_b_x96);
+    to_u128(mul_div(amount1, Q96, upper - lower)?)
+}
+
+/// Maximum liquidity mintable from `amount0` and `amount1` at the current price
+///
+/// `LiquidityAmounts.getLiquidityForAmounts`: below the range only token0 counts, above
+/// it only token1, and in range the scarcer token limits the liquidity.
+pub fn get_liquidity_for_amounts(
+    sqrt_price_x96: U256,
+    sqrt_price_a_x96: U256,
+    sqrt_price_b_x96: U256,
+    amount0: U256,
+    amount1: U256,
+) -> Result<u128> {
+    let (lower, upper) = sorted(sqrt_price_a_x96, sqrt_price_b_x96);
+
+    if sqrt_price_x96 <= lower {
+        get_liquidity_for_amount0(lower, upper, amount0)
+    } else if sqrt_price_x96 < upper {
+        let liquidity0 = get_liquidity_for_amount0(sqrt_price_x96, upper, amount0)?;
+        let liquidity1 = get_liquidity_for_amount1(lower, sqrt_price_x96, amount1)?;
+        Ok(liquidity0.min(liquidity1))
+    } else {
+        get_liquidity_for_amount1(lower, upper, amount1)
+    }
+}
+
+/// Token0 held by `liquidity` across a price range, rounded down
+pub fn get_amount0_for_liquidity(
+    sqrt_price_a_x96: U256,
+    sqrt_price_b_x96: U256,
+    liquidity: u128,
+) -> Result<U256> {
+    get_amount0_delta(sqrt_price_a_x96, sqrt_price_b_x96, liquidity, false)
+}
+
+/// Token1 held by `liquidity` across a price range, rounded down
+pub fn get_amount1_for_liquidity(
+    sqrt_price_a_x96: U256,
+    sqrt_price_b_x96: U256,
+    liquidity: u128,
+) -> Result<U256> {
+    get_amount1_delta(sqrt_price_a_x96, sqrt_price_b_x96, liquidity, false)
+}
+
+/// Token0 and token1 held by `liquidity` at the current price, rounded down
+///
+/// This is what burning the liquidity would return, before fees.
+pub fn get_amounts_for_liquidity(
+    sqrt_price_x96: U256,
+    sqrt_price_a_x96: U256,
+    sqrt_price_b_x96: U256,
+    liquidity: u128,
+) -> Result<(U256, U256)> {
+    let (lower, upper) = sorted(sqrt_price_a_x96, sqrt_price_b_x96);
+
+    if sqrt_price_x96 <= lower {
+        Ok((get_amount0_for_liquidity(lower, upper, liquidity)?, U256::ZERO))
+    } else if sqrt_price_x96 < upper {
+        Ok((
+            get_amount0_for_liquidity(sqrt_price_x96, upper, liquidity)?,
+            get_amount1_for_liquidity(lower, sqrt_price_x96, liquidity)?,
+        ))
+    } else {
+        Ok((U256::ZERO, get_amount1_for_liquidity(lower, upper, liquidity)?))
+    }
+}
+
+/// Raw token0 and token1 amounts a position holds at the given pool price
+pub fn get_position_amounts(position: &Position, sqrt_price_x96: U256) -> Result<(U256, U256)> {
+    let liquidity = to_u128(position.liquidity).context("Position liquidity exceeds uint128")?;
+    let sqrt_price_lower = get_sqrt_price_at_tick(position.tick_lower)?;
+    let sqrt_price_upper = get_sqrt_price_at_tick(position.tick_upper)?;
+
+    get_amounts_for_liquidity(sqrt_price_x96, sqrt_price_lower, sqrt_price_upper, liquidity)
+}
+
+fn to_u128(value: U256) -> Result<u128> {
+    u128::try_from(value).map_err(|_| anyhow!("Liquidity overflows uint128"))
+}
+
+fn sorted(a: U256, b: U256) -> (U256, U256) {
+    if a > b {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::uint;
+    use chrono::Utc;
+
+    // encodePriceSqrt(reserve1, reserve0) vectors from the LiquidityAmounts test suite
+    const PRICE_1_1: U256 = Q96;
+    const PRICE_100_110: U256 = uint!(75541088972021052632782079082_U256);
+    const PRICE_110_100: U256 = uint!(83095197869223157896060286990_U256);
+    const PRICE_99_110: U256 = uint!(75162434512514379355924140470_U256);
+    const PRICE_111_100: U256 = uint!(83472048772503575395058907992_U256);
+
+    fn liquidity_for(sqrt_price: U256) -> u128 {
+        let (amount0, amount1) = (U256::from(100u8), U256::from(200u8));
+        get_liquidity_for_amounts(sqrt_price, PRICE_100_110, PRICE_110_100, amount0, amount1)
+            .unwrap()
+    }
+
+    fn amounts_for(sqrt_price: U256, liquidity: u128) -> (U256, U256) {
+        get_amounts_for_liquidity(sqrt_price, PRICE_100_110, PRICE_110_100, liquidity).unwrap()
+    }
+
+    #[test]
+    fn test_liquidity_for_amounts() {
+        assert_eq!(liquidity_for(PRICE_1_1), 2148);
+        assert_eq!(liquidity_for(PRICE_99_110), 1048);
+        assert_eq!(liquidity_for(PRICE_111_100), 2097);
+        assert_eq!(liquidity_for(PRICE_100_110), 1048);
+        assert_eq!(liquidity_for(PRICE_110_100), 2097);
+    }
+
+    #[test]
+    fn test_amounts_for_liquidity() {
+        let (ninety_nine, hundred_ninety_nine) = (U256::from(99u8), U256::from(199u8));
+
+        assert_eq!(amounts_for(PRICE_1_1, 2148), (ninety_nine, ninety_nine));
+        assert_eq!(amounts_for(PRICE_99_110, 1048), (ninety_nine, U256::ZERO));
+        assert_eq!(amounts_for(PRICE_111_100, 2097), (U256::ZERO, hundred_ninety_nine));
+        assert_eq!(amounts_for(PRICE_100_110, 1048), (ninety_nine, U256::ZERO));
+        assert_eq!(amounts_for(PRICE_110_100, 2097), (U256::ZERO, hundred_ninety_nine));
+
+        // Bounds may be given in either order
+        let swapped =
+            get_amounts_for_liquidity(PRICE_1_1, PRICE_110_100, PRICE_100_110, 2148).unwrap();
+        assert_eq!(swapped, (ninety_nine, ninety_nine));
+    }
+
+    #[test]
+    fn test_liquidity_overflow() {
+        let result = get_liquidity_for_amount1(PRICE_1_1, PRICE_1_1 + U256::from(1u8), U256::MAX);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_position_amounts() {
+        let position = Position {
+            id: 1,
+            nft_id: "1".to_string(),
+            owner: "0xtest".to_string(),
+            manager: "0xtest".to_string(),
+            pool_id: "0xpool".to_string(),
+            tick_lower: -60,
+            tick_upper: 60,
+            salt: "0x0".to_string(),
+            liquidity: U256::from(1_000_000_000_000_000_000u64),
+            closed: false,
+            created_at: Utc::now(),
+        };
+
+        // A symmetric range at price 1 holds equal amounts of both tokens
+        let (amount0, amount1) = get_position_amounts(&position, PRICE_1_1).unwrap();
+        assert!(amount0 > U256::ZERO);
+        assert!(amount0.abs_diff(amount1) <= U256::from(1u8));
+
+        // Below the range everything is token0
+        let below = get_sqrt_price_at_tick(-61).unwrap();
+        assert_eq!(get_position_amounts(&position, below).unwrap().1, U256::ZERO);
+
+        let overflowing = Position { liquidity: U256::MAX, ..position };
+        assert!(get_position_amounts(&overflowing, PRICE_1_1).is_err());
+    }
+}
@@ -1,117 +1,131 @@
+use alloy::primitives::{I256, U256, U512};
+use anyhow::{anyhow, Context, Result};
 use rust_decimal::Decimal;
-use rust_decimal::prelude::*;
-use stillwater_models::{Position, PositionPnL, Swap};
-
-use crate::utils::tick_to_price;
-
-/// Calculate fees earned from swaps
-///
-/// For a concentrated liquidity position, fees are earned when:
-/// 1. The swap occurs while the position is in range
-/// 2. The position has active liquidity
-///
-/// Simplified calculation: assumes position was always in range for swaps provided
-pub fn calculate_fees_earned(_position: &Position, swaps: &[Swap]) -> Decimal {
-    if swaps.is_empty() {
-        return Decimal::ZERO;
-    }
-
-    // Simplified fee calculation
-    // In reality, would need:
-    // - Total pool liquidity at time of each swap
-    // - Position's share of liquidity
-    // - Fee tier for the pool
-    //
-    // For MVP, estimate based on swap volumes and assume 0.3% fee tier
-    let fee_rate = Decimal::from_str("0.003").unwrap(); // 0.3%
-
-    let total_volume: Decimal = swaps
-        .iter()
-        .map(|swap| {
-            // Use absolute values and convert to decimal
-            // This is a rough approximation
-            let amt0 = swap.amount0.abs().to_string();
-            let amt1 = swap.amount1.abs().to_string();
-
-            Decimal::from_str(&amt0).unwrap_or(Decimal::ZERO)
-                + Decimal::from_str(&amt1).unwrap_or(Decimal::ZERO)
-        })
-        .sum();
-
-    // Estimate fees as a fraction of total volume
-    // In production, would calculate exact share based on liquidity
-    let estimated_position_share = Decimal::from_str("0.01").unwrap(); // 1% of pool
-
-    total_volume * fee_rate * estimated_position_share
+use stillwater_models::{Position, PositionPnL, Token};
+
+use crate::full_math::mul_div;
+use crate::liquidity_amounts::{get_amounts_for_liquidity, get_position_amounts};
+use crate::tick_math::get_sqrt_price_at_tick;
+use crate::utils::{fraction_to_decimal, to_token_amount};
+
+/// Uncollected fees of a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeesEarned {
+    /// Token0 fees in token units
+    pub amount0: Decimal,
+    /// Token1 fees in token units
+    pub amount1: Decimal,
+    /// Both amounts valued at the current price, in token1 units
+    pub value: Decimal,
 }
 
-/// Calculate impermanent loss for concentrated liquidity position
-///
-/// IL for concentrated liquidity is different from full-range (v2) positions:
-/// - Only incur IL when price moves within the range
-/// - IL can be higher or lower depending on range width
-///
-/// Simplified formula:
-/// IL = (value_if_held - current_value) / value_if_held
-pub fn calculate_impermanent_loss(
-    position: &Position,
-    initial_price: Decimal,
-    current_price: Decimal,
-) -> Decimal {
-    if initial_price.is_zero() || current_price.is_zero() {
-        return Decimal::ZERO;
-    }
-
-    // For positions with extreme tick ranges (like full-range positions),
-    // use a simplified calculation to avoid overflow
-    let tick_range = (position.tick_upper - position.tick_lower).abs();
+/// Convert raw fees owed (see [`crate::fees::get_fees_owed`]) into token units and value
+/// them in token1 at the current price
+pub fn calculate_fees_earned(
+    fees_owed: (U256, U256),
+    sqrt_price_x96: U256,
+    token0: &Token,
+    token1: &Token,
+) -> Result<FeesEarned> {
+    let value = value_x128(fees_owed, price_x128(sqrt_price_x96)?);
+
+    Ok(FeesEarned {
+        amount0: to_token_amount(&fees_owed.0.to_string(), token0.decimals),
+        amount1: to_token_amount(&fees_owed.1.to_string(), token1.decimals),
+        value: fraction_to_decimal(value, one_token_x128(token1)),
+    })
+}
 
-    if tick_range > 1_000_000 {
-        // This is likely a full-range position (e.g., ±887220)
-        // Use simplified IL calculation similar to Uniswap v2
+/// Token1 base units per token0 base unit as a Q128.128 number
+fn price_x128(sqrt_price_x96: U256) -> Result<U256> {
+    mul_div(sqrt_price_x96, sqrt_price_x96, U256::from(1u8) << 64)
+}
 
-        // If price hasn't moved, no IL
-        if (current_price - initial_price).abs() < Decimal::from_str("0.0001").unwrap() {
-            return Decimal::ZERO;
-        }
+/// Value of raw token amounts in token1 base units, as a Q128 number
+fn value_x128((amount0, amount1): (U256, U256), price_x128: U256) -> U512 {
+    U512::from(amount0) * U512::from(price_x128) + (U512::from(amount1) << 128usize)
+}
 
-        // For full-range positions, IL ≈ 2*sqrt(price_ratio) - price_ratio - 1
-        // Simplified approximation: IL increases with price movement
-        let price_change_pct = ((current_price - initial_price) / initial_price).abs();
+/// One whole token1 in base units, as a Q128 number
+fn one_token_x128(token: &Token) -> U512 {
+    U512::from(10u64).pow(U512::from(token.decimals)) << 128usize
+}
 
-        // Cap IL at reasonable value
-        let il = price_change_pct * Decimal::from_str("0.2").unwrap(); // Max ~20% for moderate price changes
-        return il.min(Decimal::from_str("0.5").unwrap()); // Cap at 50%
-    }
+/// A deposit (positive delta) or withdrawal of a position's liquidity, and the pool's Q64.96
+/// sqrt price when it happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiquidityChange {
+    pub liquidity_delta: I256,
+    pub sqrt_price_x96: U256,
+}
 
-    // For normal range positions, use tick-based calculation
-    let price_lower = tick_to_price(position.tick_lower);
-    let price_upper = tick_to_price(position.tick_upper);
+/// Impermanent loss of a position against holding what was deposited into it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImpermanentLoss {
+    /// HODL value minus position value at the current price, in token1 units
+    /// (positive is a loss)
+    pub value: Decimal,
+    /// `value` as a percentage of the HODL value
+    pub percent: Decimal,
+}
 
-    // If price hasn't moved, no IL
-    if (current_price - initial_price).abs() < Decimal::from_str("0.0001").unwrap() {
-        return Decimal::ZERO;
+/// Calculate impermanent loss for a concentrated liquidity position
+///
+/// Each of the position's `changes` is converted to token amounts at the price it happened
+/// at. Holding the deposited amounts is compared with what the position holds now plus what
+/// was withdrawn from it, all valued at the current price in token1, so a closed position
+/// keeps the loss it realized.
+pub fn calculate_impermanent_loss(
+    position: &Position,
+    changes: &[LiquidityChange],
+    current_sqrt_price_x96: U256,
+    token1: &Token,
+) -> Result<ImpermanentLoss> {
+    let sqrt_price_lower = get_sqrt_price_at_tick(position.tick_lower)?;
+    let sqrt_price_upper = get_sqrt_price_at_tick(position.tick_upper)?;
+
+    let mut deposited = (U256::ZERO, U256::ZERO);
+    let mut withdrawn = (U256::ZERO, U256::ZERO);
+    for change in changes {
+        let liquidity = u128::try_from(change.liquidity_delta.unsigned_abs())
+            .map_err(|_| anyhow!("Liquidity change exceeds uint128"))?;
+        let (amount0, amount1) = get_amounts_for_liquidity(
+            change.sqrt_price_x96,
+            sqrt_price_lower,
+            sqrt_price_upper,
+            liquidity,
+        )
+        .context("Failed to compute amounts of a liquidity change")?;
+
+        let total = if change.liquidity_delta.is_negative() {
+            &mut withdrawn
+        } else {
+            &mut deposited
+        };
+        total.0 += amount0;
+        total.1 += amount1;
     }
-
-    // Simplified IL calculation for concentrated liquidity
-    // Full formula involves complex integral calculations
-    //
-    // Approximation: IL increases with price movement
-    let price_change_pct = ((current_price - initial_price) / initial_price).abs();
-
-    // Range width factor: wider range = less IL (approaching v2 behavior)
-    // Add safety check to avoid division by zero
-    if price_lower.is_zero() {
-        return Decimal::ZERO;
+    let current = get_position_amounts(position, current_sqrt_price_x96)
+        .context("Failed to compute current amounts")?;
+
+    // All valued at the current price
+    let price_x128 = price_x128(current_sqrt_price_x96)?;
+    let hodl = value_x128(deposited, price_x128);
+    let held = value_x128(current, price_x128) + value_x128(withdrawn, price_x128);
+
+    let (loss, gained) = if hodl >= held { (hodl - held, false) } else { (held - hodl, true) };
+    let mut value = fraction_to_decimal(loss, one_token_x128(token1));
+    let mut percent = if hodl.is_zero() {
+        Decimal::ZERO
+    } else {
+        fraction_to_decimal(loss * U512::from(100u8), hodl)
+    };
+
+    if gained {
+        value.set_sign_negative(true);
+        percent.set_sign_negative(true);
     }
-
-    let range_width = (price_upper - price_lower) / price_lower;
-
-    // IL increases with price movement, decreases with range width
-    let il_factor = price_change_pct / (Decimal::ONE + range_width);
-
-    // Simplified IL formula (in production, use exact Uniswap v3 math)
-    il_factor * Decimal::from_str("0.5").unwrap()
+    Ok(ImpermanentLoss { value, percent })
 }
 
 /// Calculate net P&L
@@ -120,75 +134,232 @@ pub fn calculate_net_pnl(fees: Decimal, il: Decimal, gas: Decimal) -> Decimal {
 }
 
 /// Calculate complete position P&L
+///
+/// `fees_owed` are the position's raw uncollected fees, `None` when they are unknown (fees
+/// and net P&L are then left out); `liquidity_changes` are the position's deposits and
+/// withdrawals (see [`calculate_impermanent_loss`]) and the price is the pool's current
+/// Q64.96 sqrt price. Fees, IL and net P&L are in token1 units.
 pub fn calculate_position_pnl(
     position: &Position,
-    swaps: &[Swap],
-    initial_price: Decimal,
-    current_price: Decimal,
+    fees_owed: Option<(U256, U256)>,
+    tokens: (&Token, &Token),
+    liquidity_changes: &[LiquidityChange],
+    current_sqrt_price_x96: U256,
     gas_spent: Decimal,
-) -> PositionPnL {
-    let fees_earned = calculate_fees_earned(position, swaps);
-    let impermanent_loss = calculate_impermanent_loss(position, initial_price, current_price);
-    let net_pnl = calculate_net_pnl(fees_earned, impermanent_loss, gas_spent);
-
-    PositionPnL {
-        fees_earned,
-        impermanent_loss,
+) -> Result<PositionPnL> {
+    let fees = fees_owed
+        .map(|owed| calculate_fees_earned(owed, current_sqrt_price_x96, tokens.0, tokens.1))
+        .transpose()?;
+    let impermanent_loss = calculate_impermanent_loss(
+        position,
+        liquidity_changes,
+        current_sqrt_price_x96,
+        tokens.1,
+    )?;
+    let net_pnl =
+        fees.as_ref().map(|fees| calculate_net_pnl(fees.value, impermanent_loss.value, gas_spent));
+
+    Ok(PositionPnL {
+        fees_earned: fees.as_ref().map(|fees| fees.value),
+        fees0: fees.as_ref().map(|fees| fees.amount0),
+        fees1: fees.as_ref().map(|fees| fees.amount1),
+        impermanent_loss: impermanent_loss.value,
+        impermanent_loss_percent: impermanent_loss.percent,
         gas_spent,
         net_pnl,
-    }
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloy::primitives::{I256, U256};
+    use crate::sqrt_price_math::Q96;
+    use crate::tick_math::get_sqrt_price_at_tick;
+    use alloy::primitives::uint;
     use chrono::Utc;
 
+    /// 1.1 in Q64.96, i.e. a 21% price increase from Q96
+    const PRICE_121_100: U256 = uint!(87150978765690771352898345369_U256);
+
     fn create_test_position() -> Position {
         Position {
             id: 1,
             nft_id: "1".to_string(),
             owner: "0xtest".to_string(),
+            manager: "0xtest".to_string(),
             pool_id: "0xpool".to_string(),
             tick_lower: -1000,
             tick_upper: 1000,
+            salt: "0x0".to_string(),
             liquidity: U256::from(1000000u64),
+            closed: false,
             created_at: Utc::now(),
         }
     }
 
-    fn create_test_swap(amount0: i64, amount1: i64) -> Swap {
-        Swap {
-            id: 1,
-            tx_hash: "0xtx".to_string(),
-            pool_id: "0xpool".to_string(),
-            amount0: I256::try_from(amount0).unwrap(),
-            amount1: I256::try_from(amount1).unwrap(),
-            timestamp: Utc::now(),
+    /// The position's whole liquidity, deposited at `sqrt_price_x96`
+    fn opened_at(position: &Position, sqrt_price_x96: U256) -> [LiquidityChange; 1] {
+        let liquidity_delta = I256::try_from(position.liquidity).unwrap();
+        [LiquidityChange { liquidity_delta, sqrt_price_x96 }]
+    }
+
+    fn create_test_token(decimals: u8) -> Token {
+        Token {
+            address: "0xtoken".to_string(),
+            symbol: "TKN".to_string(),
+            name: "Token".to_string(),
+            decimals,
         }
     }
 
     #[test]
     fn test_calculate_fees_earned() {
+        // 3 USDC-like (6 decimals) and 0.000001 ETH-like (18 decimals) at a raw price of 1
+        let fees_owed = (U256::from(3_000_000u64), U256::from(1_000_000_000_000u64));
+        let (token0, token1) = (create_test_token(6), create_test_token(18));
+
+        let fees = calculate_fees_earned(fees_owed, Q96, &token0, &token1).unwrap();
+        assert_eq!(fees.amount0, Decimal::from(3));
+        assert_eq!(fees.amount1, Decimal::new(1, 6));
+        // 3e6 base units of token0 are worth 3e6 base units of token1
+        assert_eq!(fees.value, Decimal::new(1_000_003, 12));
+    }
+
+    #[test]
+    fn test_calculate_fees_earned_at_price() {
+        // At a raw price of 4, each token0 base unit is worth 4 of token1
+        let fees_owed = (U256::from(10u8), U256::from(2u8));
+        let token = create_test_token(0);
+
+        let fees = calculate_fees_earned(fees_owed, Q96 * U256::from(2u8), &token, &token);
+        assert_eq!(fees.unwrap().value, Decimal::from(42));
+    }
+
+    #[test]
+    fn test_impermanent_loss_unchanged_price() {
         let position = create_test_position();
-        let swaps = vec![
-            create_test_swap(1000, 1000),
-            create_test_swap(2000, 2000),
+        let token = create_test_token(18);
+
+        let opened = opened_at(&position, Q96);
+        let il = calculate_impermanent_loss(&position, &opened, Q96, &token).unwrap();
+        assert_eq!(il, ImpermanentLoss { value: Decimal::ZERO, percent: Decimal::ZERO });
+    }
+
+    #[test]
+    fn test_impermanent_loss_in_range() {
+        // While both prices are in range the loss is L * (sqrtP1 - sqrtP0)^2 / sqrtP0,
+        // here 1e18 * 0.1^2 / 1 = 1e16 base units = 0.01 token1
+        let position = Position {
+            tick_lower: -4000,
+            tick_upper: 4000,
+            liquidity: U256::from(1_000_000_000_000_000_000u64),
+            ..create_test_position()
+        };
+        let token = create_test_token(18);
+
+        let opened = opened_at(&position, Q96);
+        let il = calculate_impermanent_loss(&position, &opened, PRICE_121_100, &token).unwrap();
+        assert!((il.value - Decimal::new(1, 2)).abs() < Decimal::new(1, 15), "{:?}", il);
+        assert!(il.percent > Decimal::ZERO);
+
+        // Moving back and forth is symmetric in sign
+        let opened = opened_at(&position, PRICE_121_100);
+        let il_back = calculate_impermanent_loss(&position, &opened, Q96, &token).unwrap();
+        assert!(il_back.value > Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_impermanent_loss_full_range_matches_v2() {
+        // A 4x price move costs a full-range (v2) position 1 - 2 * sqrt(4) / (1 + 4) = 20%
+        let position = Position {
+            tick_lower: -887220,
+            tick_upper: 887220,
+            liquidity: U256::from(1_000_000_000_000_000_000u64),
+            ..create_test_position()
+        };
+        let token = create_test_token(18);
+
+        let opened = opened_at(&position, Q96);
+        let il = calculate_impermanent_loss(&position, &opened, Q96 * U256::from(2u8), &token);
+        let il = il.unwrap();
+        assert!((il.percent - Decimal::from(20)).abs() < Decimal::new(1, 6), "{:?}", il);
+    }
+
+    #[test]
+    fn test_impermanent_loss_out_of_range() {
+        // Entered below the range (all token0) and now above it (all token1): the
+        // position sold its token0 inside the range instead of at the current price
+        let position = Position {
+            liquidity: U256::from(1_000_000_000_000_000_000u64),
+            ..create_test_position()
+        };
+        let token = create_test_token(18);
+        let below = get_sqrt_price_at_tick(-2000).unwrap();
+        let above = get_sqrt_price_at_tick(2000).unwrap();
+
+        let opened = opened_at(&position, below);
+        let il = calculate_impermanent_loss(&position, &opened, above, &token).unwrap();
+        assert!(il.value > Decimal::ZERO);
+        assert!(il.percent > Decimal::ZERO && il.percent < Decimal::from(100));
+
+        // Staying out of range on the same side costs nothing
+        let further_below = get_sqrt_price_at_tick(-3000).unwrap();
+        let il = calculate_impermanent_loss(&position, &opened, further_below, &token).unwrap();
+        assert_eq!(il.value, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_impermanent_loss_of_closed_position() {
+        // Withdrawing everything after the in-range move above realizes its 0.01 token1
+        // loss, which stays put however the price moves afterwards
+        let liquidity = U256::from(1_000_000_000_000_000_000u64);
+        let position = Position {
+            tick_lower: -4000,
+            tick_upper: 4000,
+            liquidity: U256::ZERO,
+            closed: true,
+            ..create_test_position()
+        };
+        let changes = [
+            LiquidityChange {
+                liquidity_delta: I256::try_from(liquidity).unwrap(),
+                sqrt_price_x96: Q96,
+            },
+            LiquidityChange {
+                liquidity_delta: -I256::try_from(liquidity).unwrap(),
+                sqrt_price_x96: PRICE_121_100,
+            },
         ];
+        let token = create_test_token(18);
+
+        let il = calculate_impermanent_loss(&position, &changes, PRICE_121_100, &token).unwrap();
+        assert!((il.value - Decimal::new(1, 2)).abs() < Decimal::new(1, 15), "{:?}", il);
+        assert!(il.percent > Decimal::ZERO);
 
-        let fees = calculate_fees_earned(&position, &swaps);
-        assert!(fees > Decimal::ZERO);
+        // Back at the entry price the withdrawal comes out ahead, having sold token0 above it
+        let il = calculate_impermanent_loss(&position, &changes, Q96, &token).unwrap();
+        assert!(il.value < Decimal::ZERO);
     }
 
     #[test]
-    fn test_calculate_impermanent_loss() {
-        let position = create_test_position();
-        let initial_price = Decimal::from(100);
-        let current_price = Decimal::from(110);
+    fn test_impermanent_loss_ignores_later_deposit_at_current_price() {
+        // Liquidity added at the current price has not lost anything yet
+        let liquidity = U256::from(1_000_000_000_000_000_000u64);
+        let position = Position {
+            tick_lower: -4000,
+            tick_upper: 4000,
+            liquidity: liquidity * U256::from(2u8),
+            ..create_test_position()
+        };
+        let deposit = I256::try_from(liquidity).unwrap();
+        let changes = [
+            LiquidityChange { liquidity_delta: deposit, sqrt_price_x96: Q96 },
+            LiquidityChange { liquidity_delta: deposit, sqrt_price_x96: PRICE_121_100 },
+        ];
+        let token = create_test_token(18);
 
-        let il = calculate_impermanent_loss(&position, initial_price, current_price);
-        assert!(il >= Decimal::ZERO);
+        let il = calculate_impermanent_loss(&position, &changes, PRICE_121_100, &token).unwrap();
+        assert!((il.value - Decimal::new(1, 2)).abs() < Decimal::new(1, 15), "{:?}", il);
     }
 
     #[test]
@@ -204,15 +375,53 @@ mod tests {
     #[test]
     fn test_calculate_position_pnl() {
         let position = create_test_position();
-        let swaps = vec![create_test_swap(1000, 1000)];
-        let initial_price = Decimal::from(100);
-        let current_price = Decimal::from(105);
+        let fees_owed = (U256::from(1000u16), U256::from(1000u16));
+        let initial_price = Q96;
+        let current_price = get_sqrt_price_at_tick(500).unwrap();
         let gas_spent = Decimal::from(5);
 
-        let pnl = calculate_position_pnl(&position, &swaps, initial_price, current_price, gas_spent);
-
-        assert!(pnl.fees_earned >= Decimal::ZERO);
+        let token = create_test_token(18);
+
+        let pnl = calculate_position_pnl(
+            &position,
+            Some(fees_owed),
+            (&token, &token),
+            &opened_at(&position, initial_price),
+            current_price,
+            gas_spent,
+        )
+        .unwrap();
+
+        let fees_earned = pnl.fees_earned.unwrap();
+        assert!(fees_earned > pnl.fees1.unwrap());
+        assert_eq!(pnl.fees0, Some(Decimal::new(1, 15)));
         assert!(pnl.impermanent_loss >= Decimal::ZERO);
+        assert!(pnl.impermanent_loss_percent >= Decimal::ZERO);
         assert_eq!(pnl.gas_spent, gas_spent);
+        assert_eq!(pnl.net_pnl, Some(fees_earned - pnl.impermanent_loss - gas_spent));
+    }
+
+    #[test]
+    fn test_calculate_position_pnl_without_fees() {
+        let position = create_test_position();
+        let token = create_test_token(18);
+        let current_price = get_sqrt_price_at_tick(500).unwrap();
+
+        let gas_spent = Decimal::ONE;
+
+        let pnl = calculate_position_pnl(
+            &position,
+            None,
+            (&token, &token),
+            &opened_at(&position, Q96),
+            current_price,
+            gas_spent,
+        )
+        .unwrap();
+
+        // Impermanent loss is still known, but net P&L is not
+        assert!(pnl.impermanent_loss > Decimal::ZERO);
+        assert_eq!((pnl.fees_earned, pnl.fees0, pnl.fees1), (None, None, None));
+        assert_eq!(pnl.net_pnl, None);
     }
 }
@@ -0,0 +1,281 @@
+use alloy::primitives::{I256, U160, U256};
+use anyhow::{anyhow, bail, Result};
+
+use crate::full_math::{div_rounding_up, mul_div, mul_div_rounding_up};
+
+/// Number of fractional bits of a Q64.96 number
+pub const RESOLUTION: usize = 96;
+
+/// `2^96`, i.e. 1.0 in Q64.96
+pub const Q96: U256 = U256::from_limbs([0, 1 << 32, 0, 0]);
+
+/// Ensure a Q64.96 price fits a `uint160`
+fn to_u160(price: U256) -> Result<U256> {
+    if price > U256::from(U160::MAX) {
+        bail!("Sqrt price overflows uint160");
+    }
+    Ok(price)
+}
+
+/// Next sqrt price after adding or removing `amount` of token0, rounded up
+///
+/// `SqrtPriceMath.getNextSqrtPriceFromAmount0RoundingUp`: rounding up keeps the price
+/// moving far enough in either direction.
+pub fn get_next_sqrt_price_from_amount0_rounding_up(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256> {
+    if amount.is_zero() {
+        return Ok(sqrt_price_x96);
+    }
+    let numerator1 = U256::from(liquidity) << RESOLUTION;
+    let (product, overflow) = amount.overflowing_mul(sqrt_price_x96);
+
+    if add {
+        if !overflow {
+            let (denominator, overflow) = numerator1.overflowing_add(product);
+            if !overflow {
+                return mul_div_rounding_up(numerator1, sqrt_price_x96, denominator);
+            }
+        }
+        // Always fits in 160 bits: the price only decreases
+        return Ok(div_rounding_up(numerator1, numerator1 / sqrt_price_x96 + amount));
+    }
+
+    if overflow || numerator1 <= product {
+        bail!("Price overflow removing {} of token0", amount);
+    }
+    to_u160(mul_div_rounding_up(numerator1, sqrt_price_x96, numerator1 - product)?)
+}
+
+/// Next sqrt price after adding or removing `amount` of token1, rounded down
+///
+/// `SqrtPriceMath.getNextSqrtPriceFromAmount1RoundingDown`
+pub fn get_next_sqrt_price_from_amount1_rounding_down(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256> {
+    let liquidity = U256::from(liquidity);
+    let fits_u160 = amount <= U256::from(U160::MAX);
+
+    if add {
+        let quotient = if fits_u160 {
+            (amount << RESOLUTION) / liquidity
+        } else {
+            mul_div(amount, Q96, liquidity)?
+        };
+        let next = sqrt_price_x96
+            .checked_add(quotient)
+            .ok_or_else(|| anyhow!("Sqrt price overflows uint160"))?;
+        return to_u160(next);
+    }
+
+    let quotient = if fits_u160 {
+        div_rounding_up(amount << RESOLUTION, liquidity)
+    } else {
+        mul_div_rounding_up(amount, Q96, liquidity)?
+    };
+    if sqrt_price_x96 <= quotient {
+        bail!("Not enough liquidity to remove {} of token1", amount);
+    }
+    Ok(sqrt_price_x96 - quotient)
+}
+
+/// Next sqrt price after swapping `amount_in` into the pool (`getNextSqrtPriceFromInput`)
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount_in: U256,
+    zero_for_one: bool,
+) -> Result<U256> {
+    if sqrt_price_x96.is_zero() || liquidity == 0 {
+        bail!("Swap needs a non-zero price and liquidity");
+    }
+
+    if zero_for_one {
+        get_next_sqrt_price_from_amount0_rounding_up(sqrt_price_x96, liquidity, amount_in, true)
+    } else {
+        get_next_sqrt_price_from_amount1_rounding_down(sqrt_price_x96, liquidity, amount_in, true)
+    }
+}
+
+/// Next sqrt price after taking `amount_out` from the pool (`getNextSqrtPriceFromOutput`)
+pub fn get_next_sqrt_price_from_output(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount_out: U256,
+    zero_for_one: bool,
+) -> Result<U256> {
+    if sqrt_price_x96.is_zero() || liquidity == 0 {
+        bail!("Swap needs a non-zero price and liquidity");
+    }
+
+    if zero_for_one {
+        get_next_sqrt_price_from_amount1_rounding_down(sqrt_price_x96, liquidity, amount_out, false)
+    } else {
+        get_next_sqrt_price_from_amount0_rounding_up(sqrt_price_x96, liquidity, amount_out, false)
+    }
+}
+
+/// Token0 amount for `liquidity` between two sqrt prices (`getAmount0Delta`)
+///
+/// `liquidity * (sqrtB - sqrtA) / (sqrtA * sqrtB)`, rounded up or down.
+pub fn get_amount0_delta(
+    sqrt_price_a_x96: U256,
+    sqrt_price_b_x96: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256> {
+    let (lower, upper) = sorted(sqrt_price_a_x96, sqrt_price_b_x96);
+    if lower.is_zero() {
+        bail!("Sqrt price must be non-zero");
+    }
+
+    let numerator1 = U256::from(liquidity) << RESOLUTION;
+    let numerator2 = upper - lower;
+
+    if round_up {
+        Ok(div_rounding_up(mul_div_rounding_up(numerator1, numerator2, upper)?, lower))
+    } else {
+        Ok(mul_div(numerator1, numerator2, upper)? / lower)
+    }
+}
+
+/// Token1 amount for `liquidity` between two sqrt prices (`getAmount1Delta`)
+///
+/// `liquidity * (sqrtB - sqrtA)`, rounded up or down.
+pub fn get_amount1_delta(
+    sqrt_price_a_x96: U256,
+    sqrt_price_b_x96: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256> {
+    let (lower, upper) = sorted(sqrt_price_a_x96, sqrt_price_b_x96);
+    let liquidity = U256::from(liquidity);
+
+    if round_up {
+        mul_div_rounding_up(liquidity, upper - lower, Q96)
+    } else {
+        mul_div(liquidity, upper - lower, Q96)
+    }
+}
+
+/// Signed token0 delta for a liquidity change, from the caller's point of view
+///
+/// Adding liquidity (positive) owes the pool, rounded up; removing it is paid out,
+/// rounded down.
+pub fn get_amount0_delta_signed(
+    sqrt_price_a_x96: U256,
+    sqrt_price_b_x96: U256,
+    liquidity: i128,
+) -> Result<I256> {
+    signed_delta(liquidity, |abs, round_up| {
+        get_amount0_delta(sqrt_price_a_x96, sqrt_price_b_x96, abs, round_up)
+    })
+}
+
+/// Signed token1 delta for a liquidity change, from the caller's point of view
+pub fn get_amount1_delta_signed(
+    sqrt_price_a_x96: U256,
+    sqrt_price_b_x96: U256,
+    liquidity: i128,
+) -> Result<I256> {
+    signed_delta(liquidity, |abs, round_up| {
+        get_amount1_delta(sqrt_price_a_x96, sqrt_price_b_x96, abs, round_up)
+    })
+}
+
+fn signed_delta(liquidity: i128, amount: impl Fn(u128, bool) -> Result<U256>) -> Result<I256> {
+    let magnitude = amount(liquidity.unsigned_abs(), liquidity >= 0)?;
+    let magnitude =
+        I256::try_from(magnitude).map_err(|_| anyhow!("Amount delta overflows int256"))?;
+    Ok(if liquidity < 0 { magnitude } else { -magnitude })
+}
+
+fn sorted(a: U256, b: U256) -> (U256, U256) {
+    if a > b {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::uint;
+
+    const ONE_ETHER: u128 = 1_000_000_000_000_000_000;
+
+    /// encodePriceSqrt(121, 100), i.e. 1.1 in Q64.96
+    const PRICE_121_100: U256 = uint!(87150978765690771352898345369_U256);
+
+    #[test]
+    fn test_next_sqrt_price_from_input() {
+        // Vectors from the SqrtPriceMath test suite
+        let token1_in =
+            get_next_sqrt_price_from_input(Q96, ONE_ETHER, U256::from(ONE_ETHER / 10), false);
+        assert_eq!(token1_in.unwrap(), PRICE_121_100);
+
+        let token0_in =
+            get_next_sqrt_price_from_input(Q96, ONE_ETHER, U256::from(ONE_ETHER / 10), true);
+        assert_eq!(token0_in.unwrap(), uint!(72025602285694852357767227579_U256));
+
+        let huge_in =
+            get_next_sqrt_price_from_input(Q96, 10 * ONE_ETHER, U256::from(1u8) << 100, true);
+        assert_eq!(huge_in.unwrap(), uint!(624999999995069620_U256));
+
+        assert_eq!(get_next_sqrt_price_from_input(Q96, ONE_ETHER, U256::ZERO, true).unwrap(), Q96);
+        assert!(get_next_sqrt_price_from_input(U256::ZERO, 1, U256::from(1u8), true).is_err());
+        assert!(get_next_sqrt_price_from_input(Q96, 0, U256::from(1u8), true).is_err());
+    }
+
+    #[test]
+    fn test_next_sqrt_price_from_output() {
+        let token0_out =
+            get_next_sqrt_price_from_output(Q96, ONE_ETHER, U256::from(ONE_ETHER / 10), false);
+        assert_eq!(token0_out.unwrap(), uint!(88031291682515930659493278152_U256));
+
+        let token1_out =
+            get_next_sqrt_price_from_output(Q96, ONE_ETHER, U256::from(ONE_ETHER / 10), true);
+        assert_eq!(token1_out.unwrap(), uint!(71305346262837903834189555302_U256));
+
+        // Output exceeding virtual reserves fails
+        let price = uint!(20282409603651670423947251286016_U256);
+        assert!(get_next_sqrt_price_from_output(price, 1024, U256::from(4u8), false).is_err());
+        assert!(get_next_sqrt_price_from_output(price, 1024, U256::from(262145u32), true).is_err());
+    }
+
+    #[test]
+    fn test_amount_deltas() {
+        let up = get_amount0_delta(Q96, PRICE_121_100, ONE_ETHER, true).unwrap();
+        let down = get_amount0_delta(Q96, PRICE_121_100, ONE_ETHER, false).unwrap();
+        assert_eq!(up, U256::from(90909090909090910u64));
+        assert_eq!(down, up - U256::from(1u8));
+
+        let up = get_amount1_delta(Q96, PRICE_121_100, ONE_ETHER, true).unwrap();
+        let down = get_amount1_delta(PRICE_121_100, Q96, ONE_ETHER, false).unwrap();
+        assert_eq!(up, U256::from(100000000000000000u64));
+        assert_eq!(down, up - U256::from(1u8));
+
+        assert_eq!(get_amount0_delta(Q96, Q96, ONE_ETHER, true).unwrap(), U256::ZERO);
+        assert_eq!(get_amount1_delta(Q96, PRICE_121_100, 0, true).unwrap(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_signed_amount_deltas() {
+        let liquidity = ONE_ETHER as i128;
+
+        // Adding liquidity is owed to the pool (negative), rounded up
+        let add = get_amount0_delta_signed(Q96, PRICE_121_100, liquidity).unwrap();
+        assert_eq!(add, I256::try_from(-90909090909090910i64).unwrap());
+
+        // Removing it is paid out (positive), rounded down
+        let remove = get_amount1_delta_signed(Q96, PRICE_121_100, -liquidity).unwrap();
+        assert_eq!(remove, I256::try_from(99999999999999999i64).unwrap());
+    }
+}
@@ -0,0 +1,182 @@
+use alloy::primitives::{uint, I256, U256};
+use anyhow::{bail, Result};
+
+/// Smallest tick a v4 pool can reach (`TickMath.MIN_TICK`)
+pub const MIN_TICK: i32 = -887272;
+
+/// Largest tick a v4 pool can reach (`TickMath.MAX_TICK`)
+pub const MAX_TICK: i32 = 887272;
+
+/// `getSqrtPriceAtTick(MIN_TICK)`
+pub const MIN_SQRT_PRICE: U256 = uint!(4295128739_U256);
+
+/// `getSqrtPriceAtTick(MAX_TICK)`
+pub const MAX_SQRT_PRICE: U256 = uint!(1461446703485210103287273052203988822378723970342_U256);
+
+/// Q128.128 factors `1 / sqrt(1.0001)^(2^i)` for each bit `i` of the absolute tick
+const TICK_FACTORS: [U256; 19] = [
+    uint!(0xfff97272373d413259a46990580e213a_U256),
+    uint!(0xfff2e50f5f656932ef12357cf3c7fdcc_U256),
+    uint!(0xffe5caca7e10e4e61c3624eaa0941cd0_U256),
+    uint!(0xffcb9843d60f6159c9db58835c926644_U256),
+    uint!(0xff973b41fa98c081472e6896dfb254c0_U256),
+    uint!(0xff2ea16466c96a3843ec78b326b52861_U256),
+    uint!(0xfe5dee046a99a2a811c461f1969c3053_U256),
+    uint!(0xfcbe86c7900a88aedcffc83b479aa3a4_U256),
+    uint!(0xf987a7253ac413176f2b074cf7815e54_U256),
+    uint!(0xf3392b0822b70005940c7a398e4b70f3_U256),
+    uint!(0xe7159475a2c29b7443b29c7fa6e889d9_U256),
+    uint!(0xd097f3bdfd2022b8845ad8f792aa5825_U256),
+    uint!(0xa9f746462d870fdf8a65dc1f90e061e5_U256),
+    uint!(0x70d869a156d2a1b890bb3df62baf32f7_U256),
+    uint!(0x31be135f97d08fd981231505542fcfa6_U256),
+    uint!(0x9aa508b5b7a84e1c677de54f3e99bc9_U256),
+    uint!(0x5d6af8dedb81196699c329225ee604_U256),
+    uint!(0x2216e584f5fa1ea926041bedfe98_U256),
+    uint!(0x48a170391f7dc42444e8fa2_U256),
+];
+
+/// `sqrt(1.0001^tick) * 2^96` as a Q64.96 number (`TickMath.getSqrtPriceAtTick`)
+///
+/// Bit-exact with the on-chain library; fails for ticks outside [`MIN_TICK`, `MAX_TICK`].
+pub fn get_sqrt_price_at_tick(tick: i32) -> Result<U256> {
+    let abs_tick = tick.unsigned_abs();
+    if abs_tick > MAX_TICK as u32 {
+        bail!("Tick {} outside [{}, {}]", tick, MIN_TICK, MAX_TICK);
+    }
+
+    let mut price = if abs_tick & 0x1 != 0 {
+        uint!(0xfffcb933bd6fad37aa2d162d1a594001_U256)
+    } else {
+        U256::from(1u8) << 128
+    };
+    for (bit, factor) in TICK_FACTORS.iter().enumerate() {
+        if abs_tick & (0x2 << bit) != 0 {
+            price = (price * factor) >> 128;
+        }
+    }
+
+    if tick > 0 {
+        price = U256::MAX / price;
+    }
+
+    // Q128.128 to Q64.96, rounding up so that getTickAtSqrtPrice inverts it
+    let round_up = !(price & U256::from(u32::MAX)).is_zero();
+    Ok((price >> 32) + U256::from(round_up as u8))
+}
+
+/// Greatest tick whose sqrt price is at most `sqrt_price_x96` (`TickMath.getTickAtSqrtPrice`)
+///
+/// Fails for prices outside [`MIN_SQRT_PRICE`, `MAX_SQRT_PRICE`).
+pub fn get_tick_at_sqrt_price(sqrt_price_x96: U256) -> Result<i32> {
+    if sqrt_price_x96 < MIN_SQRT_PRICE || sqrt_price_x96 >= MAX_SQRT_PRICE {
+        bail!("Sqrt price {} outside [MIN_SQRT_PRICE, MAX_SQRT_PRICE)", sqrt_price_x96);
+    }
+
+    // log2 of the Q128.128 price, as a Q64.64 number
+    let ratio: U256 = sqrt_price_x96 << 32;
+    let msb = ratio.bit_len() - 1;
+    let mut r: U256 = if msb >= 128 { ratio >> (msb - 127) } else { ratio << (127 - msb) };
+
+    let mut log_2: I256 = (I256::try_from(msb).unwrap() - I256::try_from(128).unwrap()) << 64;
+    for bit in (50..64).rev() {
+        r = (r * r) >> 127;
+        let f: U256 = r >> 128;
+        log_2 |= I256::from_raw(f << bit);
+        r >>= f.to::<usize>();
+    }
+
+    // log_sqrt(1.0001) of the price, as a Q128.128 number
+    let log_sqrt10001: I256 = log_2 * I256::from_raw(uint!(255738958999603826347141_U256));
+
+    let tick_low = (log_sqrt10001
+        - I256::from_raw(uint!(3402992956809132418596140100660247210_U256)))
+    .asr(128)
+    .as_i32();
+    let tick_high = (log_sqrt10001
+        + I256::from_raw(uint!(291339464771989622907027621153398088495_U256)))
+    .asr(128)
+    .as_i32();
+
+    if tick_low == tick_high || get_sqrt_price_at_tick(tick_high)? > sqrt_price_x96 {
+        Ok(tick_low)
+    } else {
+        Ok(tick_high)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q96() -> U256 {
+        U256::from(1u8) << 96
+    }
+
+    #[test]
+    fn test_sqrt_price_bounds() {
+        assert_eq!(get_sqrt_price_at_tick(MIN_TICK).unwrap(), MIN_SQRT_PRICE);
+        assert_eq!(get_sqrt_price_at_tick(MAX_TICK).unwrap(), MAX_SQRT_PRICE);
+        assert_eq!(get_sqrt_price_at_tick(0).unwrap(), q96());
+
+        assert!(get_sqrt_price_at_tick(MIN_TICK - 1).is_err());
+        assert!(get_sqrt_price_at_tick(MAX_TICK + 1).is_err());
+    }
+
+    #[test]
+    fn test_sqrt_price_known_vectors() {
+        // Values from the v3/v4 TickMath test suites
+        assert_eq!(get_sqrt_price_at_tick(MIN_TICK + 1).unwrap(), uint!(4295343490_U256));
+        assert_eq!(
+            get_sqrt_price_at_tick(MAX_TICK - 1).unwrap(),
+            uint!(1461373636630004318706518188784493106690254656249_U256)
+        );
+    }
+
+    #[test]
+    fn test_sqrt_price_matches_float_at_every_tick_bit() {
+        // Each bit of the absolute tick selects one constant; check each against f64
+        for bit in 0..20 {
+            for tick in [1i32 << bit, -(1i32 << bit)] {
+                if tick.unsigned_abs() > MAX_TICK as u32 {
+                    continue;
+                }
+                let exact = get_sqrt_price_at_tick(tick).unwrap();
+                let expected = (tick as f64 / 2.0 * 0.0001f64.ln_1p()).exp() * 2f64.powi(96);
+                let actual = f64::from(exact);
+                assert!(
+                    ((actual - expected) / expected).abs() < 1e-12,
+                    "tick {}: {} vs {}",
+                    tick,
+                    actual,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_tick_at_sqrt_price_bounds() {
+        assert_eq!(get_tick_at_sqrt_price(MIN_SQRT_PRICE).unwrap(), MIN_TICK);
+        assert_eq!(get_tick_at_sqrt_price(MAX_SQRT_PRICE - U256::from(1u8)).unwrap(), MAX_TICK - 1);
+        assert_eq!(get_tick_at_sqrt_price(q96()).unwrap(), 0);
+
+        assert!(get_tick_at_sqrt_price(MIN_SQRT_PRICE - U256::from(1u8)).is_err());
+        assert!(get_tick_at_sqrt_price(MAX_SQRT_PRICE).is_err());
+    }
+
+    #[test]
+    fn test_tick_round_trip() {
+        // getTickAtSqrtPrice(getSqrtPriceAtTick(t)) == t, and the price one below
+        // belongs to the previous tick
+        let mut tick = MIN_TICK;
+        while tick < MAX_TICK {
+            let sqrt_price = get_sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(get_tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+            if tick > MIN_TICK {
+                assert_eq!(get_tick_at_sqrt_price(sqrt_price - U256::from(1u8)).unwrap(), tick - 1);
+            }
+            tick += 997;
+        }
+    }
+}
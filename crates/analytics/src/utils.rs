@@ -1,6 +1,12 @@
+use alloy::primitives::{U256, U512};
 use rust_decimal::Decimal;
 use rust_decimal::prelude::*;
 
+use crate::tick_math::{
+    get_sqrt_price_at_tick, get_tick_at_sqrt_price, MAX_SQRT_PRICE, MAX_TICK, MIN_SQRT_PRICE,
+    MIN_TICK,
+};
+
 /// Check if current tick is within position's range
 pub fn is_in_range(current_tick: i32, tick_lower: i32, tick_upper: i32) -> bool {
     current_tick >= tick_lower && current_tick < tick_upper
@@ -21,45 +27,107 @@ pub fn distance_to_range_edge(current_tick: i32, tick_lower: i32, tick_upper: i3
     dist_to_lower.min(dist_to_upper)
 }
 
-/// Convert tick to price using Uniswap v3/v4 formula: price = 1.0001^tick
+/// Convert tick to a raw price: `1.0001^tick`, via the exact on-chain sqrt price
+///
+/// Ticks outside [`MIN_TICK`, `MAX_TICK`] are clamped; prices beyond `Decimal`'s range
+/// saturate (or truncate to zero).
 pub fn tick_to_price(tick: i32) -> Decimal {
-    // For very large ticks, powi will overflow
-    // Use logarithmic calculation: price = e^(tick * ln(1.0001))
-    // This is more numerically stable for large tick values
-
-    // ln(1.0001) ≈ 0.00009999500033330834
-    let ln_base = Decimal::from_str("0.00009999500033330834").unwrap();
-
-    // Calculate tick * ln(1.0001)
-    let tick_decimal = Decimal::from(tick);
-    let exponent = tick_decimal * ln_base;
-
-    // Calculate e^exponent
-    // For safety, cap the result to avoid overflow
-    if exponent.abs() > Decimal::from(100) {
-        // For extremely large ticks, return a reasonable bound
-        if tick > 0 {
-            Decimal::from_str("1000000000").unwrap() // Cap at 1 billion
-        } else {
-            Decimal::from_str("0.000000001").unwrap() // Cap at 1 billionth
-        }
-    } else {
-        exponent.exp()
-    }
+    let tick = tick.clamp(MIN_TICK, MAX_TICK);
+    get_sqrt_price_at_tick(tick).map(sqrt_price_x96_to_price).unwrap_or_default()
 }
 
-/// Convert price to tick (inverse of tick_to_price)
+/// Convert a raw price to the tick containing it (inverse of tick_to_price)
+///
+/// Like the pool's own tick, this is the greatest tick whose price is at most `price`.
 pub fn price_to_tick(price: Decimal) -> i32 {
     if price <= Decimal::ZERO {
         return 0;
     }
 
-    // tick = log(price) / log(1.0001)
-    // Using approximation for now
-    let log_price = price.ln();
-    let log_base = Decimal::from_str("1.0001").unwrap().ln();
+    get_tick_at_sqrt_price(price_to_sqrt_price_x96(price)).unwrap_or(0)
+}
+
+/// Convert a raw price to a Q64.96 sqrt price, rounded down and clamped to the valid range
+pub fn price_to_sqrt_price_x96(price: Decimal) -> U256 {
+    let mantissa = U512::from(price.mantissa().max(0).unsigned_abs());
+    let scale = U512::from(10u64).pow(U512::from(price.scale()));
+    let squared: U512 = (mantissa << 192usize) / scale;
+    let sqrt_price = U256::from(squared.root(2));
+
+    sqrt_price.clamp(MIN_SQRT_PRICE, MAX_SQRT_PRICE - U256::from(1u8))
+}
+
+/// Convert a Q64.96 sqrt price to a raw price (token1 base units per token0 base unit)
+///
+/// Computes `sqrtPriceX96^2 / 2^192` exactly and truncates to the most decimal places
+/// that fit in `Decimal`; prices above `Decimal::MAX` saturate.
+pub fn sqrt_price_x96_to_price(sqrt_price_x96: U256) -> Decimal {
+    let squared = U512::from(sqrt_price_x96) * U512::from(sqrt_price_x96);
+    fraction_to_decimal(squared, U512::from(1u8) << 192usize)
+}
+
+/// `numerator / denominator` as a `Decimal`, truncated to the most decimal places that fit
+///
+/// Results above `Decimal::MAX` (or a zero denominator) saturate.
+pub fn fraction_to_decimal(numerator: U512, denominator: U512) -> Decimal {
+    if denominator.is_zero() {
+        return Decimal::MAX;
+    }
+    let max_mantissa = U512::from(u128::MAX >> 32);
+
+    for scale in (0..=28u32).rev() {
+        let Some(scaled) = numerator.checked_mul(U512::from(10u64).pow(U512::from(scale))) else {
+            continue;
+        };
+        let quotient = scaled / denominator;
+        if quotient <= max_mantissa {
+            return Decimal::from_i128_with_scale(quotient.to::<i128>(), scale).normalize();
+        }
+    }
+    Decimal::MAX
+}
 
-    (log_price / log_base).round().to_i32().unwrap_or(0)
+/// Convert a raw price (token1 base units per token0 base unit) to token units
+///
+/// `price * 10^(decimals0 - decimals1)`, i.e. how many whole token1 one whole token0 buys.
+pub fn adjust_price_for_decimals(raw_price: Decimal, decimals0: u8, decimals1: u8) -> Decimal {
+    let shift = i64::from(decimals0) - i64::from(decimals1);
+    Decimal::TEN
+        .checked_powi(shift)
+        .and_then(|factor| raw_price.checked_mul(factor))
+        .unwrap_or(Decimal::MAX)
+}
+
+/// Convert a raw integer token amount (e.g. an `I256` string) to token units
+///
+/// Digits beyond `Decimal`'s 28 digits of precision are truncated; amounts too large
+/// for `Decimal` saturate.
+pub fn to_token_amount(raw: &str, decimals: u8) -> Decimal {
+    let (negative, digits) = match raw.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, raw),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Decimal::ZERO;
+    }
+
+    let mut digits = digits.trim_start_matches('0');
+    let mut scale = u32::from(decimals);
+    while scale > 0 && (scale > 28 || digits.len() > 28) {
+        digits = &digits[..digits.len().saturating_sub(1)];
+        scale -= 1;
+    }
+
+    let amount = if digits.is_empty() {
+        Decimal::ZERO
+    } else if digits.len() > 28 {
+        Decimal::MAX
+    } else {
+        let mantissa = digits.parse::<i128>().unwrap_or_default();
+        Decimal::try_from_i128_with_scale(mantissa, scale).unwrap_or(Decimal::MAX)
+    };
+
+    if negative { -amount } else { amount }
 }
 
 /// Calculate range width as a percentage
@@ -96,6 +164,53 @@ mod tests {
         assert_eq!(distance_to_range_edge(200, 50, 150), 0);
     }
 
+    #[test]
+    fn test_to_token_amount() {
+        assert_eq!(to_token_amount("1500000", 6), Decimal::from_str("1.5").unwrap());
+        assert_eq!(to_token_amount("-2000000000000000000", 18), Decimal::from(-2));
+        assert_eq!(to_token_amount("42", 0), Decimal::from(42));
+        assert_eq!(to_token_amount("0", 18), Decimal::ZERO);
+        assert_eq!(to_token_amount("not a number", 18), Decimal::ZERO);
+
+        // 1e30 wei keeps its leading digits
+        let large = to_token_amount(&format!("1{}", "0".repeat(30)), 18);
+        assert_eq!(large, Decimal::from(1_000_000_000_000u64));
+    }
+
+    #[test]
+    fn test_sqrt_price_x96_to_price() {
+        let q96 = U256::from(1u8) << 96;
+        assert_eq!(sqrt_price_x96_to_price(q96), Decimal::ONE);
+        assert_eq!(sqrt_price_x96_to_price(q96 * U256::from(2u8)), Decimal::from(4));
+        assert_eq!(sqrt_price_x96_to_price(q96 >> 1), Decimal::from_str("0.25").unwrap());
+        assert_eq!(sqrt_price_x96_to_price(U256::ZERO), Decimal::ZERO);
+
+        // MAX_SQRT_PRICE is far beyond Decimal's range
+        assert_eq!(sqrt_price_x96_to_price(U256::from(1u8) << 159), Decimal::MAX);
+    }
+
+    #[test]
+    fn test_fraction_to_decimal() {
+        let third = fraction_to_decimal(U512::from(1u8), U512::from(3u8));
+        assert_eq!(third, Decimal::from_str("0.3333333333333333333333333333").unwrap());
+        assert_eq!(fraction_to_decimal(U512::from(10u8), U512::from(4u8)), Decimal::new(25, 1));
+        assert_eq!(fraction_to_decimal(U512::from(1u8), U512::ZERO), Decimal::MAX);
+
+        // Numerators too large to scale still divide exactly
+        let huge = U512::from(1u8) << 500usize;
+        assert_eq!(fraction_to_decimal(huge, huge >> 1usize), Decimal::TWO);
+    }
+
+    #[test]
+    fn test_adjust_price_for_decimals() {
+        // 1 USDC (6 decimals) per 1e-12 WETH base units: 1 WETH = 2000 USDC
+        let raw = Decimal::from_str("0.000000002").unwrap();
+        assert_eq!(adjust_price_for_decimals(raw, 18, 6), Decimal::from(2000));
+
+        let inverse = Decimal::from_str("500000000").unwrap();
+        assert_eq!(adjust_price_for_decimals(inverse, 6, 18), Decimal::from_str("0.0005").unwrap());
+    }
+
     #[test]
     fn test_tick_to_price() {
         let price_0 = tick_to_price(0);
@@ -109,4 +224,35 @@ mod tests {
         let price_neg100 = tick_to_price(-100);
         assert!(price_neg100 < Decimal::ONE);
     }
+
+    #[test]
+    fn test_tick_to_price_is_exact() {
+        assert_eq!(tick_to_price(0), Decimal::ONE);
+        // 1.0001^2 = 1.00020001, up to the last bit of the Q64.96 sqrt price
+        let price = tick_to_price(2);
+        assert!((price - Decimal::from_str("1.00020001").unwrap()).abs() < Decimal::new(1, 18));
+
+        // Extreme ticks saturate instead of panicking
+        assert_eq!(tick_to_price(MAX_TICK), Decimal::MAX);
+        assert_eq!(tick_to_price(i32::MIN), tick_to_price(MIN_TICK));
+    }
+
+    #[test]
+    fn test_price_to_tick() {
+        assert_eq!(price_to_tick(Decimal::ONE), 0);
+        assert_eq!(price_to_tick(Decimal::ZERO), 0);
+        assert_eq!(price_to_tick(Decimal::from(-5)), 0);
+
+        // Floors like the pool tick: just below 1.0001^-1 is tick -2
+        assert_eq!(price_to_tick(Decimal::from_str("1.00010001").unwrap()), 1);
+        assert_eq!(price_to_tick(Decimal::from_str("1.00009999").unwrap()), 0);
+        assert_eq!(price_to_tick(Decimal::from_str("0.9999").unwrap()), -2);
+        assert_eq!(price_to_tick(Decimal::from_str("0.99990001").unwrap()), -1);
+
+        // tick_to_price truncates, so round trip from the middle of each tick
+        let half_tick = Decimal::from_str("1.00005").unwrap();
+        for tick in [-276324, -100, 7, 60, 200311] {
+            assert_eq!(price_to_tick(tick_to_price(tick) * half_tick), tick, "tick {}", tick);
+        }
+    }
 }
@@ -1,7 +1,8 @@
 use anyhow::Result;
 use dotenv::dotenv;
 use sqlx::PgPool;
-use stillwater_indexer::GraphIndexer;
+use stillwater_indexer::{sync_token_metadata, GraphIndexer, LogIndexer};
+use stillwater_models::BlockchainService;
 use tracing::{error, info};
 
 #[tokio::main]
@@ -13,7 +14,8 @@ async fn main() -> Result<()> {
 
     println!("=== Starting Stillwater Position Sync ===");
 
-    info!("Starting position sync from The Graph...");
+    // `sync rpc` reads PoolManager logs directly from the chain instead of The Graph
+    let from_rpc = std::env::args().nth(1).as_deref() == Some("rpc");
 
     // Get database URL
     let database_url =
@@ -24,12 +26,62 @@ async fn main() -> Result<()> {
 
     info!("Connected to database");
 
+    if from_rpc {
+        info!("Starting log sync from ETHEREUM_RPC_URL...");
+
+        let rpc_url =
+            std::env::var("ETHEREUM_RPC_URL").expect("ETHEREUM_RPC_URL must be set in environment");
+        let blockchain = BlockchainService::new(&rpc_url)?;
+        let indexer = LogIndexer::from_env(blockchain.clone())
+            .expect("Failed to create LogIndexer. Ensure POOL_MANAGER_ADDRESS is set");
+
+        // Swaps stored before log indexes were tracked are matched to their receipts
+        let backfill = indexer.backfill_swap_log_indexes(&db_pool).await?;
+        if backfill.recovered > 0 || backfill.discarded > 0 || backfill.pending > 0 {
+            info!(
+                "✓ Backfilled {} swap log indexes ({} dropped for re-ingestion, {} transactions \
+                 without a receipt left for the next run)",
+                backfill.recovered, backfill.discarded, backfill.pending
+            );
+        }
+
+        match indexer.sync(&db_pool).await {
+            Ok(stats) => {
+                info!(
+                    "✓ Synced {} pools, {} liquidity events, {} swaps, {} donations from logs",
+                    stats.pools, stats.liquidity_events, stats.swaps, stats.donations
+                );
+                if stats.reorgs > 0 {
+                    info!("Recovered from {} chain reorganizations", stats.reorgs);
+                }
+            }
+            Err(e) => {
+                error!("✗ Failed to sync logs: {}", e);
+                return Err(e);
+            }
+        }
+
+        let tokens = sync_token_metadata(&db_pool, &blockchain).await?;
+        info!("✓ Registered {} new tokens", tokens);
+
+        println!("=== Euphoria Sync Complete ===");
+        return Ok(());
+    }
+
+    info!("Starting position sync from The Graph...");
+
     // Create indexer
     let indexer = GraphIndexer::from_env()
         .expect("Failed to create GraphIndexer. Ensure GRAPH_API_URL is set");
 
     info!("Indexer initialized with Graph API URL");
 
+    // Swaps stored before log indexes were tracked are matched by their entity ids
+    let recovered = indexer.backfill_swap_log_indexes(&db_pool).await?;
+    if recovered > 0 {
+        info!("✓ Backfilled {} swap log indexes", recovered);
+    }
+
     // Sync positions
     match indexer.sync_positions(&db_pool).await {
         Ok(count) => {
@@ -41,6 +93,34 @@ async fn main() -> Result<()> {
         }
     }
 
+    // Sync swaps for every pool with positions
+    match indexer.sync_all_swaps(&db_pool).await {
+        Ok(stats) => {
+            info!("✓ Synced {} swaps across {} pools", stats.swaps, stats.pools);
+            if !stats.failed_pools.is_empty() {
+                error!(
+                    "✗ Failed to sync swaps for {} pools: {}",
+                    stats.failed_pools.len(),
+                    stats.failed_pools.join(", ")
+                );
+            }
+        }
+        Err(e) => {
+            error!("✗ Failed to sync swaps: {}", e);
+            return Err(e);
+        }
+    }
+
+    // Tokens the subgraph did not describe are read from the chain when an RPC is set
+    match std::env::var("ETHEREUM_RPC_URL") {
+        Ok(rpc_url) => {
+            let blockchain = BlockchainService::new(&rpc_url)?;
+            let tokens = sync_token_metadata(&db_pool, &blockchain).await?;
+            info!("✓ Registered {} new tokens", tokens);
+        }
+        Err(_) => info!("ETHEREUM_RPC_URL not set, skipping token metadata lookup"),
+    }
+
     info!("Sync completed successfully!");
     println!("=== Euphoria Sync Complete ===");
 
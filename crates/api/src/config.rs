@@ -1,6 +1,10 @@
+use alloy::primitives::Address;
 use redis::Client as RedisClient;
 use sqlx::{PgPool, postgres::PgPoolOptions};
+use std::time::Duration;
+use tracing::warn;
 use tracing_subscriber::EnvFilter;
+use stillwater_indexer::{env_u64, GraphIndexer, LogIndexer};
 use stillwater_models::BlockchainService;
 
 /// Initializes tracing (logging)
@@ -25,7 +29,90 @@ pub fn init_redis() -> RedisClient {
 }
 
 /// Initializes blockchain service (Ethereum RPC provider)
+///
+/// Live pool state reads are enabled when `STATE_VIEW_ADDRESS` is set, and NFT position
+/// reads when `POSITION_MANAGER_ADDRESS` is set.
 pub fn init_blockchain() -> BlockchainService {
     let rpc_url = std::env::var("ETHEREUM_RPC_URL").expect("ETHEREUM_RPC_URL must be set in .env");
-    BlockchainService::new(&rpc_url).expect("Failed to create blockchain service")
+    let mut blockchain =
+        BlockchainService::new(&rpc_url).expect("Failed to create blockchain service");
+
+    if let Ok(address) = std::env::var("POSITION_MANAGER_ADDRESS") {
+        blockchain = blockchain.with_position_manager(
+            address.parse::<Address>().expect("Invalid POSITION_MANAGER_ADDRESS"),
+        );
+    }
+
+    match std::env::var("STATE_VIEW_ADDRESS") {
+        Ok(address) => blockchain
+            .with_state_view(address.parse::<Address>().expect("Invalid STATE_VIEW_ADDRESS")),
+        Err(_) => {
+            warn!("STATE_VIEW_ADDRESS not set, live pool state reads are disabled");
+            blockchain
+        }
+    }
+}
+
+/// Background indexer polling configuration
+#[derive(Debug, Clone)]
+pub struct PollerConfig {
+    /// Delay between successful sync rounds
+    pub interval: Duration,
+    /// Upper bound for the backoff delay after consecutive failures
+    pub max_backoff: Duration,
+}
+
+/// Initializes the background indexer from the environment
+///
+/// Returns `None` when `GRAPH_API_URL` is unset so the API can run without ingestion.
+pub fn init_indexer() -> Option<(GraphIndexer, PollerConfig)> {
+    let indexer = match GraphIndexer::from_env() {
+        Ok(indexer) => indexer,
+        Err(e) => {
+            warn!("Background indexer disabled: {}", e);
+            return None;
+        }
+    };
+
+    let interval_secs =
+        env_u64("INDEXER_POLL_INTERVAL_SECS").expect("Invalid indexer config").unwrap_or(30);
+    let max_backoff_secs =
+        env_u64("INDEXER_MAX_BACKOFF_SECS").expect("Invalid indexer config").unwrap_or(600);
+
+    let config = PollerConfig {
+        interval: Duration::from_secs(interval_secs),
+        max_backoff: Duration::from_secs(max_backoff_secs.max(interval_secs)),
+    };
+
+    Some((indexer, config))
+}
+
+/// Backfills the log index of swaps stored before log indexes were tracked
+///
+/// Swaps are first matched against the subgraph when the background indexer is enabled.
+/// The rest are matched against their receipts from `ETHEREUM_RPC_URL`, which needs
+/// `POOL_MANAGER_ADDRESS` to recognize Swap logs; without it those swaps are left for a
+/// later run.
+pub async fn backfill_swap_log_indexes(
+    db_pool: &PgPool,
+    graph_indexer: Option<&GraphIndexer>,
+    blockchain: &BlockchainService,
+) {
+    if let Some(graph_indexer) = graph_indexer
+        && let Err(e) = graph_indexer.backfill_swap_log_indexes(db_pool).await
+    {
+        warn!("Failed to backfill swap log indexes from The Graph: {:#}", e);
+    }
+
+    let indexer = match LogIndexer::from_env(blockchain.clone()) {
+        Ok(indexer) => indexer,
+        Err(e) => {
+            warn!("Swap log index backfill disabled: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = indexer.backfill_swap_log_indexes(db_pool).await {
+        warn!("Failed to backfill swap log indexes: {:#}", e);
+    }
 }
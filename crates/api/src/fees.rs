@@ -0,0 +1,143 @@
+use alloy::eips::BlockId;
+use alloy::primitives::{Address, B256, U256};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Duration;
+use serde::Serialize;
+use stillwater_analytics::{get_fees_owed, FeeReplay, PositionKey};
+use stillwater_db::{
+    get_liquidity_events_for_pool, get_liquidity_events_for_position, get_swaps_page,
+};
+use stillwater_models::{Pool, Position};
+use tracing::debug;
+
+use crate::market;
+use crate::state::AppState;
+
+/// Swaps read per query when replaying a position's fees
+const REPLAY_PAGE_SIZE: i64 = 1000;
+
+/// Where a position's uncollected fees were computed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeSource {
+    /// Fee growth and the position's checkpoint read through StateView
+    Chain,
+    /// Indexed swaps replayed against the position's liquidity events
+    Replay,
+}
+
+/// Raw uncollected token0 and token1 fees of a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeesOwed {
+    pub amounts: (U256, U256),
+    pub source: FeeSource,
+}
+
+/// Resolve a position's uncollected fees
+///
+/// Uses on-chain fee growth when StateView is configured, otherwise replays the indexed
+/// swaps since the position was opened.
+pub async fn position_fees(state: &AppState, pool: &Pool, position: &Position) -> Result<FeesOwed> {
+    match uncollected_fees(state, pool, position).await {
+        Ok(fees) => return Ok(fees),
+        Err(e) => debug!("Fee growth unavailable for position {}: {:#}", position.nft_id, e),
+    }
+
+    replayed_fees(state, pool, position).await
+}
+
+/// Compute a position's uncollected fees from on-chain fee growth
+///
+/// The range's fee growth (`getFeeGrowthInside`) is applied to the position's liquidity
+/// since its last checkpoint (`feeGrowthInsideLast`). Both are read at the same block so
+/// the difference is never taken across two pool states.
+pub async fn uncollected_fees(
+    state: &AppState,
+    pool: &Pool,
+    position: &Position,
+) -> Result<FeesOwed> {
+    let blockchain = &state.blockchain;
+    let pool_id = pool.pool_id.parse::<B256>().context("Failed to parse pool id")?;
+    let owner = position.manager.parse::<Address>().context("Failed to parse position manager")?;
+    let salt = position.salt.parse::<B256>().context("Failed to parse position salt")?;
+    let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+
+    let block = BlockId::number(blockchain.get_block_number().await?);
+    let checkpoint = blockchain
+        .get_position_info(pool_id, owner, tick_lower, tick_upper, salt, block)
+        .await?;
+    let fee_growth_inside =
+        blockchain.get_fee_growth_inside(pool_id, tick_lower, tick_upper, block).await?;
+
+    let amounts =
+        get_fees_owed(checkpoint.liquidity, fee_growth_inside, checkpoint.fee_growth_inside_last)?;
+
+    Ok(FeesOwed { amounts, source: FeeSource::Chain })
+}
+
+/// Compute a position's uncollected fees by replaying indexed swaps
+///
+/// Only needs the `swaps` and `liquidity_events` tables. The pool's active liquidity is
+/// rebuilt from all of its liquidity events; swaps are read a page at a time and the replay
+/// stops once the position is closed. Fails if any swap the position could have earned
+/// from cannot be attributed (e.g. subgraph swaps in dynamic-fee pools, which are stored
+/// without a fee), rather than undercount its fees.
+pub async fn replayed_fees(state: &AppState, pool: &Pool, position: &Position) -> Result<FeesOwed> {
+    let events = get_liquidity_events_for_position(&state.db_pool, position).await?;
+    let opened_at = events
+        .first()
+        .map(|event| event.timestamp)
+        .ok_or_else(|| anyhow!("No liquidity events indexed for position {}", position.nft_id))?;
+
+    let pool_events = get_liquidity_events_for_pool(&state.db_pool, &pool.pool_id).await?;
+
+    // Start from the price at the end of the previous block
+    let start = market::price_at(state, pool, opened_at - Duration::seconds(1)).await.ok();
+
+    let key = PositionKey::from_position(position);
+    let mut replay = FeeReplay::new(start.map(|point| point.sqrt_price_x96));
+    if let Ok(fee) = u32::try_from(pool.fee_tier)
+        && !pool.dynamic_fee
+    {
+        replay = replay.with_pool_fee(fee);
+    }
+    replay.track(key.clone());
+
+    // Liquidity from before the position opened only sets up the pool's active liquidity
+    let (earlier, mut pending) =
+        pool_events.split_at(pool_events.partition_point(|event| event.timestamp < opened_at));
+    for event in earlier {
+        replay.apply_liquidity_event(event);
+    }
+    let mut last_swap = None;
+    loop {
+        let swaps = get_swaps_page(
+            &state.db_pool,
+            &pool.pool_id,
+            opened_at,
+            last_swap.as_ref(),
+            REPLAY_PAGE_SIZE,
+        )
+        .await?;
+        pending = replay.apply_page(&swaps, pending);
+
+        let closed = replay.fees(&key).is_some_and(|f| f.liquidity == 0)
+            && !pending.iter().any(|event| PositionKey::from_event(event) == key);
+        if swaps.len() < REPLAY_PAGE_SIZE as usize || closed {
+            break;
+        }
+        last_swap = swaps.into_iter().last();
+    }
+    replay.apply_page(&[], pending);
+
+    if replay.skipped_swaps() > 0 {
+        bail!(
+            "{} swaps could not be attributed to position {}",
+            replay.skipped_swaps(),
+            position.nft_id
+        );
+    }
+
+    let fees = replay.fees(&key).context("Replayed position is not tracked")?;
+    Ok(FeesOwed { amounts: fees.uncollected, source: FeeSource::Replay })
+}
@@ -1 +1,2 @@
+pub mod pools;
 pub mod positions;
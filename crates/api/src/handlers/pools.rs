@@ -0,0 +1,70 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use serde::Deserialize;
+use stillwater_db::get_pool_by_id;
+use stillwater_models::PoolKey;
+use tracing::{error, info};
+
+use crate::state::AppState;
+
+const NO_HOOKS: &str = "0x0000000000000000000000000000000000000000";
+
+#[derive(Debug, Deserialize)]
+pub struct PoolKeyQueryParams {
+    pub currency0: String,
+    pub currency1: String,
+    pub fee: i32,
+    pub tick_spacing: i32,
+    #[serde(default = "default_hooks")]
+    pub hooks: String,
+}
+
+fn default_hooks() -> String {
+    NO_HOOKS.to_string()
+}
+
+/// GET /pools?currency0=A&currency1=B&fee=F&tick_spacing=T&hooks=H
+/// Look up an indexed pool by its PoolKey
+pub async fn get_pool_by_key_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PoolKeyQueryParams>,
+) -> impl IntoResponse {
+    let key = match PoolKey::from_parts(
+        &params.currency0,
+        &params.currency1,
+        params.fee,
+        params.tick_spacing,
+        &params.hooks,
+    )
+    .and_then(|key| key.validate().map(|_| key))
+    {
+        Ok(key) => key,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": format!("Invalid pool key: {:#}", e) })),
+            );
+        }
+    };
+
+    let pool_id = key.to_id().to_string();
+    info!("Fetching pool {} by key", pool_id);
+
+    match get_pool_by_id(&state.db_pool, &pool_id).await {
+        Ok(Some(pool)) => (StatusCode::OK, Json(serde_json::to_value(pool).unwrap())),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Pool not indexed", "pool_id": pool_id })),
+        ),
+        Err(e) => {
+            error!("Failed to fetch pool: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Internal server error" })),
+            )
+        }
+    }
+}
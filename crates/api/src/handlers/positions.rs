@@ -3,16 +3,19 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Json},
 };
-use chrono::Utc;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use stillwater_analytics::{
-    calculate_position_pnl, get_health_details, get_position_health, is_in_range,
+    adjust_price_for_decimals, calculate_position_pnl, get_health_details, get_hook_warnings,
+    get_position_amounts, get_position_health, is_in_range, tick_to_price, to_token_amount,
 };
-use stillwater_db::{get_position_by_nft, get_positions_by_owner, get_swaps_for_pool};
-use stillwater_models::PositionPnL;
-use tracing::{error, info};
+use stillwater_db::{get_pool_by_id, get_position_by_nft, get_positions_by_owner};
+use stillwater_indexer::get_or_fetch_token;
+use stillwater_models::{Pool, Position, PositionPnL, Token};
+use tracing::{error, info, warn};
 
+use crate::fees::{self, FeeSource};
+use crate::market::{self, PricePoint, PriceSource};
 use crate::state::AppState;
 
 #[derive(Debug, Serialize)]
@@ -23,6 +26,7 @@ pub struct PositionResponse {
     pub tick_lower: i32,
     pub tick_upper: i32,
     pub liquidity: String,
+    pub closed: bool,
     pub created_at: String,
 }
 
@@ -34,45 +38,206 @@ pub struct PositionWithPnlResponse {
     pub tick_lower: i32,
     pub tick_upper: i32,
     pub liquidity: String,
+    pub closed: bool,
     pub created_at: String,
+    pub token0: Token,
+    pub token1: Token,
+    /// Range bounds and prices as token1 per token0, adjusted for token decimals
+    pub price_lower: Decimal,
+    pub price_upper: Decimal,
+    pub entry_price: Decimal,
+    pub entry_price_source: PriceSource,
+    pub current_price: Decimal,
+    pub current_price_source: PriceSource,
+    /// Where `pnl` fees come from; null if they could not be computed (`pnl` fees and net
+    /// P&L are then null too)
+    pub fees_source: Option<FeeSource>,
+    /// Tokens the position's liquidity holds at the current price, before fees
+    pub holdings: Option<PositionHoldings>,
     pub pnl: PositionPnL,
     pub in_range: bool,
     pub current_tick: i32,
 }
 
+/// Token amounts held by a position, in token units
+#[derive(Debug, Serialize)]
+pub struct PositionHoldings {
+    pub amount0: Decimal,
+    pub amount1: Decimal,
+}
+
 #[derive(Debug, Serialize)]
 pub struct PositionHealthResponse {
     pub nft_id: String,
     pub status: String,
     pub details: String,
+    pub current_tick: i32,
+    pub current_price_source: PriceSource,
+    /// Risks from the pool's hooks (e.g. return-delta hooks that can skim fees)
+    pub warnings: Vec<String>,
 }
 
+/// Optional what-if overrides
+///
+/// By default the current tick and price come from chain state (or the latest indexed
+/// swap) and the entry price from the position's creation. Prices are in token units
+/// (token1 per token0).
 #[derive(Debug, Deserialize)]
 pub struct PnlQueryParams {
-    #[serde(default = "default_initial_price")]
-    pub initial_price: String,
-    #[serde(default = "default_current_price")]
-    pub current_price: String,
-    #[serde(default = "default_current_tick")]
-    pub current_tick: i32,
+    pub initial_price: Option<String>,
+    pub current_price: Option<String>,
+    pub current_tick: Option<i32>,
     #[serde(default = "default_gas_spent")]
     pub gas_spent: String,
 }
 
-fn default_initial_price() -> String {
-    "1.0".to_string()
+fn default_gas_spent() -> String {
+    "0".to_string()
+}
+
+type ErrorResponse = (StatusCode, Json<serde_json::Value>);
+
+fn error_response(status: StatusCode, message: &str) -> ErrorResponse {
+    (status, Json(serde_json::json!({ "error": message })))
 }
 
-fn default_current_price() -> String {
-    "1.0".to_string()
+/// A position with its pool, tokens, resolved prices and P&L
+struct PositionContext {
+    position: Position,
+    pool: Pool,
+    token0: Token,
+    token1: Token,
+    entry: PricePoint,
+    current: PricePoint,
+    fees_source: Option<FeeSource>,
+    pnl: PositionPnL,
 }
 
-fn default_current_tick() -> i32 {
-    0
+/// Load a pool and its token metadata, reading unknown tokens from the chain
+async fn load_pool(state: &AppState, pool_id: &str) -> anyhow::Result<(Pool, Token, Token)> {
+    let pool = get_pool_by_id(&state.db_pool, pool_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Pool {} not found", pool_id))?;
+
+    let token0 = get_or_fetch_token(&state.db_pool, &state.blockchain, &pool.token0).await?;
+    let token1 = get_or_fetch_token(&state.db_pool, &state.blockchain, &pool.token1).await?;
+    Ok((pool, token0, token1))
 }
 
-fn default_gas_spent() -> String {
-    "0".to_string()
+/// Parse an optional price override in token units into a raw price
+fn parse_price_override(
+    value: Option<&str>,
+    name: &str,
+    token0: &Token,
+    token1: &Token,
+) -> Result<Option<Decimal>, ErrorResponse> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+
+    match value.parse::<Decimal>() {
+        Ok(price) if price > Decimal::ZERO => {
+            Ok(Some(adjust_price_for_decimals(price, token1.decimals, token0.decimals)))
+        }
+        _ => Err(error_response(StatusCode::BAD_REQUEST, &format!("Invalid {} parameter", name))),
+    }
+}
+
+/// Load a position owned by `owner` and compute its P&L
+async fn load_position_context(
+    state: &AppState,
+    owner: &str,
+    nft_id: &str,
+    params: &PnlQueryParams,
+) -> Result<PositionContext, ErrorResponse> {
+    // Get position from database
+    let position = match get_position_by_nft(&state.db_pool, nft_id).await {
+        Ok(Some(p)) => p,
+        Ok(None) => return Err(error_response(StatusCode::NOT_FOUND, "Position not found")),
+        Err(e) => {
+            error!("Failed to fetch position: {}", e);
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"));
+        }
+    };
+
+    // Verify owner matches
+    if position.owner.to_lowercase() != owner.to_lowercase() {
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            "Position does not belong to this owner",
+        ));
+    }
+
+    // Pool state and token decimals for amounts and prices
+    let (pool, token0, token1) = match load_pool(state, &position.pool_id).await {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            error!("Failed to load pool tokens: {:#}", e);
+            return Err(error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to load token metadata",
+            ));
+        }
+    };
+
+    let gas_spent = params.gas_spent.parse::<Decimal>().map_err(|_| {
+        error_response(StatusCode::BAD_REQUEST, "Invalid gas_spent parameter")
+    })?;
+    let initial_override =
+        parse_price_override(params.initial_price.as_deref(), "initial_price", &token0, &token1)?;
+    let current_override =
+        parse_price_override(params.current_price.as_deref(), "current_price", &token0, &token1)?;
+
+    // Resolve prices server-side unless overridden for what-if analysis
+    let current_override = PricePoint::from_override(params.current_tick, current_override)
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, &format!("{:#}", e)))?;
+    let current = match current_override {
+        Some(point) => point,
+        None => market::current_price(state, &pool).await.map_err(|e| {
+            error!("Failed to resolve current price: {:#}", e);
+            error_response(StatusCode::SERVICE_UNAVAILABLE, "Current pool price unavailable")
+        })?,
+    };
+
+    let initial_override = PricePoint::from_override(None, initial_override)
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, &format!("{:#}", e)))?;
+    let entry = match initial_override {
+        Some(point) => point,
+        None => market::entry_price(state, &pool, &position).await.map_err(|e| {
+            error!("Failed to resolve entry price: {:#}", e);
+            error_response(StatusCode::SERVICE_UNAVAILABLE, "Entry price unavailable")
+        })?,
+    };
+
+    let liquidity_changes =
+        market::liquidity_changes(state, &pool, &position, &entry).await.map_err(|e| {
+            error!("Failed to price liquidity changes: {:#}", e);
+            error_response(StatusCode::SERVICE_UNAVAILABLE, "Liquidity change prices unavailable")
+        })?;
+
+    let fees = match fees::position_fees(state, &pool, &position).await {
+        Ok(fees) => Some(fees),
+        Err(e) => {
+            warn!("Uncollected fees unavailable for position {}: {:#}", position.nft_id, e);
+            None
+        }
+    };
+    // Calculate P&L
+    let pnl = calculate_position_pnl(
+        &position,
+        fees.map(|fees| fees.amounts),
+        (&token0, &token1),
+        &liquidity_changes,
+        current.sqrt_price_x96,
+        gas_spent,
+    )
+    .map_err(|e| {
+        error!("Failed to calculate P&L for position {}: {:#}", position.nft_id, e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to calculate P&L")
+    })?;
+
+    let fees_source = fees.map(|fees| fees.source);
+    Ok(PositionContext { position, pool, token0, token1, entry, current, fees_source, pnl })
 }
 
 /// GET /positions/:owner
@@ -94,6 +259,7 @@ pub async fn get_positions_handler(
                     tick_lower: p.tick_lower,
                     tick_upper: p.tick_upper,
                     liquidity: p.liquidity.to_string(),
+                    closed: p.closed,
                     created_at: p.created_at.to_rfc3339(),
                 })
                 .collect();
@@ -119,86 +285,25 @@ pub async fn get_position_with_pnl_handler(
 ) -> impl IntoResponse {
     info!("Fetching position {} for owner {} with P&L", nft_id, owner);
 
-    // Get position from database
-    let position = match get_position_by_nft(&state.db_pool, &nft_id).await {
-        Ok(Some(p)) => p,
-        Ok(None) => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({ "error": "Position not found" })),
-            )
-        }
-        Err(e) => {
-            error!("Failed to fetch position: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Internal server error" })),
-            );
-        }
+    let ctx = match load_position_context(&state, &owner, &nft_id, &params).await {
+        Ok(ctx) => ctx,
+        Err(response) => return response,
     };
-
-    // Verify owner matches
-    if position.owner.to_lowercase() != owner.to_lowercase() {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(serde_json::json!({ "error": "Position does not belong to this owner" })),
-        );
-    }
-
-    // Get swaps for the pool from the past 24 hours
-    let since = Utc::now() - chrono::Duration::hours(24);
-    let swaps = match get_swaps_for_pool(&state.db_pool, &position.pool_id, since).await {
-        Ok(s) => s,
+    let PositionContext { position, token0, token1, entry, current, fees_source, pnl, .. } = ctx;
+
+    let in_range = is_in_range(current.tick, position.tick_lower, position.tick_upper);
+    let holdings = match get_position_amounts(&position, current.sqrt_price_x96) {
+        Ok((amount0, amount1)) => Some(PositionHoldings {
+            amount0: to_token_amount(&amount0.to_string(), token0.decimals),
+            amount1: to_token_amount(&amount1.to_string(), token1.decimals),
+        }),
         Err(e) => {
-            error!("Failed to fetch swaps: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Failed to fetch swaps" })),
-            );
-        }
-    };
-
-    // Parse price parameters
-    let initial_price = match params.initial_price.parse::<Decimal>() {
-        Ok(p) => p,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({ "error": "Invalid initial_price parameter" })),
-            );
-        }
-    };
-
-    let current_price = match params.current_price.parse::<Decimal>() {
-        Ok(p) => p,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({ "error": "Invalid current_price parameter" })),
-            );
-        }
-    };
-
-    let gas_spent = match params.gas_spent.parse::<Decimal>() {
-        Ok(g) => g,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({ "error": "Invalid gas_spent parameter" })),
-            );
+            error!("Failed to compute holdings for position {}: {:#}", position.nft_id, e);
+            None
         }
     };
-
-    // Calculate P&L
-    let pnl = calculate_position_pnl(
-        &position,
-        &swaps,
-        initial_price,
-        current_price,
-        gas_spent,
-    );
-
-    let in_range = is_in_range(params.current_tick, position.tick_lower, position.tick_upper);
+    let to_token_units =
+        |raw_price| adjust_price_for_decimals(raw_price, token0.decimals, token1.decimals);
 
     let response = PositionWithPnlResponse {
         nft_id: position.nft_id,
@@ -207,10 +312,21 @@ pub async fn get_position_with_pnl_handler(
         tick_lower: position.tick_lower,
         tick_upper: position.tick_upper,
         liquidity: position.liquidity.to_string(),
+        closed: position.closed,
         created_at: position.created_at.to_rfc3339(),
+        price_lower: to_token_units(tick_to_price(position.tick_lower)),
+        price_upper: to_token_units(tick_to_price(position.tick_upper)),
+        entry_price: to_token_units(entry.price),
+        entry_price_source: entry.source,
+        current_price: to_token_units(current.price),
+        current_price_source: current.source,
+        fees_source,
+        token0,
+        token1,
+        holdings,
         pnl,
         in_range,
-        current_tick: params.current_tick,
+        current_tick: current.tick,
     };
 
     (StatusCode::OK, Json(serde_json::to_value(response).unwrap()))
@@ -225,93 +341,22 @@ pub async fn get_position_health_handler(
 ) -> impl IntoResponse {
     info!("Fetching health for position {} owner {}", nft_id, owner);
 
-    // Get position from database
-    let position = match get_position_by_nft(&state.db_pool, &nft_id).await {
-        Ok(Some(p)) => p,
-        Ok(None) => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({ "error": "Position not found" })),
-            )
-        }
-        Err(e) => {
-            error!("Failed to fetch position: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Internal server error" })),
-            );
-        }
-    };
-
-    // Verify owner matches
-    if position.owner.to_lowercase() != owner.to_lowercase() {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(serde_json::json!({ "error": "Position does not belong to this owner" })),
-        );
-    }
-
-    // Get swaps for the pool from the past 24 hours
-    let since = Utc::now() - chrono::Duration::hours(24);
-    let swaps = match get_swaps_for_pool(&state.db_pool, &position.pool_id, since).await {
-        Ok(s) => s,
-        Err(e) => {
-            error!("Failed to fetch swaps: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Failed to fetch swaps" })),
-            );
-        }
+    let ctx = match load_position_context(&state, &owner, &nft_id, &params).await {
+        Ok(ctx) => ctx,
+        Err(response) => return response,
     };
 
-    // Parse price parameters
-    let initial_price = match params.initial_price.parse::<Decimal>() {
-        Ok(p) => p,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({ "error": "Invalid initial_price parameter" })),
-            );
-        }
-    };
-
-    let current_price = match params.current_price.parse::<Decimal>() {
-        Ok(p) => p,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({ "error": "Invalid current_price parameter" })),
-            );
-        }
-    };
-
-    let gas_spent = match params.gas_spent.parse::<Decimal>() {
-        Ok(g) => g,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({ "error": "Invalid gas_spent parameter" })),
-            );
-        }
-    };
-
-    // Calculate P&L
-    let pnl = calculate_position_pnl(
-        &position,
-        &swaps,
-        initial_price,
-        current_price,
-        gas_spent,
-    );
-
     // Get health status
-    let status = get_position_health(&position, params.current_tick, &pnl);
-    let details = get_health_details(&position, params.current_tick, &pnl);
+    let status = get_position_health(&ctx.position, ctx.current.tick, &ctx.pnl);
+    let details = get_health_details(&ctx.position, ctx.current.tick, &ctx.pnl);
 
     let response = PositionHealthResponse {
-        nft_id: position.nft_id,
+        nft_id: ctx.position.nft_id,
         status: format!("{:?}", status),
         details,
+        current_tick: ctx.current.tick,
+        current_price_source: ctx.current.source,
+        warnings: get_hook_warnings(&ctx.pool),
     };
 
     (StatusCode::OK, Json(serde_json::to_value(response).unwrap()))
@@ -1,14 +1,19 @@
 mod config;
+mod fees;
 mod handlers;
+mod market;
+mod poller;
 mod state;
 
 use axum::{Router, extract::State, routing::get};
 use dotenv::dotenv;
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
 use tracing::info;
 use state::AppState;
 
+use handlers::pools::get_pool_by_key_handler;
 use handlers::positions::{
     get_positions_handler,
     get_position_with_pnl_handler,
@@ -29,11 +34,20 @@ async fn main() {
     let blockchain = config::init_blockchain();
     info!("Blockchain service initialized");
 
+    let shutdown = CancellationToken::new();
+    let background_task = poller::spawn_background(
+        config::init_indexer(),
+        db_pool.clone(),
+        blockchain.clone(),
+        shutdown.clone(),
+    );
+
     let app_state = AppState::new(db_pool, redis_client, blockchain);
 
     let app = Router::new()
         .route("/", get(root_handler))
         .route("/health", get(health_handler))
+        .route("/pools", get(get_pool_by_key_handler))
         .route("/positions/{owner}", get(get_positions_handler))
         .route("/positions/{owner}/{nft_id}", get(get_position_with_pnl_handler))
         .route("/positions/{owner}/{nft_id}/health", get(get_position_health_handler))
@@ -43,7 +57,19 @@ async fn main() {
     info!("Server running on http://{}", addr);
 
     let listener = TcpListener::bind(addr).await.expect("Failed to bind TCP listener");
-    axum::serve(listener, app.into_make_service()).await.expect("Failed to start server");
+    axum::serve(listener, app.into_make_service())
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .expect("Failed to start server");
+
+    info!("Shutting down");
+    shutdown.cancel();
+    background_task.await.expect("Background ingestion task panicked");
+}
+
+/// Resolves when the process receives Ctrl+C
+async fn shutdown_signal() {
+    tokio::signal::ctrl_c().await.expect("Failed to install Ctrl+C handler");
 }
 
 async fn root_handler(State(_state): State<AppState>) -> &'static str {
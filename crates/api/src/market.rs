@@ -0,0 +1,219 @@
+use alloy::primitives::{B256, I256, U256};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use stillwater_analytics::{
+    get_sqrt_price_at_tick, price_to_sqrt_price_x96, price_to_tick, sqrt_price_x96_to_price,
+    tick_to_price, LiquidityChange, MAX_TICK, MIN_TICK,
+};
+use stillwater_db::{
+    get_latest_swap_before_log, get_latest_swap_for_pool, get_liquidity_events_for_position,
+};
+use stillwater_models::{LiquidityEvent, Pool, Position, Swap};
+use tracing::debug;
+
+use crate::state::AppState;
+
+/// Where a pool tick and price were read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    /// Live `slot0` read through StateView
+    Chain,
+    /// Post-swap state of the latest indexed swap
+    Swap,
+    /// The pool's Initialize event (no swaps yet)
+    Initialize,
+    /// Supplied by the caller for what-if analysis
+    Override,
+}
+
+/// A pool tick and raw price (token1 base units per token0 base unit)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PricePoint {
+    pub tick: i32,
+    pub price: Decimal,
+    /// Exact Q64.96 sqrt price behind `price`
+    pub sqrt_price_x96: U256,
+    pub source: PriceSource,
+}
+
+impl PricePoint {
+    /// Caller-supplied tick and/or raw price; a missing one is derived from the other
+    ///
+    /// When both are given the price must fall within the tick, so the tick and the sqrt
+    /// price derived from the price describe the same point.
+    pub fn from_override(tick: Option<i32>, price: Option<Decimal>) -> Result<Option<Self>> {
+        let (tick, price, sqrt_price_x96) = match (tick, price) {
+            (Some(tick), Some(price)) => {
+                let price_tick = price_to_tick(price);
+                if price_tick != tick {
+                    bail!("Price {} is in tick {}, not tick {}", price, price_tick, tick);
+                }
+                (tick, price, price_to_sqrt_price_x96(price))
+            }
+            (Some(tick), None) => {
+                let sqrt_price_x96 = get_sqrt_price_at_tick(tick.clamp(MIN_TICK, MAX_TICK))?;
+                (tick, tick_to_price(tick), sqrt_price_x96)
+            }
+            (None, Some(price)) => (price_to_tick(price), price, price_to_sqrt_price_x96(price)),
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(Self { tick, price, sqrt_price_x96, source: PriceSource::Override }))
+    }
+
+    fn from_swap(swap: &Swap) -> Option<Self> {
+        let sqrt_price_x96 = swap.sqrt_price_x96?;
+        let price = sqrt_price_x96_to_price(sqrt_price_x96);
+        let tick = swap.tick.unwrap_or_else(|| price_to_tick(price));
+        Some(Self { tick, price, sqrt_price_x96, source: PriceSource::Swap })
+    }
+
+    fn from_initialize(pool: &Pool) -> Option<Self> {
+        let sqrt_price_x96 = pool.initial_sqrt_price_x96?;
+        let price = sqrt_price_x96_to_price(sqrt_price_x96);
+        let tick = pool.initial_tick.unwrap_or_else(|| price_to_tick(price));
+        Some(Self { tick, price, sqrt_price_x96, source: PriceSource::Initialize })
+    }
+}
+
+/// Resolve a pool's current tick and price
+///
+/// Reads live `slot0` when StateView is configured, otherwise falls back to the latest
+/// indexed swap and then to the pool's initial price.
+pub async fn current_price(state: &AppState, pool: &Pool) -> Result<PricePoint> {
+    match read_slot0(state, pool).await {
+        Ok(point) => return Ok(point),
+        Err(e) => debug!("Live slot0 unavailable for pool {}: {:#}", pool.pool_id, e),
+    }
+
+    price_at(state, pool, Utc::now()).await
+}
+
+/// Resolve the pool price when a position was opened
+///
+/// The position opens with its first indexed liquidity event (see [`event_price`]); without
+/// any indexed events, it opens at `created_at`.
+pub async fn entry_price(state: &AppState, pool: &Pool, position: &Position) -> Result<PricePoint> {
+    let events = get_liquidity_events_for_position(&state.db_pool, position).await?;
+    match events.first() {
+        Some(opened) => event_price(state, pool, opened).await,
+        None => price_at(state, pool, position.created_at).await,
+    }
+}
+
+/// Resolve the pool price a liquidity event happened at
+///
+/// This is the price left by the last swap logged before the event (swaps later in the
+/// same block do not count). Without a log position for the event, only swaps in earlier
+/// blocks count.
+pub async fn event_price(
+    state: &AppState,
+    pool: &Pool,
+    event: &LiquidityEvent,
+) -> Result<PricePoint> {
+    match (event.block_number, event.log_index()) {
+        (Some(block_number), Some(log_index)) => {
+            let swap = get_latest_swap_before_log(
+                &state.db_pool,
+                &pool.pool_id,
+                block_number,
+                log_index,
+            )
+            .await?;
+            price_from(pool, swap.as_ref())
+        }
+        _ => price_at(state, pool, event.timestamp - Duration::seconds(1)).await,
+    }
+}
+
+/// A position's deposits and withdrawals, each at the pool price it happened at
+///
+/// The opening deposit is priced at `entry`, so an overridden entry price applies to it.
+/// Without indexed events, the position's current liquidity counts as deposited at `entry`.
+pub async fn liquidity_changes(
+    state: &AppState,
+    pool: &Pool,
+    position: &Position,
+    entry: &PricePoint,
+) -> Result<Vec<LiquidityChange>> {
+    let events = get_liquidity_events_for_position(&state.db_pool, position).await?;
+    if events.is_empty() {
+        let liquidity_delta =
+            I256::try_from(position.liquidity).context("Position liquidity overflows int256")?;
+        return Ok(vec![LiquidityChange { liquidity_delta, sqrt_price_x96: entry.sqrt_price_x96 }]);
+    }
+
+    let mut changes = Vec::with_capacity(events.len());
+    for (i, event) in events.iter().enumerate() {
+        let sqrt_price_x96 = if i == 0 {
+            entry.sqrt_price_x96
+        } else {
+            event_price(state, pool, event).await?.sqrt_price_x96
+        };
+        changes.push(LiquidityChange { liquidity_delta: event.liquidity_delta, sqrt_price_x96 });
+    }
+    Ok(changes)
+}
+
+async fn read_slot0(state: &AppState, pool: &Pool) -> Result<PricePoint> {
+    let pool_id = pool.pool_id.parse::<B256>().context("Failed to parse pool id")?;
+    let slot0 = state.blockchain.get_slot0(pool_id).await?;
+    if slot0.sqrt_price_x96.is_zero() {
+        bail!("Pool {} is not initialized on chain", pool.pool_id);
+    }
+
+    Ok(PricePoint {
+        tick: slot0.tick,
+        price: sqrt_price_x96_to_price(slot0.sqrt_price_x96),
+        sqrt_price_x96: slot0.sqrt_price_x96,
+        source: PriceSource::Chain,
+    })
+}
+
+/// Last known price at or before `at`: the latest swap, else the pool's initial price
+pub async fn price_at(state: &AppState, pool: &Pool, at: DateTime<Utc>) -> Result<PricePoint> {
+    let swap = get_latest_swap_for_pool(&state.db_pool, &pool.pool_id, at).await?;
+    price_from(pool, swap.as_ref())
+}
+
+/// Price left by `swap`, else the pool's initial price
+fn price_from(pool: &Pool, swap: Option<&Swap>) -> Result<PricePoint> {
+    swap.and_then(PricePoint::from_swap)
+        .or_else(|| PricePoint::from_initialize(pool))
+        .ok_or_else(|| anyhow!("No price recorded for pool {}", pool.pool_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stillwater_analytics::MAX_SQRT_PRICE;
+
+    #[test]
+    fn test_override_derives_missing_half() {
+        assert_eq!(PricePoint::from_override(None, None).unwrap(), None);
+
+        let from_tick = PricePoint::from_override(Some(0), None).unwrap().unwrap();
+        assert_eq!(from_tick.source, PriceSource::Override);
+        assert!((from_tick.price - Decimal::ONE).abs() < Decimal::new(1, 4));
+        assert_eq!(from_tick.sqrt_price_x96, U256::from(1u8) << 96);
+
+        let from_price = PricePoint::from_override(None, Some(Decimal::ONE)).unwrap().unwrap();
+        assert_eq!(from_price.tick, 0);
+        assert_eq!(from_price.sqrt_price_x96, U256::from(1u8) << 96);
+
+        // Ticks outside the valid range clamp to the extreme sqrt price
+        let beyond = PricePoint::from_override(Some(i32::MAX), None).unwrap().unwrap();
+        assert_eq!(beyond.sqrt_price_x96, MAX_SQRT_PRICE);
+    }
+
+    #[test]
+    fn test_override_rejects_mismatched_tick_and_price() {
+        let both = PricePoint::from_override(Some(6931), Some(Decimal::TWO)).unwrap().unwrap();
+        assert_eq!((both.tick, both.price), (6931, Decimal::TWO));
+        assert_eq!(both.sqrt_price_x96, price_to_sqrt_price_x96(Decimal::TWO));
+
+        assert!(PricePoint::from_override(Some(10), Some(Decimal::TWO)).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use anyhow::{bail, Result};
+use sqlx::PgPool;
+use std::sync::Arc;
+use stillwater_indexer::{backoff_delay, GraphIndexer};
+use stillwater_models::BlockchainService;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::config::{self, PollerConfig};
+
+/// Spawn the background ingestion task
+///
+/// Swap log indexes are backfilled first, then the indexer is polled when configured.
+/// The backfill runs before the first sync round so the checkpoints it rewinds are not
+/// overwritten by a round in flight, and off the startup path so the API serves
+/// meanwhile. Cancelling `shutdown` stops either step.
+pub fn spawn_background(
+    indexer: Option<(GraphIndexer, PollerConfig)>,
+    db_pool: PgPool,
+    blockchain: BlockchainService,
+    shutdown: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let graph_indexer = indexer.as_ref().map(|(indexer, _)| indexer);
+        tokio::select! {
+            _ = config::backfill_swap_log_indexes(&db_pool, graph_indexer, &blockchain) => {}
+            _ = shutdown.cancelled() => return,
+        }
+
+        if let Some((indexer, config)) = indexer {
+            run_indexer(indexer, db_pool, config, shutdown).await;
+        }
+    })
+}
+
+/// Run the supervised background indexer loop
+///
+/// Each round runs in its own task so a panic is reported and retried like any other
+/// failure instead of taking the poller down. Cancelling `shutdown` aborts the current
+/// round (syncs are idempotent and checkpointed) and ends the loop.
+async fn run_indexer(
+    indexer: GraphIndexer,
+    db_pool: PgPool,
+    config: PollerConfig,
+    shutdown: CancellationToken,
+) {
+    let indexer = Arc::new(indexer);
+
+    info!("Background indexer started (interval: {:?})", config.interval);
+    let mut failures: u32 = 0;
+
+    loop {
+        let round = tokio::spawn(sync_round(indexer.clone(), db_pool.clone()));
+        let abort = round.abort_handle();
+
+        let outcome = tokio::select! {
+            outcome = round => outcome,
+            _ = shutdown.cancelled() => {
+                abort.abort();
+                break;
+            }
+        };
+
+        let delay = match outcome {
+            Ok(Ok(())) => {
+                failures = 0;
+                config.interval
+            }
+            Ok(Err(e)) => {
+                failures = failures.saturating_add(1);
+                error!("Indexer sync round failed ({} in a row): {:#}", failures, e);
+                backoff_delay(config.interval, config.max_backoff, failures)
+            }
+            Err(e) => {
+                failures = failures.saturating_add(1);
+                error!("Indexer sync round panicked ({} in a row): {}", failures, e);
+                backoff_delay(config.interval, config.max_backoff, failures)
+            }
+        };
+
+        if failures > 0 {
+            warn!("Retrying indexer sync in {:?}", delay);
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown.cancelled() => break,
+        }
+    }
+
+    info!("Background indexer stopped");
+}
+
+/// Run one sync round: positions, then swaps for every pool with positions
+async fn sync_round(indexer: Arc<GraphIndexer>, db_pool: PgPool) -> Result<()> {
+    let positions = indexer.sync_positions(&db_pool).await?;
+    let swaps = indexer.sync_all_swaps(&db_pool).await?;
+
+    info!("Indexer round complete: {} positions, {} swaps", positions, swaps.swaps);
+
+    if !swaps.failed_pools.is_empty() {
+        bail!("Failed to sync swaps for pools: {}", swaps.failed_pools.join(", "));
+    }
+    Ok(())
+}
@@ -6,6 +6,7 @@ use stillwater_models::BlockchainService;
 #[derive(Clone)]
 pub struct AppState {
     pub db_pool: PgPool,
+    #[allow(dead_code)] // Reserved for the caching layer
     pub redis_client: RedisClient,
     pub blockchain: BlockchainService,
 }
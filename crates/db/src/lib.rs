@@ -1,8 +1,11 @@
 use alloy::primitives::{I256, U256};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use sqlx::{postgres::PgPoolOptions, PgPool, Row};
-use stillwater_models::{Pool, Position, PositionSnapshot, Swap};
+use sqlx::{postgres::{PgPoolOptions, PgRow}, PgPool, Row};
+use stillwater_models::{
+    Donation, IndexedBlock, LiquidityEvent, Pool, Position, PositionSnapshot, Swap, SyncEntity,
+    SyncState, Token, DEFAULT_SALT,
+};
 
 pub type DbPool = PgPool;
 
@@ -24,13 +27,55 @@ pub async fn get_pool() -> Result<PgPool> {
 // Pool Operations
 // ============================================================================
 
+/// Map a `pools` row selected with `initial_sqrt_price_x96::text` into a Pool
+fn pool_from_row(r: &PgRow) -> Pool {
+    let sqrt_price_str: Option<String> = r.get(9);
+    Pool {
+        pool_id: r.get(0),
+        token0: r.get(1),
+        token1: r.get(2),
+        fee_tier: r.get(3),
+        tick_spacing: r.get(4),
+        hooks: r.get(5),
+        dynamic_fee: r.get(6),
+        created_at: r.get(7),
+        created_block: r.get(8),
+        initial_sqrt_price_x96: sqrt_price_str.and_then(|p| U256::from_str_radix(&p, 10).ok()),
+        initial_tick: r.get(10),
+        hook_flags: r.get(11),
+        hook_return_delta: r.get(12),
+    }
+}
+
 /// Insert a new pool
+///
+/// An existing pool that lacks initialization data (first seen through the subgraph)
+/// takes it over, including the creation time.
 pub async fn insert_pool(pool: &PgPool, p: &Pool) -> Result<()> {
+    let sqrt_price_str = p.initial_sqrt_price_x96.map(|price| price.to_string());
+
     sqlx::query(
         r#"
-        INSERT INTO pools (pool_id, token0, token1, fee_tier, tick_spacing, created_at)
-        VALUES ($1, $2, $3, $4, $5, $6)
-        ON CONFLICT (pool_id) DO NOTHING
+        INSERT INTO pools (
+            pool_id, token0, token1, fee_tier, tick_spacing, hooks, dynamic_fee, created_at,
+            created_block, initial_sqrt_price_x96, initial_tick, hook_flags, hook_return_delta
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10::numeric, $11, $12, $13)
+        ON CONFLICT (pool_id) DO UPDATE SET
+            hooks = COALESCE(pools.hooks, EXCLUDED.hooks),
+            hook_flags = COALESCE(pools.hook_flags, EXCLUDED.hook_flags),
+            hook_return_delta = CASE
+                WHEN pools.hook_flags IS NULL THEN EXCLUDED.hook_return_delta
+                ELSE pools.hook_return_delta
+            END,
+            created_at = CASE
+                WHEN pools.created_block IS NULL AND EXCLUDED.created_block IS NOT NULL
+                THEN EXCLUDED.created_at
+                ELSE pools.created_at
+            END,
+            created_block = COALESCE(pools.created_block, EXCLUDED.created_block),
+            initial_sqrt_price_x96 = COALESCE(pools.initial_sqrt_price_x96, EXCLUDED.initial_sqrt_price_x96),
+            initial_tick = COALESCE(pools.initial_tick, EXCLUDED.initial_tick)
         "#,
     )
     .bind(&p.pool_id)
@@ -38,7 +83,14 @@ pub async fn insert_pool(pool: &PgPool, p: &Pool) -> Result<()> {
     .bind(&p.token1)
     .bind(p.fee_tier)
     .bind(p.tick_spacing)
+    .bind(&p.hooks)
+    .bind(p.dynamic_fee)
     .bind(p.created_at)
+    .bind(p.created_block)
+    .bind(&sqrt_price_str)
+    .bind(p.initial_tick)
+    .bind(p.hook_flags)
+    .bind(p.hook_return_delta)
     .execute(pool)
     .await
     .context("Failed to insert pool")?;
@@ -48,9 +100,10 @@ pub async fn insert_pool(pool: &PgPool, p: &Pool) -> Result<()> {
 
 /// Get a pool by pool_id
 pub async fn get_pool_by_id(pool: &PgPool, pool_id: &str) -> Result<Option<Pool>> {
-    let result = sqlx::query_as::<_, Pool>(
+    let row = sqlx::query(
         r#"
-        SELECT pool_id, token0, token1, fee_tier, tick_spacing, created_at
+        SELECT pool_id, token0, token1, fee_tier, tick_spacing, hooks, dynamic_fee, created_at,
+               created_block, initial_sqrt_price_x96::text, initial_tick, hook_flags, hook_return_delta
         FROM pools
         WHERE pool_id = $1
         "#,
@@ -60,30 +113,132 @@ pub async fn get_pool_by_id(pool: &PgPool, pool_id: &str) -> Result<Option<Pool>
     .await
     .context("Failed to get pool by ID")?;
 
+    Ok(row.as_ref().map(pool_from_row))
+}
+
+/// Get all known pools
+pub async fn get_all_pools(pool: &PgPool) -> Result<Vec<Pool>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT pool_id, token0, token1, fee_tier, tick_spacing, hooks, dynamic_fee, created_at,
+               created_block, initial_sqrt_price_x96::text, initial_tick, hook_flags, hook_return_delta
+        FROM pools
+        ORDER BY created_at ASC
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to get all pools")?;
+
+    Ok(rows.iter().map(pool_from_row).collect())
+}
+
+// ============================================================================
+// Token Operations
+// ============================================================================
+
+/// Insert token metadata (existing entries are kept)
+pub async fn insert_token(pool: &PgPool, token: &Token) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO tokens (address, symbol, name, decimals)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (address) DO NOTHING
+        "#,
+    )
+    .bind(token.address.to_lowercase())
+    .bind(&token.symbol)
+    .bind(&token.name)
+    .bind(token.decimals as i16)
+    .execute(pool)
+    .await
+    .context("Failed to insert token")?;
+
+    Ok(())
+}
+
+/// Get token metadata by address
+pub async fn get_token(pool: &PgPool, address: &str) -> Result<Option<Token>> {
+    let result = sqlx::query_as::<_, Token>(
+        r#"
+        SELECT address, symbol, name, decimals
+        FROM tokens
+        WHERE address = $1
+        "#,
+    )
+    .bind(address.to_lowercase())
+    .fetch_optional(pool)
+    .await
+    .context("Failed to get token")?;
+
     Ok(result)
 }
 
+/// Get the addresses of pool tokens that have no metadata yet
+pub async fn get_unknown_pool_tokens(pool: &PgPool) -> Result<Vec<String>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT DISTINCT t.address
+        FROM (
+            SELECT LOWER(token0) AS address FROM pools
+            UNION
+            SELECT LOWER(token1) AS address FROM pools
+        ) t
+        LEFT JOIN tokens ON tokens.address = t.address
+        WHERE tokens.address IS NULL
+        ORDER BY t.address
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to get unknown pool tokens")?;
+
+    Ok(rows.iter().map(|row| row.get("address")).collect())
+}
+
 // ============================================================================
 // Position Operations
 // ============================================================================
 
+/// Map a `positions` row selected with `liquidity::text` into a Position
+fn position_from_row(r: &PgRow) -> Position {
+    let liquidity_str: String = r.get(8);
+    Position {
+        id: r.get(0),
+        nft_id: r.get(1),
+        owner: r.get(2),
+        manager: r.get(3),
+        pool_id: r.get(4),
+        tick_lower: r.get(5),
+        tick_upper: r.get(6),
+        salt: r.get(7),
+        liquidity: U256::from_str_radix(&liquidity_str, 10).unwrap_or_default(),
+        closed: r.get(9),
+        created_at: r.get(10),
+    }
+}
+
 /// Insert a new position
 pub async fn insert_position(pool: &PgPool, pos: &Position) -> Result<()> {
     let liquidity_str = pos.liquidity.to_string();
 
     sqlx::query(
         r#"
-        INSERT INTO positions (nft_id, owner, pool_id, tick_lower, tick_upper, liquidity, created_at)
-        VALUES ($1, $2, $3, $4, $5, $6::numeric, $7)
-        ON CONFLICT (nft_id) DO NOTHING
+        INSERT INTO positions
+            (nft_id, owner, manager, pool_id, tick_lower, tick_upper, salt, liquidity, closed, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8::numeric, $9, $10)
+        ON CONFLICT DO NOTHING
         "#,
     )
     .bind(&pos.nft_id)
     .bind(&pos.owner)
+    .bind(&pos.manager)
     .bind(&pos.pool_id)
     .bind(pos.tick_lower)
     .bind(pos.tick_upper)
+    .bind(&pos.salt)
     .bind(&liquidity_str)
+    .bind(pos.closed)
     .bind(pos.created_at)
     .execute(pool)
     .await
@@ -96,7 +251,7 @@ pub async fn insert_position(pool: &PgPool, pos: &Position) -> Result<()> {
 pub async fn get_position_by_id(pool: &PgPool, id: i64) -> Result<Option<Position>> {
     let row = sqlx::query(
         r#"
-        SELECT id, nft_id, owner, pool_id, tick_lower, tick_upper, liquidity::text, created_at
+        SELECT id, nft_id, owner, manager, pool_id, tick_lower, tick_upper, salt, liquidity::text, closed, created_at
         FROM positions
         WHERE id = $1
         "#,
@@ -106,26 +261,14 @@ pub async fn get_position_by_id(pool: &PgPool, id: i64) -> Result<Option<Positio
     .await
     .context("Failed to get position by ID")?;
 
-    Ok(row.map(|r| {
-        let liquidity_str: String = r.get(6);
-        Position {
-            id: r.get(0),
-            nft_id: r.get(1),
-            owner: r.get(2),
-            pool_id: r.get(3),
-            tick_lower: r.get(4),
-            tick_upper: r.get(5),
-            liquidity: U256::from_str_radix(&liquidity_str, 10).unwrap_or_default(),
-            created_at: r.get(7),
-        }
-    }))
+    Ok(row.as_ref().map(position_from_row))
 }
 
 /// Get a position by NFT ID
 pub async fn get_position_by_nft(pool: &PgPool, nft_id: &str) -> Result<Option<Position>> {
     let row = sqlx::query(
         r#"
-        SELECT id, nft_id, owner, pool_id, tick_lower, tick_upper, liquidity::text, created_at
+        SELECT id, nft_id, owner, manager, pool_id, tick_lower, tick_upper, salt, liquidity::text, closed, created_at
         FROM positions
         WHERE nft_id = $1
         "#,
@@ -135,26 +278,14 @@ pub async fn get_position_by_nft(pool: &PgPool, nft_id: &str) -> Result<Option<P
     .await
     .context("Failed to get position by NFT ID")?;
 
-    Ok(row.map(|r| {
-        let liquidity_str: String = r.get(6);
-        Position {
-            id: r.get(0),
-            nft_id: r.get(1),
-            owner: r.get(2),
-            pool_id: r.get(3),
-            tick_lower: r.get(4),
-            tick_upper: r.get(5),
-            liquidity: U256::from_str_radix(&liquidity_str, 10).unwrap_or_default(),
-            created_at: r.get(7),
-        }
-    }))
+    Ok(row.as_ref().map(position_from_row))
 }
 
 /// Get all positions for an owner
 pub async fn get_positions_by_owner(pool: &PgPool, owner: &str) -> Result<Vec<Position>> {
     let rows = sqlx::query(
         r#"
-        SELECT id, nft_id, owner, pool_id, tick_lower, tick_upper, liquidity::text, created_at
+        SELECT id, nft_id, owner, manager, pool_id, tick_lower, tick_upper, salt, liquidity::text, closed, created_at
         FROM positions
         WHERE owner = $1
         ORDER BY created_at DESC
@@ -165,29 +296,14 @@ pub async fn get_positions_by_owner(pool: &PgPool, owner: &str) -> Result<Vec<Po
     .await
     .context("Failed to get positions by owner")?;
 
-    Ok(rows
-        .into_iter()
-        .map(|r| {
-            let liquidity_str: String = r.get(6);
-            Position {
-                id: r.get(0),
-                nft_id: r.get(1),
-                owner: r.get(2),
-                pool_id: r.get(3),
-                tick_lower: r.get(4),
-                tick_upper: r.get(5),
-                liquidity: U256::from_str_radix(&liquidity_str, 10).unwrap_or_default(),
-                created_at: r.get(7),
-            }
-        })
-        .collect())
+    Ok(rows.iter().map(position_from_row).collect())
 }
 
 /// Get all positions in a pool
 pub async fn get_positions_by_pool(pool: &PgPool, pool_id: &str) -> Result<Vec<Position>> {
     let rows = sqlx::query(
         r#"
-        SELECT id, nft_id, owner, pool_id, tick_lower, tick_upper, liquidity::text, created_at
+        SELECT id, nft_id, owner, manager, pool_id, tick_lower, tick_upper, salt, liquidity::text, closed, created_at
         FROM positions
         WHERE pool_id = $1
         ORDER BY created_at DESC
@@ -198,53 +314,443 @@ pub async fn get_positions_by_pool(pool: &PgPool, pool_id: &str) -> Result<Vec<P
     .await
     .context("Failed to get positions by pool")?;
 
-    Ok(rows
-        .into_iter()
-        .map(|r| {
-            let liquidity_str: String = r.get(6);
-            Position {
-                id: r.get(0),
-                nft_id: r.get(1),
-                owner: r.get(2),
-                pool_id: r.get(3),
-                tick_lower: r.get(4),
-                tick_upper: r.get(5),
-                liquidity: U256::from_str_radix(&liquidity_str, 10).unwrap_or_default(),
-                created_at: r.get(7),
+    Ok(rows.iter().map(position_from_row).collect())
+}
+
+/// Get the ids of all pools that have at least one position
+pub async fn get_position_pool_ids(pool: &PgPool) -> Result<Vec<String>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT DISTINCT pool_id
+        FROM positions
+        ORDER BY pool_id
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to get position pool ids")?;
+
+    Ok(rows.iter().map(|row| row.get("pool_id")).collect())
+}
+
+/// Get NFT positions (non-default salt) held by a manager in a pool and tick range
+pub async fn get_nft_positions_in_range(
+    pool: &PgPool,
+    manager: &str,
+    pool_id: &str,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Result<Vec<Position>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, nft_id, owner, manager, pool_id, tick_lower, tick_upper, salt, liquidity::text, closed, created_at
+        FROM positions
+        WHERE manager = $1 AND pool_id = $2 AND tick_lower = $3 AND tick_upper = $4 AND salt <> $5
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(manager)
+    .bind(pool_id)
+    .bind(tick_lower)
+    .bind(tick_upper)
+    .bind(DEFAULT_SALT)
+    .fetch_all(pool)
+    .await
+    .context("Failed to get NFT positions in range")?;
+
+    Ok(rows.iter().map(position_from_row).collect())
+}
+
+/// Update the holder of an NFT position
+pub async fn update_position_owner(pool: &PgPool, nft_id: &str, owner: &str) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE positions
+        SET owner = $2
+        WHERE nft_id = $1
+        "#,
+    )
+    .bind(nft_id)
+    .bind(owner)
+    .execute(pool)
+    .await
+    .context("Failed to update position owner")?;
+
+    Ok(())
+}
+
+/// Move an NFT position to the holder it was transferred to in `block_number`
+///
+/// The previous holder is recorded so [`rollback_above_block`] can restore it if the block
+/// is reorganized away.
+pub async fn transfer_position_owner(
+    pool: &PgPool,
+    nft_id: &str,
+    owner: &str,
+    block_number: i64,
+) -> Result<()> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO position_owner_changes (nft_id, block_number, previous_owner, owner)
+        SELECT nft_id, $3, owner, $2
+        FROM positions
+        WHERE nft_id = $1 AND owner <> $2
+        "#,
+    )
+    .bind(nft_id)
+    .bind(owner)
+    .bind(block_number)
+    .execute(&mut *tx)
+    .await
+    .context("Failed to record position owner change")?;
+
+    sqlx::query("UPDATE positions SET owner = $2 WHERE nft_id = $1")
+        .bind(nft_id)
+        .bind(owner)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to update position owner")?;
+
+    tx.commit().await.context("Failed to commit position owner change")?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Liquidity Event Operations
+// ============================================================================
+
+/// Store a liquidity event and refresh the derived position it belongs to
+///
+/// The position's liquidity is the sum of all deltas for its
+/// (manager, pool, tick_lower, tick_upper, salt) key, where the manager is the event's
+/// PoolManager-level `owner`; it is marked closed once that sum reaches zero. The position
+/// takes the event's NFT id (or its event id when unlinked) and `holder` when the event
+/// creates it. Replaying an already stored event is a no-op, unless the stored copy was
+/// not linked to an NFT and the replay is: the event is then moved to its NFT position.
+/// Returns whether the event was new.
+pub async fn apply_liquidity_event(
+    pool: &PgPool,
+    event: &LiquidityEvent,
+    holder: &str,
+) -> Result<bool> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    let stored = sqlx::query(
+        "SELECT owner, salt, nft_id FROM liquidity_events WHERE event_id = $1 FOR UPDATE",
+    )
+    .bind(&event.event_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .context("Failed to look up liquidity event")?;
+
+    let inserted = match stored {
+        None => {
+            sqlx::query(
+                r#"
+                INSERT INTO liquidity_events
+                    (event_id, tx_hash, block_number, pool_id, owner, tick_lower, tick_upper, salt,
+                     liquidity_delta, timestamp, nft_id)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9::numeric, $10, $11)
+                "#,
+            )
+            .bind(&event.event_id)
+            .bind(&event.tx_hash)
+            .bind(event.block_number)
+            .bind(&event.pool_id)
+            .bind(&event.owner)
+            .bind(event.tick_lower)
+            .bind(event.tick_upper)
+            .bind(&event.salt)
+            .bind(event.liquidity_delta.to_string())
+            .bind(event.timestamp)
+            .bind(&event.nft_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to insert liquidity event")?;
+
+            let nft_id = event.nft_id.as_deref().unwrap_or(&event.event_id);
+            upsert_position_from_events(
+                &mut tx,
+                event,
+                &event.owner,
+                &event.salt,
+                Some(nft_id),
+                Some(holder),
+            )
+            .await?;
+            true
+        }
+        Some(row) => {
+            let stored_owner: String = row.get(0);
+            let stored_salt: String = row.get(1);
+            let stored_nft_id: Option<String> = row.get(2);
+            if let (None, Some(nft_id)) = (stored_nft_id, event.nft_id.as_deref()) {
+                relink_liquidity_event(&mut tx, event, &stored_owner, &stored_salt, nft_id, holder)
+                    .await?;
             }
-        })
-        .collect())
+            false
+        }
+    };
+
+    tx.commit().await.context("Failed to commit liquidity event")?;
+
+    Ok(inserted)
+}
+
+/// Insert or refresh the position keyed by `event`'s pool and range with `manager` and
+/// `salt`, from the liquidity events stored under that key
+///
+/// `nft_id` and `holder` are only used when the position is created; they default to its
+/// earliest event id and its manager.
+async fn upsert_position_from_events(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event: &LiquidityEvent,
+    manager: &str,
+    salt: &str,
+    nft_id: Option<&str>,
+    holder: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO positions
+            (nft_id, owner, manager, pool_id, tick_lower, tick_upper, salt, liquidity, closed, created_at)
+        SELECT COALESCE($1, MIN(event_id)), COALESCE($2, owner), owner, pool_id, tick_lower,
+               tick_upper, salt, GREATEST(SUM(liquidity_delta), 0), SUM(liquidity_delta) <= 0,
+               MIN(timestamp)
+        FROM liquidity_events
+        WHERE owner = $3 AND pool_id = $4 AND tick_lower = $5 AND tick_upper = $6 AND salt = $7
+        GROUP BY owner, pool_id, tick_lower, tick_upper, salt
+        ON CONFLICT (manager, pool_id, tick_lower, tick_upper, salt) DO UPDATE
+        SET liquidity = EXCLUDED.liquidity,
+            closed = EXCLUDED.closed,
+            created_at = LEAST(positions.created_at, EXCLUDED.created_at)
+        "#,
+    )
+    .bind(nft_id)
+    .bind(holder)
+    .bind(manager)
+    .bind(&event.pool_id)
+    .bind(event.tick_lower)
+    .bind(event.tick_upper)
+    .bind(salt)
+    .execute(&mut **tx)
+    .await
+    .context("Failed to update position from liquidity events")?;
+
+    Ok(())
+}
+
+/// Move a liquidity event stored without an NFT link onto its NFT position
+///
+/// Events ingested before NFT linking were keyed by their transaction origin. The old
+/// position row is re-keyed in place when the NFT position does not exist yet, so its id
+/// and snapshots carry over; whatever remains under the old key is recomputed.
+async fn relink_liquidity_event(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event: &LiquidityEvent,
+    old_owner: &str,
+    old_salt: &str,
+    nft_id: &str,
+    holder: &str,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE liquidity_events SET owner = $2, salt = $3, nft_id = $4 WHERE event_id = $1",
+    )
+    .bind(&event.event_id)
+    .bind(&event.owner)
+    .bind(&event.salt)
+    .bind(nft_id)
+    .execute(&mut **tx)
+    .await
+    .context("Failed to relink liquidity event")?;
+
+    sqlx::query(
+        r#"
+        UPDATE positions
+        SET manager = $6, salt = $7, nft_id = $8, owner = $9
+        WHERE manager = $1 AND pool_id = $2 AND tick_lower = $3 AND tick_upper = $4 AND salt = $5
+          AND NOT EXISTS (
+            SELECT 1 FROM positions
+            WHERE manager = $6 AND pool_id = $2 AND tick_lower = $3 AND tick_upper = $4 AND salt = $7
+          )
+        "#,
+    )
+    .bind(old_owner)
+    .bind(&event.pool_id)
+    .bind(event.tick_lower)
+    .bind(event.tick_upper)
+    .bind(old_salt)
+    .bind(&event.owner)
+    .bind(&event.salt)
+    .bind(nft_id)
+    .bind(holder)
+    .execute(&mut **tx)
+    .await
+    .context("Failed to move position to its NFT")?;
+
+    upsert_position_from_events(tx, event, &event.owner, &event.salt, Some(nft_id), Some(holder))
+        .await?;
+    upsert_position_from_events(tx, event, old_owner, old_salt, None, None).await?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM positions p
+        WHERE manager = $1 AND pool_id = $2 AND tick_lower = $3 AND tick_upper = $4 AND salt = $5
+          AND NOT EXISTS (
+            SELECT 1 FROM liquidity_events e
+            WHERE e.owner = p.manager AND e.pool_id = p.pool_id AND e.tick_lower = p.tick_lower
+              AND e.tick_upper = p.tick_upper AND e.salt = p.salt
+          )
+        "#,
+    )
+    .bind(old_owner)
+    .bind(&event.pool_id)
+    .bind(event.tick_lower)
+    .bind(event.tick_upper)
+    .bind(old_salt)
+    .execute(&mut **tx)
+    .await
+    .context("Failed to remove emptied position")?;
+
+    Ok(())
+}
+
+/// Get all liquidity events for a position, oldest first
+pub async fn get_liquidity_events_for_position(
+    pool: &PgPool,
+    position: &Position,
+) -> Result<Vec<LiquidityEvent>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, event_id, tx_hash, block_number, pool_id, owner, tick_lower, tick_upper, salt,
+               liquidity_delta::text, timestamp, nft_id
+        FROM liquidity_events
+        WHERE owner = $1 AND pool_id = $2 AND tick_lower = $3 AND tick_upper = $4 AND salt = $5
+        ORDER BY timestamp ASC, id ASC
+        "#,
+    )
+    .bind(&position.manager)
+    .bind(&position.pool_id)
+    .bind(position.tick_lower)
+    .bind(position.tick_upper)
+    .bind(&position.salt)
+    .fetch_all(pool)
+    .await
+    .context("Failed to get liquidity events for position")?;
+
+    Ok(rows.iter().map(liquidity_event_from_row).collect())
+}
+
+/// Get all liquidity events of every position in a pool, oldest first
+pub async fn get_liquidity_events_for_pool(
+    pool: &PgPool,
+    pool_id: &str,
+) -> Result<Vec<LiquidityEvent>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, event_id, tx_hash, block_number, pool_id, owner, tick_lower, tick_upper, salt,
+               liquidity_delta::text, timestamp, nft_id
+        FROM liquidity_events
+        WHERE pool_id = $1
+        ORDER BY timestamp ASC, id ASC
+        "#,
+    )
+    .bind(pool_id)
+    .fetch_all(pool)
+    .await
+    .context("Failed to get liquidity events for pool")?;
+
+    Ok(rows.iter().map(liquidity_event_from_row).collect())
+}
+
+/// Map a `liquidity_events` row selected with the delta cast to text into a LiquidityEvent
+fn liquidity_event_from_row(r: &PgRow) -> LiquidityEvent {
+    let delta_str: String = r.get(9);
+    LiquidityEvent {
+        id: r.get(0),
+        event_id: r.get(1),
+        tx_hash: r.get(2),
+        block_number: r.get(3),
+        pool_id: r.get(4),
+        owner: r.get(5),
+        tick_lower: r.get(6),
+        tick_upper: r.get(7),
+        salt: r.get(8),
+        liquidity_delta: delta_str.parse::<I256>().unwrap_or_default(),
+        timestamp: r.get(10),
+        nft_id: r.get(11),
+    }
 }
 
 // ============================================================================
 // Swap Operations
 // ============================================================================
 
-/// Insert a new swap
-pub async fn insert_swap(pool: &PgPool, swap: &Swap) -> Result<()> {
+/// Map a `swaps` row selected with numeric columns cast to text into a Swap
+fn swap_from_row(r: &PgRow) -> Swap {
+    let amount0_str: String = r.get(6);
+    let amount1_str: String = r.get(7);
+    let sqrt_price_str: Option<String> = r.get(8);
+    let liquidity_str: Option<String> = r.get(10);
+    Swap {
+        id: r.get(0),
+        tx_hash: r.get(1),
+        log_index: r.get(2),
+        block_number: r.get(3),
+        pool_id: r.get(4),
+        sender: r.get(5),
+        amount0: amount0_str.parse::<I256>().unwrap_or_default(),
+        amount1: amount1_str.parse::<I256>().unwrap_or_default(),
+        sqrt_price_x96: sqrt_price_str.and_then(|p| U256::from_str_radix(&p, 10).ok()),
+        tick: r.get(9),
+        liquidity: liquidity_str.and_then(|l| U256::from_str_radix(&l, 10).ok()),
+        fee: r.get(11),
+        timestamp: r.get(12),
+    }
+}
+
+/// Insert a new swap, returning whether it was not already stored
+pub async fn insert_swap(pool: &PgPool, swap: &Swap) -> Result<bool> {
     let amount0_str = swap.amount0.to_string();
     let amount1_str = swap.amount1.to_string();
+    let sqrt_price_str = swap.sqrt_price_x96.map(|p| p.to_string());
+    let liquidity_str = swap.liquidity.map(|l| l.to_string());
 
-    sqlx::query(
+    let result = sqlx::query(
         r#"
-        INSERT INTO swaps (tx_hash, pool_id, amount0, amount1, timestamp)
-        VALUES ($1, $2, $3::numeric, $4::numeric, $5)
-        ON CONFLICT (tx_hash, pool_id) DO NOTHING
+        INSERT INTO swaps (
+            tx_hash, log_index, block_number, pool_id, sender, amount0, amount1,
+            sqrt_price_x96, tick, liquidity, fee, timestamp
+        )
+        VALUES ($1, $2, $3, $4, $5, $6::numeric, $7::numeric, $8::numeric, $9, $10::numeric, $11, $12)
+        ON CONFLICT (tx_hash, log_index) DO NOTHING
         "#,
     )
     .bind(&swap.tx_hash)
+    .bind(swap.log_index)
+    .bind(swap.block_number)
     .bind(&swap.pool_id)
+    .bind(&swap.sender)
     .bind(&amount0_str)
     .bind(&amount1_str)
+    .bind(&sqrt_price_str)
+    .bind(swap.tick)
+    .bind(&liquidity_str)
+    .bind(swap.fee)
     .bind(swap.timestamp)
     .execute(pool)
     .await
     .context("Failed to insert swap")?;
 
-    Ok(())
+    Ok(result.rows_affected() > 0)
 }
 
 /// Get swaps for a pool since a specific timestamp
+///
+/// Swaps still waiting for their log index to be backfilled are left out.
 pub async fn get_swaps_for_pool(
     pool: &PgPool,
     pool_id: &str,
@@ -252,10 +758,11 @@ pub async fn get_swaps_for_pool(
 ) -> Result<Vec<Swap>> {
     let rows = sqlx::query(
         r#"
-        SELECT id, tx_hash, pool_id, amount0::text, amount1::text, timestamp
+        SELECT id, tx_hash, log_index, block_number, pool_id, sender, amount0::text,
+               amount1::text, sqrt_price_x96::text, tick, liquidity::text, fee, timestamp
         FROM swaps
-        WHERE pool_id = $1 AND timestamp >= $2
-        ORDER BY timestamp ASC
+        WHERE pool_id = $1 AND timestamp >= $2 AND log_index IS NOT NULL
+        ORDER BY timestamp ASC, block_number ASC, log_index ASC
         "#,
     )
     .bind(pool_id)
@@ -264,21 +771,241 @@ pub async fn get_swaps_for_pool(
     .await
     .context("Failed to get swaps for pool")?;
 
-    Ok(rows
-        .into_iter()
-        .map(|r| {
-            let amount0_str: String = r.get(3);
-            let amount1_str: String = r.get(4);
-            Swap {
-                id: r.get(0),
-                tx_hash: r.get(1),
-                pool_id: r.get(2),
-                amount0: amount0_str.parse::<I256>().unwrap_or_default(),
-                amount1: amount1_str.parse::<I256>().unwrap_or_default(),
-                timestamp: r.get(5),
-            }
-        })
-        .collect())
+    Ok(rows.iter().map(swap_from_row).collect())
+}
+
+/// Get up to `limit` swaps of a pool from `since` in chain order, after the swap `after`
+///
+/// Pass the last swap of a page as `after` to read the next one.
+pub async fn get_swaps_page(
+    pool: &PgPool,
+    pool_id: &str,
+    since: DateTime<Utc>,
+    after: Option<&Swap>,
+    limit: i64,
+) -> Result<Vec<Swap>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, tx_hash, log_index, block_number, pool_id, sender, amount0::text,
+               amount1::text, sqrt_price_x96::text, tick, liquidity::text, fee, timestamp
+        FROM swaps
+        WHERE pool_id = $1 AND timestamp >= $2 AND log_index IS NOT NULL
+          AND ($3::timestamptz IS NULL
+               OR (timestamp, COALESCE(block_number, -1), log_index) > ($3, $4, $5))
+        ORDER BY timestamp ASC, COALESCE(block_number, -1) ASC, log_index ASC
+        LIMIT $6
+        "#,
+    )
+    .bind(pool_id)
+    .bind(since)
+    .bind(after.map(|swap| swap.timestamp))
+    .bind(after.map(|swap| swap.block_number.unwrap_or(-1)))
+    .bind(after.map(|swap| swap.log_index))
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .context("Failed to get swaps page for pool")?;
+
+    Ok(rows.iter().map(swap_from_row).collect())
+}
+
+/// Get the last swap with a recorded post-swap price at or before `at`
+pub async fn get_latest_swap_for_pool(
+    pool: &PgPool,
+    pool_id: &str,
+    at: DateTime<Utc>,
+) -> Result<Option<Swap>> {
+    let row = sqlx::query(
+        r#"
+        SELECT id, tx_hash, log_index, block_number, pool_id, sender, amount0::text,
+               amount1::text, sqrt_price_x96::text, tick, liquidity::text, fee, timestamp
+        FROM swaps
+        WHERE pool_id = $1 AND timestamp <= $2 AND sqrt_price_x96 IS NOT NULL
+          AND log_index IS NOT NULL
+        ORDER BY timestamp DESC, block_number DESC, log_index DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(pool_id)
+    .bind(at)
+    .fetch_optional(pool)
+    .await
+    .context("Failed to get latest swap for pool")?;
+
+    Ok(row.as_ref().map(swap_from_row))
+}
+
+/// Get the last swap with a recorded post-swap price logged before a given log
+///
+/// Swaps are ordered by block number and log index, so swaps earlier in the same block
+/// count and later ones do not. Swaps without a block number are left out.
+pub async fn get_latest_swap_before_log(
+    pool: &PgPool,
+    pool_id: &str,
+    block_number: i64,
+    log_index: i64,
+) -> Result<Option<Swap>> {
+    let row = sqlx::query(
+        r#"
+        SELECT id, tx_hash, log_index, block_number, pool_id, sender, amount0::text,
+               amount1::text, sqrt_price_x96::text, tick, liquidity::text, fee, timestamp
+        FROM swaps
+        WHERE pool_id = $1 AND sqrt_price_x96 IS NOT NULL AND log_index IS NOT NULL
+          AND block_number IS NOT NULL AND (block_number, log_index) < ($2, $3)
+        ORDER BY block_number DESC, log_index DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(pool_id)
+    .bind(block_number)
+    .bind(log_index)
+    .fetch_optional(pool)
+    .await
+    .context("Failed to get latest swap before log")?;
+
+    Ok(row.as_ref().map(swap_from_row))
+}
+
+/// Get the transactions of swaps stored before log indexes were tracked
+pub async fn get_unindexed_swap_txs(pool: &PgPool) -> Result<Vec<String>> {
+    let rows = sqlx::query(
+        "SELECT DISTINCT tx_hash FROM swaps WHERE log_index IS NULL ORDER BY tx_hash",
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to get unindexed swaps")?;
+
+    Ok(rows.iter().map(|r| r.get(0)).collect())
+}
+
+/// Fill in a stored swap that has no log index from its re-fetched log or subgraph entity
+///
+/// The row is matched on transaction, pool and amounts, and takes the log index along
+/// with whichever block and pool state fields the source provides. Returns whether a row was updated; nothing is updated when
+/// another row already holds the log.
+pub async fn backfill_swap_log_index(pool: &PgPool, swap: &Swap) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE swaps
+        SET log_index = $5,
+            block_number = COALESCE($6, block_number),
+            sender = COALESCE($7, sender),
+            sqrt_price_x96 = COALESCE($8::numeric, sqrt_price_x96),
+            tick = COALESCE($9, tick),
+            liquidity = COALESCE($10::numeric, liquidity),
+            fee = COALESCE($11, fee)
+        WHERE id = (
+            SELECT id FROM swaps
+            WHERE tx_hash = $1 AND pool_id = $2 AND log_index IS NULL
+              AND amount0 = $3::numeric AND amount1 = $4::numeric
+            ORDER BY id
+            LIMIT 1
+        )
+          AND NOT EXISTS (SELECT 1 FROM swaps WHERE tx_hash = $1 AND log_index = $5)
+        "#,
+    )
+    .bind(&swap.tx_hash)
+    .bind(&swap.pool_id)
+    .bind(swap.amount0.to_string())
+    .bind(swap.amount1.to_string())
+    .bind(swap.log_index)
+    .bind(swap.block_number)
+    .bind(&swap.sender)
+    .bind(swap.sqrt_price_x96.map(|p| p.to_string()))
+    .bind(swap.tick)
+    .bind(swap.liquidity.map(|l| l.to_string()))
+    .bind(swap.fee)
+    .execute(pool)
+    .await
+    .context("Failed to backfill swap log index")?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Drop a transaction's swaps that have no log index and could not be backfilled
+///
+/// The swap checkpoints of their pools and the log checkpoint are rewound to before them,
+/// so the next sync re-ingests them with their log index. Returns the number of dropped
+/// swaps.
+pub async fn discard_unindexed_swaps(pool: &PgPool, tx_hash: &str) -> Result<u64> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO sync_state (entity_type, pool_id, last_block, last_timestamp)
+        SELECT $2, pool_id, MIN(block_number), MIN(timestamp)
+        FROM swaps
+        WHERE tx_hash = $1 AND log_index IS NULL
+        GROUP BY pool_id
+        ON CONFLICT (entity_type, pool_id) DO UPDATE
+        SET last_block = LEAST(sync_state.last_block, EXCLUDED.last_block),
+            last_timestamp = LEAST(sync_state.last_timestamp, EXCLUDED.last_timestamp),
+            updated_at = NOW()
+        "#,
+    )
+    .bind(tx_hash)
+    .bind(SyncEntity::Swaps.as_str())
+    .execute(&mut *tx)
+    .await
+    .context("Failed to rewind swap checkpoints")?;
+
+    sqlx::query(
+        r#"
+        UPDATE sync_state
+        SET last_block = unindexed.first_block - 1, updated_at = NOW()
+        FROM (
+            SELECT MIN(block_number) AS first_block
+            FROM swaps
+            WHERE tx_hash = $1 AND log_index IS NULL
+        ) unindexed
+        WHERE sync_state.entity_type = $2 AND sync_state.pool_id = ''
+          AND sync_state.last_block >= unindexed.first_block
+        "#,
+    )
+    .bind(tx_hash)
+    .bind(SyncEntity::Logs.as_str())
+    .execute(&mut *tx)
+    .await
+    .context("Failed to rewind log checkpoint")?;
+
+    let deleted = sqlx::query("DELETE FROM swaps WHERE tx_hash = $1 AND log_index IS NULL")
+        .bind(tx_hash)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to discard unindexed swaps")?
+        .rows_affected();
+
+    tx.commit().await.context("Failed to commit discarded swaps")?;
+
+    Ok(deleted)
+}
+
+// ============================================================================
+// Donation Operations
+// ============================================================================
+
+/// Insert a new donation, returning whether it was not already stored
+pub async fn insert_donation(pool: &PgPool, donation: &Donation) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO donations (tx_hash, log_index, block_number, pool_id, sender, amount0, amount1, timestamp)
+        VALUES ($1, $2, $3, $4, $5, $6::numeric, $7::numeric, $8)
+        ON CONFLICT (tx_hash, log_index) DO NOTHING
+        "#,
+    )
+    .bind(&donation.tx_hash)
+    .bind(donation.log_index)
+    .bind(donation.block_number)
+    .bind(&donation.pool_id)
+    .bind(&donation.sender)
+    .bind(donation.amount0.to_string())
+    .bind(donation.amount1.to_string())
+    .bind(donation.timestamp)
+    .execute(pool)
+    .await
+    .context("Failed to insert donation")?;
+
+    Ok(result.rows_affected() > 0)
 }
 
 // ============================================================================
@@ -344,3 +1071,305 @@ pub async fn get_snapshots_for_position(
         })
         .collect())
 }
+
+// ============================================================================
+// Sync State Operations
+// ============================================================================
+
+/// Get the sync checkpoint for an entity type (use an empty `pool_id` for global syncs)
+pub async fn get_sync_state(
+    pool: &PgPool,
+    entity: SyncEntity,
+    pool_id: &str,
+) -> Result<Option<SyncState>> {
+    let result = sqlx::query_as::<_, SyncState>(
+        r#"
+        SELECT entity_type, pool_id, last_block, last_timestamp, updated_at
+        FROM sync_state
+        WHERE entity_type = $1 AND pool_id = $2
+        "#,
+    )
+    .bind(entity.as_str())
+    .bind(pool_id)
+    .fetch_optional(pool)
+    .await
+    .context("Failed to get sync state")?;
+
+    Ok(result)
+}
+
+/// Record the sync checkpoint for an entity type
+///
+/// Checkpoints never move backwards: an older timestamp than the stored one is ignored.
+pub async fn upsert_sync_state(
+    pool: &PgPool,
+    entity: SyncEntity,
+    pool_id: &str,
+    last_block: Option<i64>,
+    last_timestamp: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO sync_state (entity_type, pool_id, last_block, last_timestamp, updated_at)
+        VALUES ($1, $2, $3, $4, NOW())
+        ON CONFLICT (entity_type, pool_id) DO UPDATE
+        SET last_block = COALESCE(EXCLUDED.last_block, sync_state.last_block),
+            last_timestamp = EXCLUDED.last_timestamp,
+            updated_at = NOW()
+        WHERE EXCLUDED.last_timestamp >= sync_state.last_timestamp
+        "#,
+    )
+    .bind(entity.as_str())
+    .bind(pool_id)
+    .bind(last_block)
+    .bind(last_timestamp)
+    .execute(pool)
+    .await
+    .context("Failed to upsert sync state")?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Indexed Block Operations
+// ============================================================================
+
+/// Record (or replace) the hash of an ingested block
+pub async fn upsert_indexed_block(pool: &PgPool, block: &IndexedBlock) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO indexed_blocks (block_number, block_hash, parent_hash, timestamp)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (block_number) DO UPDATE
+        SET block_hash = EXCLUDED.block_hash,
+            parent_hash = EXCLUDED.parent_hash,
+            timestamp = EXCLUDED.timestamp
+        "#,
+    )
+    .bind(block.block_number)
+    .bind(&block.block_hash)
+    .bind(&block.parent_hash)
+    .bind(block.timestamp)
+    .execute(pool)
+    .await
+    .context("Failed to upsert indexed block")?;
+
+    Ok(())
+}
+
+/// Get an ingested block by number
+pub async fn get_indexed_block(pool: &PgPool, block_number: i64) -> Result<Option<IndexedBlock>> {
+    let result = sqlx::query_as::<_, IndexedBlock>(
+        r#"
+        SELECT block_number, block_hash, parent_hash, timestamp
+        FROM indexed_blocks
+        WHERE block_number = $1
+        "#,
+    )
+    .bind(block_number)
+    .fetch_optional(pool)
+    .await
+    .context("Failed to get indexed block")?;
+
+    Ok(result)
+}
+
+/// Get all tracked blocks, newest first
+pub async fn get_indexed_blocks(pool: &PgPool) -> Result<Vec<IndexedBlock>> {
+    let result = sqlx::query_as::<_, IndexedBlock>(
+        r#"
+        SELECT block_number, block_hash, parent_hash, timestamp
+        FROM indexed_blocks
+        ORDER BY block_number DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to get indexed blocks")?;
+
+    Ok(result)
+}
+
+/// Stop tracking blocks, and the owner changes made in them, below a block number (they are
+/// considered final)
+pub async fn prune_indexed_blocks(pool: &PgPool, below: i64) -> Result<()> {
+    sqlx::query("DELETE FROM indexed_blocks WHERE block_number < $1")
+        .bind(below)
+        .execute(pool)
+        .await
+        .context("Failed to prune indexed blocks")?;
+
+    sqlx::query("DELETE FROM position_owner_changes WHERE block_number < $1")
+        .bind(below)
+        .execute(pool)
+        .await
+        .context("Failed to prune position owner changes")?;
+
+    Ok(())
+}
+
+/// Roll back everything ingested above a fork point after a chain reorganization
+///
+/// Deletes liquidity events, swaps, donations and tracked blocks above `fork`, recomputes
+/// (or removes) the positions those liquidity events belonged to, drops snapshots taken
+/// after the fork in affected pools, restores the holders replaced by orphaned NFT
+/// transfers, removes pools created above the fork, and rewinds the log checkpoint to
+/// `fork`. Returns the number of deleted events.
+pub async fn rollback_above_block(pool: &PgPool, fork: &IndexedBlock) -> Result<u64> {
+    let mut tx = pool.begin().await.context("Failed to begin transaction")?;
+
+    let orphaned_events = sqlx::query(
+        r#"
+        DELETE FROM liquidity_events
+        WHERE block_number > $1
+        RETURNING owner, pool_id, tick_lower, tick_upper, salt
+        "#,
+    )
+    .bind(fork.block_number)
+    .fetch_all(&mut *tx)
+    .await
+    .context("Failed to roll back liquidity events")?;
+
+    let orphaned_swaps = sqlx::query("DELETE FROM swaps WHERE block_number > $1 RETURNING pool_id")
+        .bind(fork.block_number)
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to roll back swaps")?;
+
+    let orphaned_donations =
+        sqlx::query("DELETE FROM donations WHERE block_number > $1 RETURNING pool_id")
+            .bind(fork.block_number)
+            .fetch_all(&mut *tx)
+            .await
+            .context("Failed to roll back donations")?;
+
+    let mut keys: Vec<(String, String, i32, i32, String)> = orphaned_events
+        .iter()
+        .map(|r| (r.get(0), r.get(1), r.get(2), r.get(3), r.get(4)))
+        .collect();
+    keys.sort();
+    keys.dedup();
+
+    for (manager, pool_id, tick_lower, tick_upper, salt) in &keys {
+        sqlx::query(
+            r#"
+            DELETE FROM positions p
+            WHERE manager = $1 AND pool_id = $2 AND tick_lower = $3 AND tick_upper = $4 AND salt = $5
+              AND NOT EXISTS (
+                SELECT 1 FROM liquidity_events e
+                WHERE e.owner = p.manager AND e.pool_id = p.pool_id AND e.tick_lower = p.tick_lower
+                  AND e.tick_upper = p.tick_upper AND e.salt = p.salt
+              )
+            "#,
+        )
+        .bind(manager)
+        .bind(pool_id)
+        .bind(tick_lower)
+        .bind(tick_upper)
+        .bind(salt)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to remove orphaned position")?;
+
+        sqlx::query(
+            r#"
+            UPDATE positions p
+            SET liquidity = GREATEST(s.total, 0),
+                closed = s.total <= 0,
+                created_at = s.first_timestamp
+            FROM (
+                SELECT SUM(liquidity_delta) AS total, MIN(timestamp) AS first_timestamp
+                FROM liquidity_events
+                WHERE owner = $1 AND pool_id = $2 AND tick_lower = $3 AND tick_upper = $4 AND salt = $5
+            ) s
+            WHERE p.manager = $1 AND p.pool_id = $2 AND p.tick_lower = $3 AND p.tick_upper = $4
+              AND p.salt = $5 AND s.total IS NOT NULL
+            "#,
+        )
+        .bind(manager)
+        .bind(pool_id)
+        .bind(tick_lower)
+        .bind(tick_upper)
+        .bind(salt)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to recompute position after rollback")?;
+    }
+
+    let mut affected_pools: Vec<String> = orphaned_events
+        .iter()
+        .chain(&orphaned_swaps)
+        .chain(&orphaned_donations)
+        .map(|r| r.get("pool_id"))
+        .collect();
+    affected_pools.sort();
+    affected_pools.dedup();
+
+    sqlx::query(
+        r#"
+        DELETE FROM position_snapshots
+        WHERE timestamp > $1
+          AND position_id IN (SELECT id FROM positions WHERE pool_id = ANY($2))
+        "#,
+    )
+    .bind(fork.timestamp)
+    .bind(&affected_pools)
+    .execute(&mut *tx)
+    .await
+    .context("Failed to roll back position snapshots")?;
+
+    sqlx::query(
+        r#"
+        UPDATE positions p
+        SET owner = c.previous_owner
+        FROM (
+            SELECT DISTINCT ON (nft_id) nft_id, previous_owner
+            FROM position_owner_changes
+            WHERE block_number > $1
+            ORDER BY nft_id, block_number, id
+        ) c
+        WHERE p.nft_id = c.nft_id
+        "#,
+    )
+    .bind(fork.block_number)
+    .execute(&mut *tx)
+    .await
+    .context("Failed to restore position owners")?;
+
+    sqlx::query("DELETE FROM position_owner_changes WHERE block_number > $1")
+        .bind(fork.block_number)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to roll back position owner changes")?;
+
+    // Cascades to anything else still referencing the orphaned pools
+    sqlx::query("DELETE FROM pools WHERE created_block > $1")
+        .bind(fork.block_number)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to roll back pools")?;
+
+    sqlx::query("DELETE FROM indexed_blocks WHERE block_number > $1")
+        .bind(fork.block_number)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to roll back indexed blocks")?;
+
+    sqlx::query(
+        r#"
+        UPDATE sync_state
+        SET last_block = $2, last_timestamp = $3, updated_at = NOW()
+        WHERE entity_type = $1 AND pool_id = '' AND last_block > $2
+        "#,
+    )
+    .bind(SyncEntity::Logs.as_str())
+    .bind(fork.block_number)
+    .bind(fork.timestamp)
+    .execute(&mut *tx)
+    .await
+    .context("Failed to rewind log checkpoint")?;
+
+    tx.commit().await.context("Failed to commit rollback")?;
+
+    Ok((orphaned_events.len() + orphaned_swaps.len() + orphaned_donations.len()) as u64)
+}
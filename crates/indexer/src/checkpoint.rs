@@ -0,0 +1,91 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+
+/// Point in the event stream that a sync can resume from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub timestamp: DateTime<Utc>,
+    pub block: Option<i64>,
+}
+
+impl Checkpoint {
+    /// Parse a checkpoint from subgraph timestamp and block number strings
+    pub fn parse(timestamp: &str, block: Option<&str>) -> Result<Self> {
+        let timestamp = timestamp.parse::<i64>().context("Failed to parse timestamp")?;
+        let timestamp =
+            DateTime::from_timestamp(timestamp, 0).ok_or_else(|| anyhow!("Invalid timestamp"))?;
+        let block = block
+            .map(|b| b.parse::<i64>().context("Failed to parse block number"))
+            .transpose()?;
+
+        Ok(Self { timestamp, block })
+    }
+}
+
+/// Tracks how far a sync run got so the next run resumes without gaps
+///
+/// Resumes are inclusive (`timestamp_gte`), so the checkpoint may safely point at an
+/// event that was already stored; inserts are idempotent.
+#[derive(Debug, Default)]
+pub struct SyncProgress {
+    latest: Option<Checkpoint>,
+    earliest_failure: Option<Checkpoint>,
+}
+
+impl SyncProgress {
+    /// Record an event that was stored successfully
+    pub fn record_success(&mut self, checkpoint: Checkpoint) {
+        if self.latest.is_none_or(|latest| checkpoint.timestamp > latest.timestamp) {
+            self.latest = Some(checkpoint);
+        }
+    }
+
+    /// Record an event that failed to store and must be retried next run
+    pub fn record_failure(&mut self, checkpoint: Checkpoint) {
+        if self.earliest_failure.is_none_or(|failure| checkpoint.timestamp < failure.timestamp) {
+            self.earliest_failure = Some(checkpoint);
+        }
+    }
+
+    /// The checkpoint to persist: the earliest failure if any, otherwise the latest success
+    pub fn resume_point(&self) -> Option<Checkpoint> {
+        self.earliest_failure.or(self.latest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint(timestamp: i64) -> Checkpoint {
+        Checkpoint { timestamp: DateTime::from_timestamp(timestamp, 0).unwrap(), block: None }
+    }
+
+    #[test]
+    fn test_resume_point_is_latest_success() {
+        let mut progress = SyncProgress::default();
+        progress.record_success(checkpoint(200));
+        progress.record_success(checkpoint(100));
+
+        assert_eq!(progress.resume_point(), Some(checkpoint(200)));
+    }
+
+    #[test]
+    fn test_resume_point_stops_at_earliest_failure() {
+        let mut progress = SyncProgress::default();
+        progress.record_success(checkpoint(300));
+        progress.record_failure(checkpoint(250));
+        progress.record_failure(checkpoint(150));
+
+        assert_eq!(progress.resume_point(), Some(checkpoint(150)));
+    }
+
+    #[test]
+    fn test_parse_checkpoint() {
+        let parsed = Checkpoint::parse("1700000000", Some("42")).unwrap();
+        assert_eq!(parsed.timestamp.timestamp(), 1_700_000_000);
+        assert_eq!(parsed.block, Some(42));
+
+        assert!(Checkpoint::parse("not-a-number", None).is_err());
+    }
+}
@@ -0,0 +1,321 @@
+use alloy::primitives::{Address, I256, U256};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use stillwater_models::{LiquidityEvent, Permissions, Pool, Swap, Token, DEFAULT_SALT};
+
+use crate::checkpoint::Checkpoint;
+use crate::linking::NftLink;
+use crate::types::{ModifyLiquidityResponse, PoolResponse, SwapResponse, TokenResponse};
+
+/// Liquidity event converted from the subgraph, with the holder of the position it creates
+#[derive(Debug, Clone)]
+pub struct ConvertedLiquidityEvent {
+    pub event: LiquidityEvent,
+    /// Holder of the derived position
+    pub holder: String,
+}
+
+/// Convert a subgraph pool into a pool row
+pub fn pool_from_response(pool_resp: &PoolResponse) -> Result<Pool> {
+    let fee_tier = pool_resp.fee.parse::<i32>().context("Failed to parse fee")?;
+    let tick_spacing =
+        pool_resp.tick_spacing.parse::<i32>().context("Failed to parse tick spacing")?;
+
+    let created = match pool_resp.created_at_timestamp.as_deref() {
+        Some(timestamp) => {
+            Some(Checkpoint::parse(timestamp, pool_resp.created_at_block_number.as_deref())?)
+        }
+        None => None,
+    };
+
+    let permissions = pool_resp
+        .hooks
+        .as_deref()
+        .map(|hooks| hooks.parse::<Address>().context("Failed to parse hooks"))
+        .transpose()?
+        .map(Permissions::from_hooks);
+
+    let pool = Pool {
+        pool_id: pool_resp.id.clone(),
+        token0: pool_resp.token0.id.clone(),
+        token1: pool_resp.token1.id.clone(),
+        fee_tier,
+        tick_spacing,
+        hooks: pool_resp.hooks.as_ref().map(|h| h.to_lowercase()),
+        hook_flags: permissions.as_ref().map(|p| p.flags().into()),
+        hook_return_delta: permissions.as_ref().is_some_and(|p| p.has_return_delta()),
+        dynamic_fee: Pool::is_dynamic_fee(fee_tier),
+        created_block: created.and_then(|c| c.block),
+        // The subgraph only tracks the current price; the log indexer fills these in
+        initial_sqrt_price_x96: None,
+        initial_tick: None,
+        created_at: created.map_or_else(Utc::now, |c| c.timestamp),
+    };
+
+    // Cross-check the subgraph id against the one the PoolManager derives from the key
+    if pool.hooks.is_some() {
+        let key = pool.key()?;
+        key.validate()?;
+        let id = key.to_id().to_string();
+        if !id.eq_ignore_ascii_case(&pool.pool_id) {
+            bail!("Pool id {} does not match its key (expected {})", pool.pool_id, id);
+        }
+    }
+
+    Ok(pool)
+}
+
+/// Convert subgraph token metadata, if the response includes it
+pub fn token_from_response(token_resp: &TokenResponse) -> Result<Option<Token>> {
+    let Some(decimals) = token_resp.decimals.as_deref() else {
+        return Ok(None);
+    };
+    let decimals = decimals.parse::<u8>().context("Failed to parse token decimals")?;
+
+    Ok(Some(Token {
+        address: token_resp.id.to_lowercase(),
+        symbol: token_resp.symbol.clone().unwrap_or_default(),
+        name: token_resp.name.clone().unwrap_or_default(),
+        decimals,
+    }))
+}
+
+/// Convert a subgraph ModifyLiquidity event, optionally linked to a PositionManager NFT
+pub fn liquidity_event_from_response(
+    event_resp: &ModifyLiquidityResponse,
+    link: Option<&NftLink>,
+) -> Result<ConvertedLiquidityEvent> {
+    let tick_lower = event_resp.tick_lower.parse::<i32>().context("Failed to parse tick_lower")?;
+    let tick_upper = event_resp.tick_upper.parse::<i32>().context("Failed to parse tick_upper")?;
+    let liquidity_delta =
+        event_resp.amount.parse::<I256>().context("Failed to parse liquidity delta")?;
+    let checkpoint =
+        Checkpoint::parse(&event_resp.timestamp, event_resp.transaction.block_number.as_deref())?;
+
+    // NFT positions are owned by the PositionManager in the PoolManager, keyed by
+    // salt = tokenId. Unlinked events fall back to the transaction origin.
+    let (owner, salt, nft_id, holder) = match link {
+        Some(link) => (
+            event_resp.sender.clone().unwrap_or_else(|| event_resp.owner.clone()),
+            link.salt()?,
+            Some(link.token_id.clone()),
+            link.holder.clone(),
+        ),
+        None => (
+            event_resp.owner.clone(),
+            // The subgraph does not expose the position salt
+            DEFAULT_SALT.to_string(),
+            None,
+            event_resp.owner.clone(),
+        ),
+    };
+
+    let event = LiquidityEvent {
+        id: 0, // Will be auto-generated
+        event_id: event_resp.id.clone(),
+        tx_hash: event_resp.transaction.id.clone(),
+        block_number: checkpoint.block,
+        pool_id: event_resp.pool.id.clone(),
+        owner,
+        tick_lower,
+        tick_upper,
+        salt,
+        liquidity_delta,
+        timestamp: checkpoint.timestamp,
+        nft_id,
+    };
+
+    Ok(ConvertedLiquidityEvent { event, holder })
+}
+
+/// Log index of a subgraph swap, from `logIndex` or its `txHash-logIndex` (or
+/// `txHash#logIndex`) entity id
+fn swap_log_index(swap_resp: &SwapResponse) -> Result<i64> {
+    let log_index = match swap_resp.log_index.as_deref() {
+        Some(log_index) => log_index,
+        None => swap_resp
+            .id
+            .rsplit_once(['-', '#'])
+            .map(|(_, log_index)| log_index)
+            .ok_or_else(|| anyhow!("Swap {} has no log index", swap_resp.id))?,
+    };
+    log_index.parse::<i64>().context("Failed to parse log index")
+}
+
+/// Convert a subgraph swap into a swap row
+pub fn swap_from_response(swap_resp: &SwapResponse) -> Result<Swap> {
+    let amount0 = swap_resp.amount0.parse::<I256>().context("Failed to parse amount0")?;
+    let amount1 = swap_resp.amount1.parse::<I256>().context("Failed to parse amount1")?;
+    let timestamp =
+        swap_resp.transaction.timestamp.parse::<i64>().context("Failed to parse timestamp")?;
+    let swap_time =
+        DateTime::from_timestamp(timestamp, 0).ok_or_else(|| anyhow!("Invalid timestamp"))?;
+
+    let tx_hash = swap_resp.transaction.id.clone().unwrap_or_else(|| swap_resp.id.clone());
+    let block_number =
+        swap_resp.transaction.block_number.as_deref().and_then(|b| b.parse::<i64>().ok());
+    let log_index = swap_log_index(swap_resp)?;
+    let sqrt_price_x96 = swap_resp
+        .sqrt_price_x96
+        .as_deref()
+        .map(|p| U256::from_str_radix(p, 10).context("Failed to parse sqrtPriceX96"))
+        .transpose()?;
+    let tick = swap_resp
+        .tick
+        .as_deref()
+        .map(|t| t.parse::<i32>().context("Failed to parse tick"))
+        .transpose()?;
+    // Static-fee pools always charge their PoolKey fee; dynamic fees are set per swap by
+    // hooks and are not indexed
+    let fee = swap_resp
+        .pool
+        .fee
+        .as_deref()
+        .map(|f| f.parse::<i32>().context("Failed to parse fee"))
+        .transpose()?
+        .filter(|fee| !Pool::is_dynamic_fee(*fee));
+
+    Ok(Swap {
+        id: 0, // Will be auto-generated
+        tx_hash,
+        log_index,
+        block_number,
+        pool_id: swap_resp.pool.id.clone(),
+        sender: swap_resp.sender.clone(),
+        amount0,
+        amount1,
+        sqrt_price_x96,
+        tick,
+        // Not indexed by the subgraph; only the log indexer stores post-swap liquidity
+        liquidity: None,
+        fee,
+        timestamp: swap_time,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ModifyLiquiditiesData, SwapsData};
+    use stillwater_models::DYNAMIC_FEE_FLAG;
+
+    const FIXTURE: &str = include_str!("../fixtures/subgraph.json");
+
+    fn fixture_events() -> Vec<ModifyLiquidityResponse> {
+        let data: ModifyLiquiditiesData = serde_json::from_str(FIXTURE).unwrap();
+        data.into()
+    }
+
+    fn fixture_swaps() -> Vec<SwapResponse> {
+        let data: SwapsData = serde_json::from_str(FIXTURE).unwrap();
+        data.into()
+    }
+
+    #[test]
+    fn test_pool_from_response() {
+        let pool = pool_from_response(&fixture_events()[0].pool).unwrap();
+
+        assert_eq!(pool.fee_tier, 3000);
+        assert_eq!(pool.tick_spacing, 60);
+        assert!(pool.token0 < pool.token1);
+        assert_eq!(pool.hooks.as_deref(), Some("0x0000000000000000000000000000000000000000"));
+        assert!(!pool.dynamic_fee);
+        assert_eq!(pool.hook_flags, Some(0));
+        assert!(!pool.hook_return_delta);
+        assert_eq!(pool.created_block, Some(4999000));
+        assert_eq!(pool.created_at.timestamp(), 1699990000);
+    }
+
+    #[test]
+    fn test_pool_id_mismatch_is_rejected() {
+        let mut pool_resp = fixture_events()[0].pool.clone();
+        pool_resp.fee = "500".to_string();
+
+        assert!(pool_from_response(&pool_resp).is_err());
+    }
+
+    #[test]
+    fn test_token_from_response() {
+        let pool_resp = &fixture_events()[0].pool;
+        let token = token_from_response(&pool_resp.token1).unwrap().unwrap();
+
+        assert_eq!(token.symbol, "WETH");
+        assert_eq!(token.decimals, 18);
+
+        let bare = TokenResponse { id: "0xabc".to_string(), symbol: None, name: None, decimals: None };
+        assert!(token_from_response(&bare).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_unlinked_liquidity_event_belongs_to_origin() {
+        let event_resp = &fixture_events()[0];
+        let converted = liquidity_event_from_response(event_resp, None).unwrap();
+
+        assert_eq!(converted.event.owner, event_resp.owner);
+        assert_eq!(converted.event.salt, DEFAULT_SALT);
+        assert_eq!(converted.event.block_number, Some(5000000));
+        assert_eq!(converted.event.liquidity_delta, I256::try_from(1_000_000_000u64).unwrap());
+        assert_eq!(converted.event.nft_id, None);
+        assert_eq!(converted.holder, event_resp.owner);
+    }
+
+    #[test]
+    fn test_linked_liquidity_event_belongs_to_position_manager() {
+        let event_resp = &fixture_events()[0];
+        let link = NftLink { token_id: "42".to_string(), holder: "0xholder".to_string() };
+        let converted = liquidity_event_from_response(event_resp, Some(&link)).unwrap();
+
+        assert_eq!(Some(&converted.event.owner), event_resp.sender.as_ref());
+        assert_eq!(converted.event.salt, link.salt().unwrap());
+        assert_eq!(converted.event.nft_id.as_deref(), Some("42"));
+        assert_eq!(converted.holder, "0xholder");
+    }
+
+    #[test]
+    fn test_swap_from_response() {
+        let swap = swap_from_response(&fixture_swaps()[0]).unwrap();
+
+        assert_eq!(swap.amount0, I256::try_from(1000).unwrap());
+        assert_eq!(swap.amount1, I256::try_from(-990).unwrap());
+        assert_eq!(swap.block_number, Some(5000010));
+        assert_eq!(swap.log_index, 0);
+        assert_eq!(swap.sqrt_price_x96, Some(U256::from(1u128 << 96)));
+        assert_eq!(swap.tick, Some(0));
+        assert!(swap.sender.is_some());
+        assert!(swap.liquidity.is_none());
+        assert_eq!(swap.fee, Some(3000));
+        assert_eq!(swap.timestamp.timestamp(), 1700000120);
+    }
+
+    #[test]
+    fn test_swap_fee_unknown_for_dynamic_fee_pools() {
+        let mut swap_resp = fixture_swaps()[0].clone();
+        swap_resp.pool.fee = Some(DYNAMIC_FEE_FLAG.to_string());
+        assert_eq!(swap_from_response(&swap_resp).unwrap().fee, None);
+
+        swap_resp.pool.fee = None;
+        assert_eq!(swap_from_response(&swap_resp).unwrap().fee, None);
+    }
+
+    #[test]
+    fn test_swap_log_index_falls_back_to_entity_id() {
+        let mut swap_resp = fixture_swaps()[0].clone();
+        swap_resp.log_index = None;
+        swap_resp.id = format!("{}-12", swap_resp.transaction.id.clone().unwrap());
+        assert_eq!(swap_from_response(&swap_resp).unwrap().log_index, 12);
+
+        swap_resp.id = format!("{}#7", swap_resp.transaction.id.clone().unwrap());
+        assert_eq!(swap_from_response(&swap_resp).unwrap().log_index, 7);
+
+        swap_resp.id = "0xabc".to_string();
+        assert!(swap_from_response(&swap_resp).is_err());
+    }
+
+    #[test]
+    fn test_invalid_amount_is_rejected() {
+        let mut swap_resp = fixture_swaps()[0].clone();
+        swap_resp.amount0 = "not a number".to_string();
+
+        assert!(swap_from_response(&swap_resp).is_err());
+    }
+}
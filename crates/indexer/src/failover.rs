@@ -0,0 +1,381 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::queries;
+use crate::retry::RetryConfig;
+use crate::source::{GraphClient, IndexerSource};
+use crate::types::MetaData;
+
+/// Default number of blocks an endpoint may trail the freshest one before it is refused
+pub const DEFAULT_MAX_LAG_BLOCKS: u64 = 100;
+
+/// Default time between `_meta` freshness checks
+pub const DEFAULT_FRESHNESS_TTL: Duration = Duration::from_secs(60);
+
+/// Indexing status of one subgraph endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointStatus {
+    pub name: String,
+    /// Latest indexed block, if the `_meta` query succeeded
+    pub block: Option<u64>,
+    pub has_indexing_errors: bool,
+}
+
+impl EndpointStatus {
+    /// Whether the endpoint answered and reports no indexing errors
+    pub fn is_healthy(&self) -> bool {
+        self.block.is_some() && !self.has_indexing_errors
+    }
+}
+
+/// Order in which endpoints should be queried, freshest first
+///
+/// Unhealthy endpoints and endpoints more than `max_lag_blocks` behind the freshest healthy
+/// one are left out. Ties keep the configured order.
+pub fn rank_endpoints(statuses: &[EndpointStatus], max_lag_blocks: u64) -> Vec<usize> {
+    let Some(freshest) = statuses.iter().filter(|s| s.is_healthy()).filter_map(|s| s.block).max()
+    else {
+        return Vec::new();
+    };
+
+    let mut ranked: Vec<(usize, u64)> = statuses
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.is_healthy())
+        .filter_map(|(i, s)| Some((i, s.block?)))
+        .filter(|(_, block)| freshest - block <= max_lag_blocks)
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    ranked.into_iter().map(|(i, _)| i).collect()
+}
+
+struct Endpoint<S> {
+    name: String,
+    source: S,
+}
+
+struct Ranking {
+    checked_at: Instant,
+    order: Vec<usize>,
+}
+
+/// Subgraph source spread over several endpoints (gateway, self-hosted graph-node, studio)
+///
+/// Endpoints are ranked by their `_meta` block, refreshed every `freshness_ttl`. Queries go
+/// to the freshest healthy endpoint and fail over down the ranking on errors; endpoints with
+/// indexing errors or lagging more than `max_lag_blocks` behind are never queried.
+pub struct FailoverSource<S = GraphClient> {
+    endpoints: Vec<Endpoint<S>>,
+    max_lag_blocks: u64,
+    freshness_ttl: Duration,
+    ranking: Mutex<Option<Ranking>>,
+}
+
+impl FailoverSource<GraphClient> {
+    /// Create a failover source over subgraph URLs, sharing one retry policy
+    pub fn from_urls(urls: &[String], retry: RetryConfig) -> Self {
+        Self::new(
+            urls.iter()
+                .map(|url| (endpoint_name(url), GraphClient::with_retry_config(url.clone(), retry)))
+                .collect(),
+        )
+    }
+
+    /// Create a failover source from environment variables
+    ///
+    /// `GRAPH_API_URL` holds one or more comma-separated endpoints; `GRAPH_MAX_LAG_BLOCKS`
+    /// and `GRAPH_FRESHNESS_TTL_SECS` are optional.
+    pub fn from_env() -> Result<Self> {
+        let urls: Vec<String> = std::env::var("GRAPH_API_URL")
+            .context("GRAPH_API_URL must be set in environment")?
+            .split(',')
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .collect();
+        if urls.is_empty() {
+            return Err(anyhow!("GRAPH_API_URL must contain at least one endpoint"));
+        }
+
+        let mut source = Self::from_urls(&urls, RetryConfig::from_env()?);
+        if let Ok(lag) = std::env::var("GRAPH_MAX_LAG_BLOCKS") {
+            source.max_lag_blocks = lag.parse().context("Invalid GRAPH_MAX_LAG_BLOCKS")?;
+        }
+        if let Ok(ttl) = std::env::var("GRAPH_FRESHNESS_TTL_SECS") {
+            let ttl = ttl.parse().context("Invalid GRAPH_FRESHNESS_TTL_SECS")?;
+            source.freshness_ttl = Duration::from_secs(ttl);
+        }
+        Ok(source)
+    }
+}
+
+impl<S: IndexerSource + Sync> FailoverSource<S> {
+    /// Create a failover source over named endpoints, in order of preference
+    pub fn new(endpoints: Vec<(String, S)>) -> Self {
+        Self {
+            endpoints: endpoints
+                .into_iter()
+                .map(|(name, source)| Endpoint { name, source })
+                .collect(),
+            max_lag_blocks: DEFAULT_MAX_LAG_BLOCKS,
+            freshness_ttl: DEFAULT_FRESHNESS_TTL,
+            ranking: Mutex::new(None),
+        }
+    }
+
+    /// Set how many blocks an endpoint may trail the freshest one
+    pub fn with_max_lag_blocks(mut self, max_lag_blocks: u64) -> Self {
+        self.max_lag_blocks = max_lag_blocks;
+        self
+    }
+
+    /// Set how long a freshness check stays valid
+    pub fn with_freshness_ttl(mut self, freshness_ttl: Duration) -> Self {
+        self.freshness_ttl = freshness_ttl;
+        self
+    }
+
+    /// Query `_meta` on every endpoint
+    pub async fn check_endpoints(&self) -> Vec<EndpointStatus> {
+        let mut statuses = Vec::with_capacity(self.endpoints.len());
+        for endpoint in &self.endpoints {
+            let meta = match endpoint.source.execute(queries::META, json!({})).await {
+                Ok(data) => serde_json::from_value::<MetaData>(data)
+                    .context("Failed to parse _meta response")
+                    .map(|data| data.meta),
+                Err(e) => Err(e),
+            };
+
+            let status = match meta {
+                Ok(meta) => EndpointStatus {
+                    name: endpoint.name.clone(),
+                    block: Some(meta.block.number),
+                    has_indexing_errors: meta.has_indexing_errors,
+                },
+                Err(e) => {
+                    warn!(
+                        "Subgraph endpoint {} failed its freshness check: {:#}",
+                        endpoint.name, e
+                    );
+                    EndpointStatus {
+                        name: endpoint.name.clone(),
+                        block: None,
+                        has_indexing_errors: false,
+                    }
+                }
+            };
+            debug!("Subgraph endpoint status: {:?}", status);
+            statuses.push(status);
+        }
+        statuses
+    }
+
+    /// Endpoint order for the next query, re-checking freshness when the ranking expired
+    async fn ranking(&self) -> Result<Vec<usize>> {
+        if let Some(ranking) = self.ranking.lock().ok().and_then(|r| {
+            r.as_ref()
+                .filter(|r| r.checked_at.elapsed() < self.freshness_ttl)
+                .map(|r| r.order.clone())
+        }) {
+            return Ok(ranking);
+        }
+
+        let statuses = self.check_endpoints().await;
+        let order = rank_endpoints(&statuses, self.max_lag_blocks);
+        if order.is_empty() {
+            return Err(anyhow!("No healthy subgraph endpoint: {:?}", statuses));
+        }
+
+        for (i, status) in statuses.iter().enumerate() {
+            if status.is_healthy() && !order.contains(&i) {
+                warn!(
+                    "Refusing subgraph endpoint {} at block {:?}: more than {} blocks behind",
+                    status.name, status.block, self.max_lag_blocks
+                );
+            }
+        }
+        info!(
+            "Using subgraph endpoint {} (block {:?})",
+            statuses[order[0]].name, statuses[order[0]].block
+        );
+
+        if let Ok(mut ranking) = self.ranking.lock() {
+            *ranking = Some(Ranking { checked_at: Instant::now(), order: order.clone() });
+        }
+        Ok(order)
+    }
+
+    /// Force a freshness check before the next query
+    fn invalidate(&self) {
+        if let Ok(mut ranking) = self.ranking.lock() {
+            *ranking = None;
+        }
+    }
+}
+
+impl<S: IndexerSource + Sync> IndexerSource for FailoverSource<S> {
+    async fn execute(&self, query: &str, variables: Value) -> Result<Value> {
+        let mut last_error = None;
+
+        for i in self.ranking().await? {
+            let endpoint = &self.endpoints[i];
+            match endpoint.source.execute(query, variables.clone()).await {
+                Ok(data) => return Ok(data),
+                Err(e) => {
+                    warn!("Subgraph endpoint {} failed, failing over: {:#}", endpoint.name, e);
+                    self.invalidate();
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error
+            .unwrap_or_else(|| anyhow!("No subgraph endpoints configured"))
+            .context("All subgraph endpoints failed"))
+    }
+}
+
+/// Loggable endpoint name (the host, since gateway URLs embed the API key)
+fn endpoint_name(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "subgraph".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::FixtureSource;
+
+    fn status(name: &str, block: Option<u64>, has_indexing_errors: bool) -> EndpointStatus {
+        EndpointStatus { name: name.to_string(), block, has_indexing_errors }
+    }
+
+    /// Endpoint that answers `_meta` but fails every data query
+    struct Broken(FixtureSource);
+
+    impl IndexerSource for Broken {
+        async fn execute(&self, query: &str, variables: Value) -> Result<Value> {
+            if query == queries::META {
+                return self.0.execute(query, variables).await;
+            }
+            Err(anyhow!("502 Bad Gateway"))
+        }
+    }
+
+    #[test]
+    fn test_rank_prefers_freshest_healthy_endpoint() {
+        let statuses = vec![
+            status("gateway", Some(1_000), false),
+            status("graph-node", Some(1_020), false),
+            status("studio", Some(1_020), false),
+            status("down", None, false),
+        ];
+
+        assert_eq!(rank_endpoints(&statuses, 100), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn test_rank_refuses_lagging_and_failed_endpoints() {
+        let statuses = vec![
+            status("gateway", Some(1_000), false),
+            status("graph-node", Some(1_500), true),
+            status("studio", Some(1_200), false),
+        ];
+
+        // graph-node is ahead but has indexing errors, gateway is 200 blocks behind studio
+        assert_eq!(rank_endpoints(&statuses, 100), vec![2]);
+        assert_eq!(rank_endpoints(&statuses, 200), vec![2, 0]);
+        assert!(rank_endpoints(&[status("down", None, false)], 100).is_empty());
+    }
+
+    #[test]
+    fn test_endpoint_name_hides_api_key() {
+        let url = "https://gateway.thegraph.com/api/SECRET/subgraphs/id/abc";
+        assert_eq!(endpoint_name(url), "gateway.thegraph.com");
+    }
+
+    #[tokio::test]
+    async fn test_queries_freshest_endpoint() {
+        let stale = FixtureSource::new()
+            .with_meta(900, false)
+            .with_entities("swaps", vec![json!({ "id": "stale" })]);
+        let fresh = FixtureSource::new()
+            .with_meta(1_000, false)
+            .with_entities("swaps", vec![json!({ "id": "fresh" })]);
+        let source =
+            FailoverSource::new(vec![("stale".to_string(), stale), ("fresh".to_string(), fresh)]);
+
+        let data = source.execute(queries::RECENT_SWAPS, json!({})).await.unwrap();
+        assert_eq!(data["swaps"][0]["id"], "fresh");
+
+        // The lagging endpoint is refused outright
+        let source = source.with_max_lag_blocks(50);
+        source.invalidate();
+        let data = source.execute(queries::RECENT_SWAPS, json!({})).await.unwrap();
+        assert_eq!(data["swaps"][0]["id"], "fresh");
+    }
+
+    #[tokio::test]
+    async fn test_reports_when_all_endpoints_fail() {
+        let broken = Broken(FixtureSource::new().with_meta(1_000, false));
+        let backup = Broken(
+            FixtureSource::new()
+                .with_meta(990, false)
+                .with_entities("swaps", vec![json!({ "id": "backup" })]),
+        );
+        let source = FailoverSource::new(vec![
+            ("broken".to_string(), broken),
+            ("backup".to_string(), backup),
+        ]);
+
+        let error = source.execute(queries::RECENT_SWAPS, json!({})).await.unwrap_err();
+        assert!(format!("{:#}", error).contains("All subgraph endpoints failed"));
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_to_next_endpoint() {
+        enum Endpoint {
+            Broken(Broken),
+            Working(FixtureSource),
+        }
+
+        impl IndexerSource for Endpoint {
+            async fn execute(&self, query: &str, variables: Value) -> Result<Value> {
+                match self {
+                    Endpoint::Broken(source) => source.execute(query, variables).await,
+                    Endpoint::Working(source) => source.execute(query, variables).await,
+                }
+            }
+        }
+
+        let broken = Endpoint::Broken(Broken(FixtureSource::new().with_meta(1_000, false)));
+        let backup = Endpoint::Working(
+            FixtureSource::new()
+                .with_meta(990, false)
+                .with_entities("swaps", vec![json!({ "id": "backup" })]),
+        );
+        let source = FailoverSource::new(vec![
+            ("broken".to_string(), broken),
+            ("backup".to_string(), backup),
+        ]);
+
+        let data = source.execute(queries::RECENT_SWAPS, json!({})).await.unwrap();
+        assert_eq!(data["swaps"][0]["id"], "backup");
+    }
+
+    #[tokio::test]
+    async fn test_errors_without_healthy_endpoint() {
+        let source = FailoverSource::new(vec![(
+            "failing".to_string(),
+            FixtureSource::new().with_meta(1_000, true),
+        )]);
+
+        let error = source.execute(queries::RECENT_SWAPS, json!({})).await.unwrap_err();
+        assert!(error.to_string().contains("No healthy subgraph endpoint"));
+    }
+}
@@ -1,38 +1,109 @@
+mod checkpoint;
+mod convert;
+mod failover;
+mod linking;
+mod logs;
 mod queries;
+mod retry;
+mod source;
+mod tokens;
 mod types;
 
-use alloy::primitives::{I256, U256};
-use anyhow::{anyhow, Context, Result};
+use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Utc};
-use reqwest::Client;
+use futures::stream::{self, StreamExt};
 use serde_json::json;
 use sqlx::PgPool;
-use stillwater_db::{insert_pool, insert_position, insert_swap};
-use stillwater_models::{Pool, Position, Swap};
+use std::sync::Once;
+use stillwater_db::{
+    apply_liquidity_event, backfill_swap_log_index, get_nft_positions_in_range,
+    get_position_pool_ids, get_sync_state, get_unindexed_swap_txs, insert_pool, insert_swap,
+    insert_token, update_position_owner, upsert_sync_state,
+};
+use stillwater_models::SyncEntity;
 use tracing::{debug, info, warn};
 
+pub use checkpoint::{Checkpoint, SyncProgress};
+pub use convert::{
+    liquidity_event_from_response, pool_from_response, swap_from_response, token_from_response,
+    ConvertedLiquidityEvent,
+};
+pub use failover::{
+    rank_endpoints, EndpointStatus, FailoverSource, DEFAULT_FRESHNESS_TTL, DEFAULT_MAX_LAG_BLOCKS,
+};
+pub use linking::{link_mints, sort_chronologically, NftLink};
+pub use logs::{
+    decode_log, BlockRef, DecodedLog, LogIndexer, LogSource, LogSyncStats, PoolManagerEvent,
+    RecordedLogs, SwapBackfillStats, DEFAULT_LOG_CHUNK_SIZE,
+};
+pub use retry::{backoff_delay, env_u64, QueryError, RetryConfig};
+pub use source::{FixtureSource, GraphClient, IndexerSource};
+pub use tokens::{get_or_fetch_token, sync_token_metadata};
 pub use types::*;
 
+/// Maximum page size accepted by The Graph (`first` is capped at 1000)
+pub const PAGE_SIZE: usize = 1000;
+
+/// How far back to backfill when an entity has no sync checkpoint yet
+pub const DEFAULT_BACKFILL_DAYS: i64 = 30;
+
+/// Number of pools whose swaps are fetched at the same time
+pub const DEFAULT_SWAP_SYNC_CONCURRENCY: usize = 4;
+
+/// Outcome of syncing swaps across all tracked pools
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SwapSyncStats {
+    /// Pools that were synced successfully
+    pub pools: usize,
+    /// New swaps inserted across those pools
+    pub swaps: usize,
+    /// Pools whose sync failed; they resume from their own checkpoint next run
+    pub failed_pools: Vec<String>,
+}
+
 /// The Graph indexer client
-pub struct GraphIndexer {
-    client: Client,
-    graph_url: String,
+pub struct GraphIndexer<S = FailoverSource> {
+    source: S,
+    swap_concurrency: usize,
 }
 
-impl GraphIndexer {
-    /// Create a new Graph indexer client
+impl GraphIndexer<FailoverSource> {
+    /// Create a new Graph indexer client for a single subgraph URL
     pub fn new(graph_url: String) -> Self {
-        Self {
-            client: Client::new(),
-            graph_url,
-        }
+        Self::with_source(FailoverSource::from_urls(&[graph_url], RetryConfig::default()))
     }
 
-    /// Create indexer from environment variable
+    /// Create indexer from environment variables (`GRAPH_API_URL` endpoints, retry policy,
+    /// freshness settings and `SWAP_SYNC_CONCURRENCY`)
     pub fn from_env() -> Result<Self> {
-        let graph_url = std::env::var("GRAPH_API_URL")
-            .context("GRAPH_API_URL must be set in environment")?;
-        Ok(Self::new(graph_url))
+        let indexer = Self::with_source(FailoverSource::from_env()?);
+
+        match std::env::var("SWAP_SYNC_CONCURRENCY") {
+            Ok(value) => {
+                let concurrency =
+                    value.parse::<usize>().context("Invalid SWAP_SYNC_CONCURRENCY")?;
+                Ok(indexer.with_swap_concurrency(concurrency))
+            }
+            Err(_) => Ok(indexer),
+        }
+    }
+}
+
+impl<S: IndexerSource> GraphIndexer<S> {
+    /// Create an indexer that reads from any subgraph source
+    pub fn with_source(source: S) -> Self {
+        Self { source, swap_concurrency: DEFAULT_SWAP_SYNC_CONCURRENCY }
+    }
+
+    /// Set how many pools' swaps are synced at the same time (at least one)
+    pub fn with_swap_concurrency(mut self, swap_concurrency: usize) -> Self {
+        self.swap_concurrency = swap_concurrency.max(1);
+        self
+    }
+
+    /// Get the underlying subgraph source
+    pub fn source(&self) -> &S {
+        &self.source
     }
 
     /// Execute a GraphQL query
@@ -40,53 +111,72 @@ impl GraphIndexer {
     where
         T: for<'de> serde::Deserialize<'de>,
     {
-        let body = json!({
-            "query": query,
-            "variables": variables
-        });
+        let data = self.source.execute(query, variables).await?;
+        serde_json::from_value(data).context("Failed to parse GraphQL response")
+    }
 
-        info!("Sending GraphQL query to {}", self.graph_url);
-        debug!("Query variables: {:?}", variables);
+    /// Execute a paginated GraphQL query, following the `id_gt` cursor until exhausted
+    ///
+    /// The query must accept `$first` and `$lastId` variables and order results by `id` asc.
+    async fn query_paginated<D, T>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<Vec<T>>
+    where
+        D: for<'de> serde::Deserialize<'de> + Into<Vec<T>>,
+        T: Paginated,
+    {
+        let mut results = Vec::new();
+        let mut last_id = String::new();
 
-        let response = self
-            .client
-            .post(&self.graph_url)
-            .json(&body)
-            .send()
-            .await
-            .context("Failed to send GraphQL request")?;
+        loop {
+            let mut page_variables = variables.clone();
+            page_variables["first"] = json!(PAGE_SIZE);
+            page_variables["lastId"] = json!(last_id);
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            return Err(anyhow!("GraphQL request failed with status {}: {}", status, text));
-        }
+            let page: Vec<T> = self.query::<D>(query, page_variables).await?.into();
+            let page_len = page.len();
 
-        let result: GraphQLResponse<T> = response
-            .json()
-            .await
-            .context("Failed to parse GraphQL response")?;
+            if let Some(last) = page.last() {
+                last_id = last.cursor().to_string();
+            }
+            results.extend(page);
+
+            debug!("Fetched page of {} entities ({} total)", page_len, results.len());
 
-        if let Some(errors) = result.errors {
-            let error_messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
-            return Err(anyhow!("GraphQL errors: {}", error_messages.join(", ")));
+            if page_len < PAGE_SIZE {
+                break;
+            }
         }
 
-        result.data.ok_or_else(|| anyhow!("No data in GraphQL response"))
+        Ok(results)
     }
 
-    /// Fetch positions by owner address
-    pub async fn fetch_positions_by_owner(&self, owner: &str) -> Result<Vec<PositionResponse>> {
+    /// Fetch liquidity events by owner (origin) address
+    pub async fn fetch_modify_liquidities_by_owner(
+        &self,
+        owner: &str,
+    ) -> Result<Vec<ModifyLiquidityResponse>> {
         let variables = json!({ "owner": owner.to_lowercase() });
-        let data: PositionsData = self.query(queries::POSITIONS_BY_OWNER, variables).await?;
-        Ok(data.positions)
+        self.query_paginated::<ModifyLiquiditiesData, _>(
+            queries::MODIFY_LIQUIDITIES_BY_OWNER,
+            variables,
+        )
+        .await
     }
 
-    /// Fetch positions by pool ID
-    pub async fn fetch_positions_by_pool(&self, pool_id: &str) -> Result<Vec<PositionResponse>> {
+    /// Fetch liquidity events by pool ID
+    pub async fn fetch_modify_liquidities_by_pool(
+        &self,
+        pool_id: &str,
+    ) -> Result<Vec<ModifyLiquidityResponse>> {
         let variables = json!({ "poolId": pool_id.to_lowercase() });
-        let data: PositionsData = self.query(queries::POSITIONS_BY_POOL, variables).await?;
-        Ok(data.positions)
+        self.query_paginated::<ModifyLiquiditiesData, _>(
+            queries::MODIFY_LIQUIDITIES_BY_POOL,
+            variables,
+        )
+        .await
     }
 
     /// Fetch recent swaps for a pool since a timestamp
@@ -100,154 +190,399 @@ impl GraphIndexer {
             "poolId": pool_id.to_lowercase(),
             "timestamp": timestamp.to_string()
         });
-        let data: SwapsData = self.query(queries::RECENT_SWAPS, variables).await?;
-        Ok(data.swaps)
+        self.query_paginated::<SwapsData, _>(queries::RECENT_SWAPS, variables).await
     }
 
-    /// Fetch recent positions since a timestamp
-    pub async fn fetch_recent_positions(&self, since: DateTime<Utc>) -> Result<Vec<PositionResponse>> {
+    /// Fetch the swaps of a set of transactions
+    pub async fn fetch_swaps_by_transactions(
+        &self,
+        tx_hashes: &[String],
+    ) -> Result<Vec<SwapResponse>> {
+        let mut swaps = Vec::new();
+        for chunk in tx_hashes.chunks(PAGE_SIZE) {
+            let variables = json!({ "transactions": chunk });
+            swaps.extend(
+                self.query_paginated::<SwapsData, _>(queries::SWAPS_BY_TRANSACTIONS, variables)
+                    .await?,
+            );
+        }
+        Ok(swaps)
+    }
+
+    /// Fetch recent liquidity events since a timestamp
+    pub async fn fetch_recent_modify_liquidities(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<ModifyLiquidityResponse>> {
         let timestamp = since.timestamp();
         let variables = json!({ "timestamp": timestamp.to_string() });
-        let data: PositionsData = self.query(queries::RECENT_POSITIONS, variables).await?;
-        Ok(data.positions)
+        self.query_paginated::<ModifyLiquiditiesData, _>(
+            queries::RECENT_MODIFY_LIQUIDITIES,
+            variables,
+        )
+        .await
     }
 
-    /// Sync positions to database
-    pub async fn sync_positions(&self, db_pool: &PgPool) -> Result<usize> {
-        // Fetch positions from the last 30 days (increased from 1 hour for testing)
-        let since = Utc::now() - chrono::Duration::days(30);
-        info!("Fetching positions since {}", since);
+    /// Fetch recent PositionManager NFT transfers (including mints) since a timestamp
+    pub async fn fetch_recent_transfers(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<TransferResponse>> {
+        let timestamp = since.timestamp();
+        let variables = json!({ "timestamp": timestamp.to_string() });
+        self.query_paginated::<TransfersData, _>(queries::RECENT_TRANSFERS, variables).await
+    }
 
-        let positions = self.fetch_recent_positions(since).await?;
+    /// Fetch PositionManager positions (NFTs) by token ID
+    pub async fn fetch_positions_by_token_ids(
+        &self,
+        token_ids: &[String],
+    ) -> Result<Vec<PositionResponse>> {
+        let mut positions = Vec::with_capacity(token_ids.len());
+        for chunk in token_ids.chunks(PAGE_SIZE) {
+            let variables = json!({ "tokenIds": chunk });
+            positions.extend(
+                self.query_paginated::<PositionsData, _>(queries::POSITIONS_BY_TOKEN_IDS, variables)
+                    .await?,
+            );
+        }
+        Ok(positions)
+    }
 
-        info!("Fetched {} positions from The Graph", positions.len());
+    /// Resolve where a sync should start from its stored checkpoint
+    async fn resume_from(
+        &self,
+        db_pool: &PgPool,
+        entity: SyncEntity,
+        pool_id: &str,
+    ) -> Result<DateTime<Utc>> {
+        let since = match get_sync_state(db_pool, entity, pool_id).await? {
+            Some(state) => state.last_timestamp,
+            None => Utc::now() - chrono::Duration::days(DEFAULT_BACKFILL_DAYS),
+        };
+        Ok(since)
+    }
+
+    /// Persist the checkpoint reached by a sync run
+    async fn save_progress(
+        &self,
+        db_pool: &PgPool,
+        entity: SyncEntity,
+        pool_id: &str,
+        progress: &SyncProgress,
+    ) -> Result<()> {
+        if let Some(checkpoint) = progress.resume_point() {
+            upsert_sync_state(db_pool, entity, pool_id, checkpoint.block, checkpoint.timestamp)
+                .await?;
+            debug!("Saved {} checkpoint at {}", entity.as_str(), checkpoint.timestamp);
+        }
+        Ok(())
+    }
+
+    /// Sync liquidity events and the positions derived from them, resuming from the last
+    /// checkpoint. Returns the number of new liquidity events applied.
+    ///
+    /// Events are linked to PositionManager NFTs so `nft_id` is the token id and `owner`
+    /// follows NFT transfers; events that cannot be linked are kept as positions of their
+    /// transaction origin.
+    pub async fn sync_positions(&self, db_pool: &PgPool) -> Result<usize> {
+        let since = self.resume_from(db_pool, SyncEntity::Positions, "").await?;
+        info!("Fetching liquidity events since {}", since);
+
+        let mut events = self.fetch_recent_modify_liquidities(since).await?;
+        let transfers = self.fetch_recent_transfers(since).await?;
+
+        info!(
+            "Fetched {} liquidity events and {} NFT transfers from The Graph",
+            events.len(),
+            transfers.len()
+        );
+
+        sort_chronologically(&mut events);
+        let mint_links = link_mints(&events, &transfers);
+
+        let mut applied = 0;
+        let mut progress = SyncProgress::default();
+        for event_resp in events {
+            let checkpoint = match Checkpoint::parse(
+                &event_resp.timestamp,
+                event_resp.transaction.block_number.as_deref(),
+            ) {
+                Ok(c) => c,
+                Err(e) => {
+                    warn!(
+                        "Skipping liquidity event {} with invalid checkpoint: {}",
+                        event_resp.id, e
+                    );
+                    continue;
+                }
+            };
 
-        let mut inserted = 0;
-        for pos_resp in positions {
             // First, ensure the pool exists
-            match self.convert_and_insert_pool(db_pool, &pos_resp.pool).await {
+            match self.convert_and_insert_pool(db_pool, &event_resp.pool).await {
                 Ok(_) => {}
                 Err(e) => {
-                    warn!("Failed to insert pool {}: {}", pos_resp.pool.id, e);
+                    warn!("Failed to insert pool {}: {}", event_resp.pool.id, e);
+                    progress.record_failure(checkpoint);
                     continue;
                 }
             }
 
-            // Then insert the position
-            match self.convert_and_insert_position(db_pool, &pos_resp).await {
-                Ok(_) => {
-                    inserted += 1;
-                    debug!("Inserted position {}", pos_resp.id);
+            // Then apply the liquidity delta to its position
+            let link = match mint_links.get(&event_resp.id) {
+                Some(link) => Some(link.clone()),
+                None => self.find_nft_link(db_pool, &event_resp).await,
+            };
+            match self
+                .convert_and_apply_liquidity_event(db_pool, &event_resp, link.as_ref())
+                .await
+            {
+                Ok(is_new) => {
+                    if is_new {
+                        applied += 1;
+                        debug!("Applied liquidity event {}", event_resp.id);
+                    }
+                    progress.record_success(checkpoint);
                 }
                 Err(e) => {
-                    warn!("Failed to insert position {}: {}", pos_resp.id, e);
+                    warn!("Failed to apply liquidity event {}: {}", event_resp.id, e);
+                    progress.record_failure(checkpoint);
                 }
             }
         }
 
-        info!("Inserted {} new positions", inserted);
-        Ok(inserted)
+        // Finally, point transferred NFTs at their current holders
+        let mut token_ids: Vec<String> = transfers.iter().map(|t| t.token_id.clone()).collect();
+        token_ids.sort();
+        token_ids.dedup();
+        if !token_ids.is_empty() {
+            self.sync_position_owners(db_pool, &token_ids).await?;
+        }
+
+        self.save_progress(db_pool, SyncEntity::Positions, "", &progress).await?;
+
+        info!("Applied {} new liquidity events", applied);
+        Ok(applied)
+    }
+
+    /// Update the holder of each NFT position from the subgraph's `Position` entities
+    async fn sync_position_owners(&self, db_pool: &PgPool, token_ids: &[String]) -> Result<()> {
+        let positions = self.fetch_positions_by_token_ids(token_ids).await?;
+
+        for position in positions {
+            if let Err(e) =
+                update_position_owner(db_pool, &position.token_id, &position.owner).await
+            {
+                warn!("Failed to update owner of position {}: {}", position.token_id, e);
+            }
+        }
+
+        Ok(())
     }
 
-    /// Sync swaps to database
+    /// Link a liquidity event without a mint in its transaction to an existing NFT position
+    ///
+    /// The subgraph does not expose the event salt, so the position is matched on
+    /// (sender, pool, range), using the transaction origin to break ties.
+    async fn find_nft_link(
+        &self,
+        db_pool: &PgPool,
+        event_resp: &ModifyLiquidityResponse,
+    ) -> Option<NftLink> {
+        let sender = event_resp.sender.as_deref()?;
+        let tick_lower = event_resp.tick_lower.parse::<i32>().ok()?;
+        let tick_upper = event_resp.tick_upper.parse::<i32>().ok()?;
+
+        let candidates = match get_nft_positions_in_range(
+            db_pool,
+            sender,
+            &event_resp.pool.id,
+            tick_lower,
+            tick_upper,
+        )
+        .await
+        {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                warn!("Failed to look up NFT positions for event {}: {}", event_resp.id, e);
+                return None;
+            }
+        };
+
+        let position = match candidates.as_slice() {
+            [] => return None,
+            [position] => position,
+            _ => {
+                let mut held = candidates
+                    .iter()
+                    .filter(|p| p.owner.eq_ignore_ascii_case(&event_resp.owner));
+                match (held.next(), held.next()) {
+                    (Some(position), None) => position,
+                    _ => {
+                        warn!("Ambiguous NFT position for liquidity event {}", event_resp.id);
+                        return None;
+                    }
+                }
+            }
+        };
+
+        Some(NftLink { token_id: position.nft_id.clone(), holder: position.owner.clone() })
+    }
+
+    /// Sync swaps for a pool to database, resuming from the pool's last checkpoint
     pub async fn sync_swaps(&self, db_pool: &PgPool, pool_id: &str) -> Result<usize> {
-        let since = Utc::now() - chrono::Duration::hours(1);
+        let since = self.resume_from(db_pool, SyncEntity::Swaps, pool_id).await?;
         let swaps = self.fetch_recent_swaps(pool_id, since).await?;
 
         info!("Fetched {} swaps from The Graph for pool {}", swaps.len(), pool_id);
 
         let mut inserted = 0;
+        let mut progress = SyncProgress::default();
         for swap_resp in swaps {
+            let checkpoint = match Checkpoint::parse(
+                &swap_resp.transaction.timestamp,
+                swap_resp.transaction.block_number.as_deref(),
+            ) {
+                Ok(c) => c,
+                Err(e) => {
+                    warn!("Skipping swap {} with invalid checkpoint: {}", swap_resp.id, e);
+                    continue;
+                }
+            };
+
             match self.convert_and_insert_swap(db_pool, &swap_resp).await {
-                Ok(_) => {
-                    inserted += 1;
-                    debug!("Inserted swap {}", swap_resp.id);
+                Ok(is_new) => {
+                    if is_new {
+                        inserted += 1;
+                        debug!("Inserted swap {}", swap_resp.id);
+                    }
+                    progress.record_success(checkpoint);
                 }
                 Err(e) => {
                     warn!("Failed to insert swap {}: {}", swap_resp.id, e);
+                    progress.record_failure(checkpoint);
                 }
             }
         }
 
-        info!("Inserted {} new swaps", inserted);
+        self.save_progress(db_pool, SyncEntity::Swaps, pool_id, &progress).await?;
+
+        info!("Inserted {} new swaps for pool {}", inserted, pool_id);
         Ok(inserted)
     }
 
-    /// Convert and insert pool into database
-    async fn convert_and_insert_pool(&self, db_pool: &PgPool, pool_resp: &PoolResponse) -> Result<()> {
-        let fee_tier = pool_resp.fee.parse::<i32>()
-            .context("Failed to parse fee")?;
-        let tick_spacing = pool_resp.tick_spacing.parse::<i32>()
-            .context("Failed to parse tick spacing")?;
-
-        let pool = Pool {
-            pool_id: pool_resp.id.clone(),
-            token0: pool_resp.token0.id.clone(),
-            token1: pool_resp.token1.id.clone(),
-            fee_tier,
-            tick_spacing,
-            created_at: Utc::now(), // We don't have creation time from subgraph
-        };
+    /// Fill in the log index of swaps stored before log indexes were tracked
+    ///
+    /// The subgraph is asked for the swaps of their transactions, whose log indexes come
+    /// from `logIndex` or the `txHash#logIndex` entity id, and the stored swaps are matched
+    /// by pool and amounts. Swaps it cannot match are left for
+    /// [`LogIndexer::backfill_swap_log_indexes`], which re-fetches receipts. Returns the
+    /// number of swaps recovered.
+    pub async fn backfill_swap_log_indexes(&self, db_pool: &PgPool) -> Result<usize> {
+        let tx_hashes = get_unindexed_swap_txs(db_pool).await?;
+        if tx_hashes.is_empty() {
+            return Ok(0);
+        }
 
-        insert_pool(db_pool, &pool).await?;
-        Ok(())
+        let mut recovered = 0;
+        for swap_resp in self.fetch_swaps_by_transactions(&tx_hashes).await? {
+            let swap = match swap_from_response(&swap_resp) {
+                Ok(swap) => swap,
+                Err(e) => {
+                    warn!("Skipping swap {} in backfill: {}", swap_resp.id, e);
+                    continue;
+                }
+            };
+            if backfill_swap_log_index(db_pool, &swap).await? {
+                recovered += 1;
+            }
+        }
+
+        info!("Backfilled log indexes of {} swaps from The Graph", recovered);
+        Ok(recovered)
     }
 
-    /// Convert and insert position into database (v4: from ModifyLiquidity event)
-    async fn convert_and_insert_position(&self, db_pool: &PgPool, pos_resp: &PositionResponse) -> Result<()> {
-        let tick_lower = pos_resp.tick_lower.parse::<i32>()
-            .context("Failed to parse tick_lower")?;
-        let tick_upper = pos_resp.tick_upper.parse::<i32>()
-            .context("Failed to parse tick_upper")?;
-        let liquidity = U256::from_str_radix(&pos_resp.liquidity, 10)
-            .context("Failed to parse liquidity")?;
-        // In v4, timestamp is a direct field
-        let timestamp = pos_resp.timestamp.parse::<i64>()
-            .context("Failed to parse timestamp")?;
-        let created_at = DateTime::from_timestamp(timestamp, 0)
-            .ok_or_else(|| anyhow!("Invalid timestamp"))?;
-
-        let position = Position {
-            id: 0, // Will be auto-generated
-            nft_id: pos_resp.id.clone(),
-            owner: pos_resp.owner.clone(),
-            pool_id: pos_resp.pool.id.clone(),
-            tick_lower,
-            tick_upper,
-            liquidity,
-            created_at,
-        };
+    /// Sync swaps for every pool referenced by a position
+    ///
+    /// Pools are synced concurrently, at most `swap_concurrency` at a time. Each pool keeps
+    /// its own checkpoint, so a failing pool is reported and retried next run without
+    /// holding back the others.
+    pub async fn sync_all_swaps(&self, db_pool: &PgPool) -> Result<SwapSyncStats> {
+        static LIQUIDITY_GAP: Once = Once::new();
+        LIQUIDITY_GAP.call_once(|| {
+            warn!(
+                "Subgraph swaps carry no post-swap liquidity; swap-replay fees need swaps \
+                 from the log indexer (`sync rpc`)"
+            )
+        });
 
-        insert_position(db_pool, &position).await?;
-        Ok(())
+        let pool_ids = get_position_pool_ids(db_pool).await?;
+        let total = pool_ids.len();
+        info!("Syncing swaps for {} pools ({} at a time)", total, self.swap_concurrency);
+
+        let mut results = stream::iter(pool_ids)
+            .map(|pool_id| async move {
+                let result = self.sync_swaps(db_pool, &pool_id).await;
+                (pool_id, result)
+            })
+            .buffer_unordered(self.swap_concurrency);
+
+        let mut stats = SwapSyncStats::default();
+        let mut done = 0;
+        while let Some((pool_id, result)) = results.next().await {
+            done += 1;
+            match result {
+                Ok(inserted) => {
+                    stats.pools += 1;
+                    stats.swaps += inserted;
+                    info!("[{}/{}] Synced {} swaps for pool {}", done, total, inserted, pool_id);
+                }
+                Err(e) => {
+                    warn!(
+                        "[{}/{}] Failed to sync swaps for pool {}: {:#}",
+                        done, total, pool_id, e
+                    );
+                    stats.failed_pools.push(pool_id);
+                }
+            }
+        }
+
+        Ok(stats)
     }
 
-    /// Convert and insert swap into database
-    async fn convert_and_insert_swap(&self, db_pool: &PgPool, swap_resp: &SwapResponse) -> Result<()> {
-        let amount0 = swap_resp.amount0.parse::<I256>()
-            .context("Failed to parse amount0")?;
-        let amount1 = swap_resp.amount1.parse::<I256>()
-            .context("Failed to parse amount1")?;
-        let timestamp = swap_resp.transaction.timestamp.parse::<i64>()
-            .context("Failed to parse timestamp")?;
-        let swap_time = DateTime::from_timestamp(timestamp, 0)
-            .ok_or_else(|| anyhow!("Invalid timestamp"))?;
-
-        let tx_hash = swap_resp.transaction.id.clone()
-            .unwrap_or_else(|| swap_resp.id.clone());
-
-        let swap = Swap {
-            id: 0, // Will be auto-generated
-            tx_hash,
-            pool_id: swap_resp.pool.id.clone(),
-            amount0,
-            amount1,
-            timestamp: swap_time,
-        };
+    /// Convert and insert pool into database, registering its tokens' metadata
+    async fn convert_and_insert_pool(&self, db_pool: &PgPool, pool_resp: &PoolResponse) -> Result<()> {
+        insert_pool(db_pool, &pool_from_response(pool_resp)?).await?;
 
-        insert_swap(db_pool, &swap).await?;
+        for token_resp in [&pool_resp.token0, &pool_resp.token1] {
+            if let Some(token) = token_from_response(token_resp)? {
+                insert_token(db_pool, &token).await?;
+            }
+        }
         Ok(())
     }
+
+    /// Convert a ModifyLiquidity event and apply it to the derived position
+    ///
+    /// Returns whether the event was new.
+    async fn convert_and_apply_liquidity_event(
+        &self,
+        db_pool: &PgPool,
+        event_resp: &ModifyLiquidityResponse,
+        link: Option<&NftLink>,
+    ) -> Result<bool> {
+        let converted = liquidity_event_from_response(event_resp, link)?;
+        apply_liquidity_event(db_pool, &converted.event, &converted.holder).await
+    }
+
+    /// Convert and insert swap into database, returning whether it was new
+    async fn convert_and_insert_swap(
+        &self,
+        db_pool: &PgPool,
+        swap_resp: &SwapResponse,
+    ) -> Result<bool> {
+        insert_swap(db_pool, &swap_from_response(swap_resp)?).await
+    }
 }
 
 /// Helper function to create indexer from environment and sync all data
@@ -257,5 +592,171 @@ pub async fn sync_all(db_pool: &PgPool) -> Result<()> {
     let positions_count = indexer.sync_positions(db_pool).await?;
     info!("Synced {} positions", positions_count);
 
+    let swap_stats = indexer.sync_all_swaps(db_pool).await?;
+    info!("Synced {} swaps across {} pools", swap_stats.swaps, swap_stats.pools);
+
+    if !swap_stats.failed_pools.is_empty() {
+        bail!(
+            "Failed to sync swaps for {} pools: {}",
+            swap_stats.failed_pools.len(),
+            swap_stats.failed_pools.join(", ")
+        );
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stillwater_db::{
+        get_latest_swap_before_log, get_latest_swap_for_pool, get_liquidity_events_for_pool,
+        get_liquidity_events_for_position, get_position_by_nft, get_swaps_for_pool,
+        get_swaps_page, get_token,
+    };
+
+    const FIXTURE: &str = include_str!("../fixtures/subgraph.json");
+    const POOL_ID: &str = "0x807ab56201b8c73b925c16b0e9a54cc15619cffa1c88dc513250a3a9c8b42dae";
+
+    fn swap(i: usize) -> serde_json::Value {
+        json!({
+            "id": format!("0x{:064x}-0", i),
+            "transaction": { "id": format!("0x{:064x}", i), "timestamp": "1700000000" },
+            "pool": { "id": POOL_ID },
+            "amount0": "1",
+            "amount1": "-1"
+        })
+    }
+
+    #[tokio::test]
+    async fn test_pagination_walks_all_pages() {
+        let swaps = (0..PAGE_SIZE * 2 + 500).map(swap).collect();
+        let indexer = GraphIndexer::with_source(FixtureSource::new().with_entities("swaps", swaps));
+
+        let fetched = indexer.fetch_recent_swaps(POOL_ID, Utc::now()).await.unwrap();
+
+        assert_eq!(fetched.len(), PAGE_SIZE * 2 + 500);
+        assert!(fetched.windows(2).all(|w| w[0].id < w[1].id));
+
+        let cursors: Vec<String> = indexer
+            .source()
+            .requests()
+            .iter()
+            .map(|r| r["lastId"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(cursors, ["", &fetched[PAGE_SIZE - 1].id, &fetched[PAGE_SIZE * 2 - 1].id]);
+    }
+
+    #[tokio::test]
+    async fn test_pagination_stops_on_exact_page_boundary() {
+        let swaps = (0..PAGE_SIZE).map(swap).collect();
+        let indexer = GraphIndexer::with_source(FixtureSource::new().with_entities("swaps", swaps));
+
+        let fetched = indexer.fetch_recent_swaps(POOL_ID, Utc::now()).await.unwrap();
+
+        assert_eq!(fetched.len(), PAGE_SIZE);
+        assert_eq!(indexer.source().requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_from_fixture() {
+        let indexer = GraphIndexer::with_source(FixtureSource::from_json(FIXTURE).unwrap());
+
+        let events = indexer.fetch_recent_modify_liquidities(Utc::now()).await.unwrap();
+        let transfers = indexer.fetch_recent_transfers(Utc::now()).await.unwrap();
+        let positions = indexer.fetch_positions_by_token_ids(&["42".to_string()]).await.unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(link_mints(&events, &transfers)[&events[0].id].token_id, "42");
+        assert_eq!(positions[0].token_id, "42");
+    }
+
+    /// Runs the full sync against the database in `DATABASE_URL` (migrations applied)
+    #[tokio::test]
+    #[ignore = "requires a scratch Postgres database in DATABASE_URL"]
+    async fn test_sync_fixture_into_database() {
+        let db_pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let indexer = GraphIndexer::with_source(FixtureSource::from_json(FIXTURE).unwrap());
+
+        indexer.sync_positions(&db_pool).await.unwrap();
+        let swap_stats = indexer.sync_all_swaps(&db_pool).await.unwrap();
+        assert!(swap_stats.failed_pools.is_empty());
+        assert!(swap_stats.pools >= 1);
+
+        let position = get_position_by_nft(&db_pool, "42").await.unwrap().unwrap();
+        assert_eq!(position.liquidity.to_string(), "600000000");
+        assert_eq!(position.owner, "0x70997970c51812dc3a010c7d01b50e0d17dc79c8");
+        assert!(!position.closed);
+
+        // The pool's liquidity events include the position's
+        let events = get_liquidity_events_for_position(&db_pool, &position).await.unwrap();
+        let pool_events =
+            get_liquidity_events_for_pool(&db_pool, &position.pool_id).await.unwrap();
+        assert!(!events.is_empty());
+        assert!(events.iter().all(|e| pool_events.iter().any(|p| p.event_id == e.event_id)));
+
+        let weth = get_token(&db_pool, "0xC02aaA39b223FE8D0A0e5C4F27eAD083C756Cc2a").await.unwrap();
+        assert_eq!(weth.map(|t| (t.symbol, t.decimals)), Some(("WETH".to_string(), 18)));
+
+        let since = DateTime::from_timestamp(0, 0).unwrap();
+        let swaps = get_swaps_for_pool(&db_pool, POOL_ID, since).await.unwrap();
+        // Both swaps of the same transaction through the same pool are kept
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(swaps[0].tx_hash, swaps[1].tx_hash);
+        assert_eq!((swaps[0].log_index, swaps[1].log_index), (0, 2));
+
+        // Paging walks the same swaps in chain order
+        let first = get_swaps_page(&db_pool, POOL_ID, since, None, 1).await.unwrap();
+        assert_eq!(first.iter().map(|s| s.log_index).collect::<Vec<_>>(), [0]);
+        let rest = get_swaps_page(&db_pool, POOL_ID, since, first.last(), 10).await.unwrap();
+        assert_eq!(rest.iter().map(|s| s.log_index).collect::<Vec<_>>(), [2]);
+
+        let latest = get_latest_swap_for_pool(&db_pool, POOL_ID, Utc::now()).await.unwrap();
+        assert_eq!(latest.map(|s| s.log_index), Some(2));
+        assert!(get_latest_swap_for_pool(&db_pool, POOL_ID, since).await.unwrap().is_none());
+
+        // Only swaps logged before a given log count, even within its block
+        let before = get_latest_swap_before_log(&db_pool, POOL_ID, 5000010, 2).await.unwrap();
+        assert_eq!(before.map(|s| s.log_index), Some(0));
+        let first = get_latest_swap_before_log(&db_pool, POOL_ID, 5000010, 0).await.unwrap();
+        assert!(first.is_none());
+
+        // Replaying the same data is a no-op
+        assert_eq!(indexer.sync_positions(&db_pool).await.unwrap(), 0);
+        assert_eq!(indexer.sync_all_swaps(&db_pool).await.unwrap().swaps, 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a scratch Postgres database in DATABASE_URL"]
+    async fn test_backfill_swap_log_indexes_from_subgraph() {
+        let db_pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let indexer = GraphIndexer::with_source(FixtureSource::from_json(FIXTURE).unwrap());
+        indexer.sync_positions(&db_pool).await.unwrap();
+
+        // A swap stored before log indexes were tracked
+        let tx_hash = "0xa3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3";
+        sqlx::query("DELETE FROM swaps WHERE tx_hash = $1")
+            .bind(tx_hash)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO swaps (tx_hash, pool_id, amount0, amount1, timestamp)
+             VALUES ($1, $2, 1000, -990, to_timestamp(1700000120))",
+        )
+        .bind(tx_hash)
+        .bind(POOL_ID)
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        assert_eq!(indexer.backfill_swap_log_indexes(&db_pool).await.unwrap(), 1);
+
+        let since = DateTime::from_timestamp(0, 0).unwrap();
+        let swaps = get_swaps_for_pool(&db_pool, POOL_ID, since).await.unwrap();
+        let swap = swaps.iter().find(|s| s.tx_hash == tx_hash).unwrap();
+        assert_eq!(swap.log_index, 0);
+        assert_eq!(swap.block_number, Some(5000010));
+        assert!(!get_unindexed_swap_txs(&db_pool).await.unwrap().contains(&tx_hash.to_string()));
+    }
+}
@@ -0,0 +1,184 @@
+use alloy::primitives::{B256, U256};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+
+use crate::types::{split_event_id, ModifyLiquidityResponse, TransferResponse};
+
+/// PositionManager NFT that a liquidity event belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NftLink {
+    pub token_id: String,
+    /// NFT holder at the time the link was made
+    pub holder: String,
+}
+
+impl NftLink {
+    /// The PoolManager salt the PositionManager uses for this token (`bytes32(tokenId)`)
+    pub fn salt(&self) -> Result<String> {
+        let token_id =
+            U256::from_str_radix(&self.token_id, 10).context("Failed to parse token id")?;
+        Ok(B256::from(token_id).to_string())
+    }
+}
+
+/// Sort liquidity events into chain order (timestamp, block, log index)
+///
+/// Pages come back ordered by id, but linking increases to earlier mints needs the mint
+/// to be applied first.
+pub fn sort_chronologically(events: &mut [ModifyLiquidityResponse]) {
+    events.sort_by_cached_key(|event| {
+        (
+            event.timestamp.parse::<i64>().unwrap_or_default(),
+            event
+                .transaction
+                .block_number
+                .as_deref()
+                .and_then(|b| b.parse::<i64>().ok())
+                .unwrap_or_default(),
+            event.log_index().unwrap_or_default(),
+        )
+    });
+}
+
+/// Pair each mint with the first following liquidity add in the same transaction
+///
+/// The PositionManager emits the ERC721 mint `Transfer` right before calling
+/// `modifyLiquidity`, so within a transaction mints and adds pair up in log order.
+/// Returns a map from liquidity event id to its NFT.
+pub fn link_mints(
+    events: &[ModifyLiquidityResponse],
+    transfers: &[TransferResponse],
+) -> HashMap<String, NftLink> {
+    let mut adds_by_tx: HashMap<&str, Vec<(u64, &ModifyLiquidityResponse)>> = HashMap::new();
+    for event in events {
+        let Some((tx_hash, log_index)) = split_event_id(&event.id) else { continue };
+        if event.amount.starts_with('-') || event.amount == "0" {
+            continue;
+        }
+        adds_by_tx.entry(tx_hash).or_default().push((log_index, event));
+    }
+    for adds in adds_by_tx.values_mut() {
+        adds.sort_by_key(|(log_index, _)| *log_index);
+    }
+
+    let mut mints: Vec<(&str, u64, &TransferResponse)> = transfers
+        .iter()
+        .filter(|transfer| transfer.is_mint())
+        .filter_map(|transfer| {
+            let (tx_hash, log_index) = transfer.tx_and_log_index()?;
+            Some((tx_hash, log_index, transfer))
+        })
+        .collect();
+    mints.sort_by_key(|(tx_hash, log_index, _)| (*tx_hash, *log_index));
+
+    let mut links = HashMap::new();
+    for (tx_hash, mint_log_index, transfer) in mints {
+        let Some(adds) = adds_by_tx.get(tx_hash) else { continue };
+        let next_add = adds.iter().find(|(log_index, event)| {
+            *log_index > mint_log_index && !links.contains_key(&event.id)
+        });
+        if let Some((_, event)) = next_add {
+            links.insert(
+                event.id.clone(),
+                NftLink { token_id: transfer.token_id.clone(), holder: transfer.to.clone() },
+            );
+        }
+    }
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PoolResponse, TokenResponse, TransactionResponse};
+
+    const TX: &str = "0xaaaa";
+    const ZERO: &str = "0x0000000000000000000000000000000000000000";
+
+    fn token(id: &str) -> TokenResponse {
+        TokenResponse { id: id.to_string(), symbol: None, name: None, decimals: None }
+    }
+
+    fn event(log_index: u64, amount: &str) -> ModifyLiquidityResponse {
+        ModifyLiquidityResponse {
+            id: format!("{}-{}", TX, log_index),
+            owner: "0xorigin".to_string(),
+            pool: PoolResponse {
+                id: "0xpool".to_string(),
+                token0: token("0xtoken0"),
+                token1: token("0xtoken1"),
+                fee: "3000".to_string(),
+                tick_spacing: "60".to_string(),
+                hooks: None,
+                created_at_timestamp: None,
+                created_at_block_number: None,
+            },
+            tick_lower: "-60".to_string(),
+            tick_upper: "60".to_string(),
+            amount: amount.to_string(),
+            sender: Some("0xpositionmanager".to_string()),
+            timestamp: "1700000000".to_string(),
+            transaction: TransactionResponse {
+                id: Some(TX.to_string()),
+                block_number: Some("1".to_string()),
+                timestamp: "1700000000".to_string(),
+            },
+        }
+    }
+
+    fn mint(log_index: u64, token_id: &str) -> TransferResponse {
+        TransferResponse {
+            id: format!("{}-{}", TX, log_index),
+            token_id: token_id.to_string(),
+            from: ZERO.to_string(),
+            to: "0xholder".to_string(),
+            timestamp: "1700000000".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_link_mints_pairs_in_log_order() {
+        let events = vec![event(2, "100"), event(5, "200")];
+        let transfers = vec![mint(4, "8"), mint(1, "7")];
+
+        let links = link_mints(&events, &transfers);
+
+        assert_eq!(links[&format!("{}-2", TX)].token_id, "7");
+        assert_eq!(links[&format!("{}-5", TX)].token_id, "8");
+        assert_eq!(links[&format!("{}-5", TX)].holder, "0xholder");
+    }
+
+    #[test]
+    fn test_link_mints_ignores_removals_and_plain_transfers() {
+        let events = vec![event(2, "-100"), event(3, "100")];
+        let mut transfer = mint(1, "7");
+        transfer.from = "0x1111111111111111111111111111111111111111".to_string();
+
+        assert!(link_mints(&events, &[transfer]).is_empty());
+
+        let links = link_mints(&events, &[mint(1, "7")]);
+        assert_eq!(links.len(), 1);
+        assert!(links.contains_key(&format!("{}-3", TX)));
+    }
+
+    #[test]
+    fn test_nft_salt_is_bytes32_token_id() {
+        let link = NftLink { token_id: "255".to_string(), holder: "0xholder".to_string() };
+        assert_eq!(
+            link.salt().unwrap(),
+            "0x00000000000000000000000000000000000000000000000000000000000000ff"
+        );
+    }
+
+    #[test]
+    fn test_sort_chronologically() {
+        let mut events = vec![event(9, "1"), event(3, "1")];
+        events[0].timestamp = "1600000000".to_string();
+
+        sort_chronologically(&mut events);
+
+        assert_eq!(events[0].id, format!("{}-9", TX));
+        assert_eq!(events[1].id, format!("{}-3", TX));
+    }
+}
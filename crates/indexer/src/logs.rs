@@ -0,0 +1,1147 @@
+use alloy::primitives::{Address, B256, I256, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::{BlockTransactionsKind, Filter, FilteredParams, Log};
+use alloy::sol_types::SolEvent;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Mutex;
+use stillwater_db::{
+    apply_liquidity_event, backfill_swap_log_index, discard_unindexed_swaps, get_indexed_block,
+    get_indexed_blocks, get_pool_by_id, get_sync_state, get_unindexed_swap_txs, insert_donation,
+    insert_pool, insert_swap, prune_indexed_blocks, rollback_above_block, transfer_position_owner,
+    upsert_indexed_block, upsert_sync_state,
+};
+use stillwater_models::{
+    BlockchainService, Donation, IPoolManager, IPositionManager, IndexedBlock, LiquidityEvent,
+    Permissions, Pool, Swap, SyncEntity,
+};
+use tracing::{debug, info, warn};
+
+/// Default number of blocks requested per `eth_getLogs` call
+pub const DEFAULT_LOG_CHUNK_SIZE: u64 = 2000;
+
+/// Default number of blocks behind the head after which ingested data is considered final
+pub const DEFAULT_CONFIRMATIONS: u64 = 64;
+
+/// How many reorgs a single sync tolerates before giving up until the next run
+const MAX_REORGS_PER_SYNC: usize = 5;
+
+/// Block header fields needed to timestamp ingested logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockRef {
+    pub number: u64,
+    pub hash: B256,
+    pub parent_hash: B256,
+    pub timestamp: u64,
+}
+
+impl BlockRef {
+    /// Block timestamp as a UTC datetime
+    pub fn time(&self) -> Result<DateTime<Utc>> {
+        DateTime::from_timestamp(self.timestamp as i64, 0)
+            .ok_or_else(|| anyhow!("Invalid timestamp for block {}", self.number))
+    }
+
+    /// Convert to the row tracked for reorg detection
+    pub fn to_indexed(&self) -> Result<IndexedBlock> {
+        Ok(IndexedBlock {
+            block_number: self.number as i64,
+            block_hash: self.hash.to_string(),
+            parent_hash: self.parent_hash.to_string(),
+            timestamp: self.time()?,
+        })
+    }
+}
+
+/// Source of raw chain logs for the log-based indexer
+pub trait LogSource {
+    /// Get the latest block number
+    fn latest_block(&self) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Get all logs matching a filter (`eth_getLogs`)
+    fn logs(&self, filter: &Filter) -> impl Future<Output = Result<Vec<Log>>> + Send;
+
+    /// Get a block header by number
+    fn block(&self, number: u64) -> impl Future<Output = Result<Option<BlockRef>>> + Send;
+
+    /// Get the logs of a transaction from its receipt, if the transaction is known
+    fn receipt_logs(&self, tx_hash: B256)
+        -> impl Future<Output = Result<Option<Vec<Log>>>> + Send;
+}
+
+impl LogSource for BlockchainService {
+    async fn latest_block(&self) -> Result<u64> {
+        self.get_block_number().await
+    }
+
+    async fn logs(&self, filter: &Filter) -> Result<Vec<Log>> {
+        self.provider().get_logs(filter).await.context("Failed to fetch logs")
+    }
+
+    async fn block(&self, number: u64) -> Result<Option<BlockRef>> {
+        let block = self
+            .provider()
+            .get_block_by_number(number.into(), BlockTransactionsKind::Hashes)
+            .await
+            .context("Failed to fetch block")?;
+
+        Ok(block.map(|b| BlockRef {
+            number: b.header.number,
+            hash: b.header.hash,
+            parent_hash: b.header.parent_hash,
+            timestamp: b.header.timestamp,
+        }))
+    }
+
+    async fn receipt_logs(&self, tx_hash: B256) -> Result<Option<Vec<Log>>> {
+        let receipt = self
+            .provider()
+            .get_transaction_receipt(tx_hash)
+            .await
+            .context("Failed to fetch transaction receipt")?;
+
+        Ok(receipt.map(|r| r.inner.logs().to_vec()))
+    }
+}
+
+/// Recorded blocks and logs replayed as a log source (fixtures and offline tests)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordedLogs {
+    pub blocks: Vec<BlockRef>,
+    pub logs: Vec<Log>,
+}
+
+impl RecordedLogs {
+    /// Load a recording from JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).context("Failed to parse recorded logs")
+    }
+}
+
+impl LogSource for RecordedLogs {
+    async fn latest_block(&self) -> Result<u64> {
+        self.blocks
+            .iter()
+            .map(|b| b.number)
+            .max()
+            .ok_or_else(|| anyhow!("Recording contains no blocks"))
+    }
+
+    async fn logs(&self, filter: &Filter) -> Result<Vec<Log>> {
+        let params = FilteredParams::new(Some(filter.clone()));
+        Ok(self
+            .logs
+            .iter()
+            .filter(|log| {
+                log.block_number.is_some_and(|n| params.filter_block_range(n))
+                    && params.filter_address(&log.address())
+                    && params.filter_topics(log.topics())
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn block(&self, number: u64) -> Result<Option<BlockRef>> {
+        Ok(self.blocks.iter().find(|b| b.number == number).copied())
+    }
+
+    async fn receipt_logs(&self, tx_hash: B256) -> Result<Option<Vec<Log>>> {
+        let logs: Vec<Log> =
+            self.logs.iter().filter(|log| log.transaction_hash == Some(tx_hash)).cloned().collect();
+        Ok((!logs.is_empty()).then_some(logs))
+    }
+}
+
+/// Event emitted by the PoolManager (or a PositionManager NFT transfer)
+#[derive(Clone)]
+pub enum PoolManagerEvent {
+    Initialize(IPoolManager::Initialize),
+    ModifyLiquidity(IPoolManager::ModifyLiquidity),
+    Swap(IPoolManager::Swap),
+    Donate(IPoolManager::Donate),
+    Transfer(IPositionManager::Transfer),
+}
+
+/// Decoded log with its position in the chain
+#[derive(Clone)]
+pub struct DecodedLog {
+    pub tx_hash: String,
+    pub block_hash: Option<B256>,
+    pub log_index: u64,
+    pub block_number: u64,
+    pub timestamp: DateTime<Utc>,
+    pub event: PoolManagerEvent,
+}
+
+impl DecodedLog {
+    /// Identifier in the same `txHash-logIndex` form the subgraph uses
+    pub fn event_id(&self) -> String {
+        format!("{}-{}", self.tx_hash, self.log_index)
+    }
+}
+
+/// Counts of rows written by a log sync
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogSyncStats {
+    pub pools: usize,
+    pub liquidity_events: usize,
+    pub swaps: usize,
+    pub donations: usize,
+    pub transfers: usize,
+    /// Reorgs detected (and rolled back) during the sync
+    pub reorgs: usize,
+}
+
+/// Outcome of backfilling log indexes of swaps stored before they were tracked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapBackfillStats {
+    /// Swaps matched to their receipt log
+    pub recovered: usize,
+    /// Swaps whose receipt has no matching log, dropped to be re-ingested
+    pub discarded: u64,
+    /// Transactions whose receipt was unavailable, left for the next run
+    pub pending: usize,
+}
+
+impl std::ops::AddAssign for LogSyncStats {
+    fn add_assign(&mut self, other: Self) {
+        self.pools += other.pools;
+        self.liquidity_events += other.liquidity_events;
+        self.swaps += other.swaps;
+        self.donations += other.donations;
+        self.transfers += other.transfers;
+        self.reorgs += other.reorgs;
+    }
+}
+
+/// Decode a PoolManager or PositionManager log
+///
+/// Returns `Ok(None)` for logs from other contracts or with unrelated signatures.
+pub fn decode_log(
+    log: &Log,
+    pool_manager: Address,
+    position_manager: Option<Address>,
+) -> Result<Option<PoolManagerEvent>> {
+    let Some(topic0) = log.topic0().copied() else { return Ok(None) };
+    let data = log.data();
+
+    if log.address() == pool_manager {
+        let event = match topic0 {
+            IPoolManager::Initialize::SIGNATURE_HASH => {
+                PoolManagerEvent::Initialize(IPoolManager::Initialize::decode_log_data(data, true)?)
+            }
+            IPoolManager::ModifyLiquidity::SIGNATURE_HASH => PoolManagerEvent::ModifyLiquidity(
+                IPoolManager::ModifyLiquidity::decode_log_data(data, true)?,
+            ),
+            IPoolManager::Swap::SIGNATURE_HASH => {
+                PoolManagerEvent::Swap(IPoolManager::Swap::decode_log_data(data, true)?)
+            }
+            IPoolManager::Donate::SIGNATURE_HASH => {
+                PoolManagerEvent::Donate(IPoolManager::Donate::decode_log_data(data, true)?)
+            }
+            _ => return Ok(None),
+        };
+        return Ok(Some(event));
+    }
+
+    if Some(log.address()) == position_manager
+        && topic0 == IPositionManager::Transfer::SIGNATURE_HASH
+    {
+        let transfer = IPositionManager::Transfer::decode_log_data(data, true)?;
+        return Ok(Some(PoolManagerEvent::Transfer(transfer)));
+    }
+
+    Ok(None)
+}
+
+/// Lowercase `0x`-prefixed hex, matching the subgraph's address format
+fn hex_address(address: Address) -> String {
+    format!("{:#x}", address)
+}
+
+/// Convert an Initialize event into a pool row
+pub fn pool_from_initialize(
+    event: &IPoolManager::Initialize,
+    created_block: u64,
+    created_at: DateTime<Utc>,
+) -> Pool {
+    let fee_tier = event.fee.to::<u32>() as i32;
+    let permissions = Permissions::from_hooks(event.hooks);
+    Pool {
+        pool_id: event.id.to_string(),
+        token0: hex_address(event.currency0),
+        token1: hex_address(event.currency1),
+        fee_tier,
+        tick_spacing: event.tickSpacing.as_i32(),
+        hooks: Some(hex_address(event.hooks)),
+        hook_flags: Some(permissions.flags().into()),
+        hook_return_delta: permissions.has_return_delta(),
+        dynamic_fee: Pool::is_dynamic_fee(fee_tier),
+        created_block: Some(created_block as i64),
+        initial_sqrt_price_x96: Some(U256::from(event.sqrtPriceX96)),
+        initial_tick: Some(event.tick.as_i32()),
+        created_at,
+    }
+}
+
+/// Convert a ModifyLiquidity event into a liquidity event row
+pub fn liquidity_event_from_log(
+    decoded: &DecodedLog,
+    event: &IPoolManager::ModifyLiquidity,
+) -> LiquidityEvent {
+    LiquidityEvent {
+        id: 0, // Will be auto-generated
+        event_id: decoded.event_id(),
+        tx_hash: Some(decoded.tx_hash.clone()),
+        block_number: Some(decoded.block_number as i64),
+        pool_id: event.id.to_string(),
+        owner: hex_address(event.sender),
+        tick_lower: event.tickLower.as_i32(),
+        tick_upper: event.tickUpper.as_i32(),
+        salt: event.salt.to_string(),
+        liquidity_delta: event.liquidityDelta,
+        timestamp: decoded.timestamp,
+        nft_id: None,
+    }
+}
+
+/// Convert a Swap event into a swap row
+///
+/// PoolManager amounts are deltas of the swapper's balance; like the subgraph, swaps are
+/// stored from the pool's perspective (positive = paid into the pool).
+pub fn swap_from_log(decoded: &DecodedLog, event: &IPoolManager::Swap) -> Swap {
+    Swap {
+        id: 0, // Will be auto-generated
+        tx_hash: decoded.tx_hash.clone(),
+        log_index: decoded.log_index as i64,
+        block_number: Some(decoded.block_number as i64),
+        pool_id: event.id.to_string(),
+        sender: Some(hex_address(event.sender)),
+        amount0: -I256::try_from(event.amount0).unwrap_or_default(),
+        amount1: -I256::try_from(event.amount1).unwrap_or_default(),
+        sqrt_price_x96: Some(U256::from(event.sqrtPriceX96)),
+        tick: Some(event.tick.as_i32()),
+        liquidity: Some(U256::from(event.liquidity)),
+        fee: Some(event.fee.to::<u32>() as i32),
+        timestamp: decoded.timestamp,
+    }
+}
+
+/// Convert a Donate event into a donation row
+pub fn donation_from_log(decoded: &DecodedLog, event: &IPoolManager::Donate) -> Donation {
+    Donation {
+        id: 0, // Will be auto-generated
+        tx_hash: decoded.tx_hash.clone(),
+        log_index: decoded.log_index as i64,
+        block_number: decoded.block_number as i64,
+        pool_id: event.id.to_string(),
+        sender: hex_address(event.sender),
+        amount0: event.amount0,
+        amount1: event.amount1,
+        timestamp: decoded.timestamp,
+    }
+}
+
+/// Indexer that reads PoolManager logs directly over RPC instead of The Graph
+pub struct LogIndexer<S> {
+    source: S,
+    pool_manager: Address,
+    position_manager: Option<Address>,
+    start_block: u64,
+    chunk_size: u64,
+    confirmations: u64,
+    /// Pools known to have a row with their Initialize data (or no findable Initialize log)
+    resolved_pools: Mutex<HashSet<B256>>,
+}
+
+impl LogIndexer<BlockchainService> {
+    /// Create a log indexer from environment variables
+    ///
+    /// Reads `POOL_MANAGER_ADDRESS`, and optionally `POSITION_MANAGER_ADDRESS` (to follow
+    /// NFT owners), `LOG_INDEXER_START_BLOCK`, `LOG_INDEXER_CHUNK_SIZE` and
+    /// `LOG_INDEXER_CONFIRMATIONS`.
+    pub fn from_env(blockchain: BlockchainService) -> Result<Self> {
+        let pool_manager = std::env::var("POOL_MANAGER_ADDRESS")
+            .context("POOL_MANAGER_ADDRESS must be set in environment")?
+            .parse::<Address>()
+            .context("Invalid POOL_MANAGER_ADDRESS")?;
+        let position_manager = std::env::var("POSITION_MANAGER_ADDRESS")
+            .ok()
+            .map(|a| a.parse::<Address>().context("Invalid POSITION_MANAGER_ADDRESS"))
+            .transpose()?;
+        let start_block = std::env::var("LOG_INDEXER_START_BLOCK")
+            .ok()
+            .map(|b| b.parse::<u64>().context("Invalid LOG_INDEXER_START_BLOCK"))
+            .transpose()?
+            .unwrap_or(0);
+        let chunk_size = std::env::var("LOG_INDEXER_CHUNK_SIZE")
+            .ok()
+            .map(|c| c.parse::<u64>().context("Invalid LOG_INDEXER_CHUNK_SIZE"))
+            .transpose()?
+            .unwrap_or(DEFAULT_LOG_CHUNK_SIZE);
+        let confirmations = std::env::var("LOG_INDEXER_CONFIRMATIONS")
+            .ok()
+            .map(|c| c.parse::<u64>().context("Invalid LOG_INDEXER_CONFIRMATIONS"))
+            .transpose()?
+            .unwrap_or(DEFAULT_CONFIRMATIONS);
+
+        Ok(Self::new(blockchain, pool_manager, position_manager, start_block)
+            .with_chunk_size(chunk_size)
+            .with_confirmations(confirmations))
+    }
+}
+
+impl<S: LogSource> LogIndexer<S> {
+    /// Create a new log indexer
+    pub fn new(
+        source: S,
+        pool_manager: Address,
+        position_manager: Option<Address>,
+        start_block: u64,
+    ) -> Self {
+        Self {
+            source,
+            pool_manager,
+            position_manager,
+            start_block,
+            chunk_size: DEFAULT_LOG_CHUNK_SIZE,
+            confirmations: DEFAULT_CONFIRMATIONS,
+            resolved_pools: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Set the number of blocks requested per `eth_getLogs` call
+    pub fn with_chunk_size(mut self, chunk_size: u64) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Set how many blocks behind the head ingested data is considered final
+    ///
+    /// Blocks within this depth are tracked by hash and rolled back if reorganized away.
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations.max(1);
+        self
+    }
+
+    /// Get the underlying log source
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Sync all logs from the last checkpoint (or the start block) to the chain head
+    ///
+    /// Tracked blocks are checked against the chain before syncing and while ingesting;
+    /// after a reorg everything above the fork point is rolled back and re-ingested.
+    pub async fn sync(&self, db_pool: &PgPool) -> Result<LogSyncStats> {
+        let head = self.source.latest_block().await?;
+
+        let mut stats = LogSyncStats::default();
+        if self.handle_reorg(db_pool).await? {
+            stats.reorgs += 1;
+        }
+
+        let from = self.resume_block(db_pool).await?;
+        if from > head {
+            debug!("Log indexer is up to date at block {}", head);
+            return Ok(stats);
+        }
+
+        info!("Syncing PoolManager logs from block {} to {}", from, head);
+
+        loop {
+            let from = self.resume_block(db_pool).await?;
+            if from > head {
+                break;
+            }
+
+            let to = (from + self.chunk_size - 1).min(head);
+            match self.sync_range(db_pool, from, to, head).await? {
+                Some(range_stats) => stats += range_stats,
+                None => {
+                    stats.reorgs += 1;
+                    if stats.reorgs > MAX_REORGS_PER_SYNC {
+                        bail!("Chain reorganized {} times during one log sync", stats.reorgs);
+                    }
+                }
+            }
+        }
+
+        let finalized = head.saturating_sub(self.confirmations);
+        prune_indexed_blocks(db_pool, finalized as i64).await?;
+
+        info!("Log sync complete: {:?}", stats);
+        Ok(stats)
+    }
+
+    /// First block that still needs to be ingested
+    async fn resume_block(&self, db_pool: &PgPool) -> Result<u64> {
+        Ok(match get_sync_state(db_pool, SyncEntity::Logs, "").await? {
+            Some(state) => state.last_block.map(|b| b as u64 + 1).unwrap_or(self.start_block),
+            None => self.start_block,
+        })
+    }
+
+    /// Ingest all logs in an inclusive block range and checkpoint its last block
+    ///
+    /// Blocks within the confirmation depth of `head` (and the range's last block) are
+    /// recorded by hash first. Returns `None` when the chain no longer extends the tracked
+    /// blocks; the orphaned data has then been rolled back and the range must be retried.
+    async fn sync_range(
+        &self,
+        db_pool: &PgPool,
+        from: u64,
+        to: u64,
+        head: u64,
+    ) -> Result<Option<LogSyncStats>> {
+        let first_unfinalized = head.saturating_sub(self.confirmations) + 1;
+        let mut tracked: HashMap<u64, B256> = HashMap::new();
+        let mut last_block = None;
+
+        for number in from.max(first_unfinalized).min(to)..=to {
+            let block = self
+                .source
+                .block(number)
+                .await?
+                .ok_or_else(|| anyhow!("Block {} not found", number))?;
+
+            if !self.extends_tracked(db_pool, &block).await? {
+                warn!("Block {} does not extend the tracked chain", number);
+                self.handle_reorg(db_pool).await?;
+                return Ok(None);
+            }
+
+            upsert_indexed_block(db_pool, &block.to_indexed()?).await?;
+            tracked.insert(number, block.hash);
+            last_block = Some(block);
+        }
+        let last_block = last_block.ok_or_else(|| anyhow!("Block {} not found", to))?;
+
+        let decoded = self.fetch_decoded(from, to).await?;
+        debug!("Decoded {} logs in blocks {}..={}", decoded.len(), from, to);
+
+        // Logs from a block other than the one just recorded mean the chain moved underneath us
+        let stale =
+            decoded.iter().any(|log| match (tracked.get(&log.block_number), log.block_hash) {
+                (Some(hash), Some(log_hash)) => *hash != log_hash,
+                _ => false,
+            });
+        if stale {
+            warn!("Logs in blocks {}..={} came from a reorganized chain", from, to);
+            self.handle_reorg(db_pool).await?;
+            return Ok(None);
+        }
+
+        let mut stats = LogSyncStats::default();
+        let mut holders: HashMap<U256, (Address, u64)> = HashMap::new();
+
+        for log in &decoded {
+            match &log.event {
+                PoolManagerEvent::Initialize(event) => {
+                    insert_pool(db_pool, &pool_from_initialize(event, log.block_number, log.timestamp))
+                        .await?;
+                    self.resolved_pools.lock().unwrap().insert(event.id);
+                    stats.pools += 1;
+                }
+                PoolManagerEvent::ModifyLiquidity(event) => {
+                    self.ensure_pool(db_pool, event.id, log.block_number).await?;
+
+                    let mut liquidity_event = liquidity_event_from_log(log, event);
+                    let token_id = self.token_id(event.sender, event.salt);
+                    liquidity_event.nft_id = token_id.map(|token_id| token_id.to_string());
+                    let holder = token_id
+                        .and_then(|token_id| holders.get(&token_id))
+                        .map(|(holder, _)| hex_address(*holder))
+                        .unwrap_or_else(|| liquidity_event.owner.clone());
+
+                    if apply_liquidity_event(db_pool, &liquidity_event, &holder).await? {
+                        stats.liquidity_events += 1;
+                    }
+                }
+                PoolManagerEvent::Swap(event) => {
+                    self.ensure_pool(db_pool, event.id, log.block_number).await?;
+                    if insert_swap(db_pool, &swap_from_log(log, event)).await? {
+                        stats.swaps += 1;
+                    }
+                }
+                PoolManagerEvent::Donate(event) => {
+                    self.ensure_pool(db_pool, event.id, log.block_number).await?;
+                    if insert_donation(db_pool, &donation_from_log(log, event)).await? {
+                        stats.donations += 1;
+                    }
+                }
+                PoolManagerEvent::Transfer(event) => {
+                    holders.insert(event.id, (event.to, log.block_number));
+                    stats.transfers += 1;
+                }
+            }
+        }
+
+        // Point transferred NFTs at their latest holder (burns keep the last holder)
+        for (token_id, (holder, block_number)) in &holders {
+            if !holder.is_zero() {
+                let (nft_id, holder) = (token_id.to_string(), hex_address(*holder));
+                transfer_position_owner(db_pool, &nft_id, &holder, *block_number as i64).await?;
+            }
+        }
+
+        upsert_sync_state(db_pool, SyncEntity::Logs, "", Some(to as i64), last_block.time()?)
+            .await?;
+
+        Ok(Some(stats))
+    }
+
+    /// Whether a block's parent matches the tracked block below it (if that one is tracked)
+    async fn extends_tracked(&self, db_pool: &PgPool, block: &BlockRef) -> Result<bool> {
+        let Some(number) = block.number.checked_sub(1) else { return Ok(true) };
+        Ok(match get_indexed_block(db_pool, number as i64).await? {
+            Some(parent) => parent.block_hash == block.parent_hash.to_string(),
+            None => true,
+        })
+    }
+
+    /// Roll back to the fork point if tracked blocks were reorganized away
+    ///
+    /// Returns whether a rollback happened.
+    async fn handle_reorg(&self, db_pool: &PgPool) -> Result<bool> {
+        let tracked = get_indexed_blocks(db_pool).await?;
+        let Some(fork) = self.find_fork(&tracked).await? else { return Ok(false) };
+
+        warn!(
+            "Chain reorganization detected: rolling back to block {} ({})",
+            fork.block_number, fork.block_hash
+        );
+        let removed = rollback_above_block(db_pool, fork).await?;
+        // Pools created above the fork were rolled back too
+        self.resolved_pools.lock().unwrap().clear();
+        info!("Rolled back {} events above block {}", removed, fork.block_number);
+
+        Ok(true)
+    }
+
+    /// Find the highest tracked block that is still canonical
+    ///
+    /// `tracked` must be ordered newest first. Returns `None` if the newest tracked block is
+    /// still canonical, and an error if none are (the reorg is deeper than the tracked
+    /// window and needs a manual resync).
+    async fn find_fork<'a>(&self, tracked: &'a [IndexedBlock]) -> Result<Option<&'a IndexedBlock>> {
+        for (i, block) in tracked.iter().enumerate() {
+            let canonical = self.source.block(block.block_number as u64).await?;
+            if canonical.is_some_and(|c| c.hash.to_string() == block.block_hash) {
+                return Ok((i > 0).then_some(block));
+            }
+        }
+
+        match tracked.last() {
+            Some(oldest) => bail!(
+                "Reorg goes deeper than the oldest tracked block {}; increase LOG_INDEXER_CONFIRMATIONS and resync",
+                oldest.block_number
+            ),
+            None => Ok(None),
+        }
+    }
+
+    /// Fetch and decode all relevant logs in an inclusive block range, in chain order
+    pub async fn fetch_decoded(&self, from: u64, to: u64) -> Result<Vec<DecodedLog>> {
+        let mut addresses = vec![self.pool_manager];
+        addresses.extend(self.position_manager);
+
+        let filter = Filter::new().address(addresses).from_block(from).to_block(to).events([
+            IPoolManager::Initialize::SIGNATURE,
+            IPoolManager::ModifyLiquidity::SIGNATURE,
+            IPoolManager::Swap::SIGNATURE,
+            IPoolManager::Donate::SIGNATURE,
+            IPositionManager::Transfer::SIGNATURE,
+        ]);
+
+        let logs = self.source.logs(&filter).await?;
+        self.decode_logs(logs).await
+    }
+
+    /// Decode relevant logs in chain order, skipping removed and unrelated ones
+    async fn decode_logs(&self, mut logs: Vec<Log>) -> Result<Vec<DecodedLog>> {
+        logs.retain(|log| !log.removed);
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+
+        let mut timestamps: HashMap<u64, u64> = HashMap::new();
+        let mut decoded = Vec::with_capacity(logs.len());
+        for log in &logs {
+            let event = match decode_log(log, self.pool_manager, self.position_manager) {
+                Ok(Some(event)) => event,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to decode log {:?}: {}", log.transaction_hash, e);
+                    continue;
+                }
+            };
+
+            let block_number = log.block_number.ok_or_else(|| anyhow!("Log without block"))?;
+            let tx_hash = log.transaction_hash.ok_or_else(|| anyhow!("Log without tx hash"))?;
+            let log_index = log.log_index.ok_or_else(|| anyhow!("Log without log index"))?;
+
+            let timestamp = match log.block_timestamp.or(timestamps.get(&block_number).copied()) {
+                Some(timestamp) => timestamp,
+                None => {
+                    let block = self
+                        .source
+                        .block(block_number)
+                        .await?
+                        .ok_or_else(|| anyhow!("Block {} not found", block_number))?;
+                    block.timestamp
+                }
+            };
+            timestamps.insert(block_number, timestamp);
+
+            decoded.push(DecodedLog {
+                tx_hash: tx_hash.to_string(),
+                block_hash: log.block_hash,
+                log_index,
+                block_number,
+                timestamp: DateTime::from_timestamp(timestamp as i64, 0)
+                    .ok_or_else(|| anyhow!("Invalid timestamp for block {}", block_number))?,
+                event,
+            });
+        }
+
+        Ok(decoded)
+    }
+
+    /// Fill in the log index of swaps stored before log indexes were tracked
+    ///
+    /// Each transaction's receipt is re-fetched and its PoolManager `Swap` logs matched to
+    /// the stored swaps by pool and amounts, which also fills in their pool state. Swaps
+    /// whose receipt has no matching log are dropped and their checkpoints rewound so the
+    /// next sync re-ingests them. Swaps whose receipt is unavailable (e.g. a pruned or
+    /// lagging node) are kept for the next run, and RPC failures abort the backfill and
+    /// leave the remaining swaps for the next run.
+    pub async fn backfill_swap_log_indexes(&self, db_pool: &PgPool) -> Result<SwapBackfillStats> {
+        let tx_hashes = get_unindexed_swap_txs(db_pool).await?;
+        if tx_hashes.is_empty() {
+            return Ok(SwapBackfillStats::default());
+        }
+        info!("Backfilling log indexes of swaps in {} transactions", tx_hashes.len());
+
+        let mut stats = SwapBackfillStats::default();
+        for tx_hash in tx_hashes {
+            let logs = match tx_hash.parse::<B256>() {
+                Ok(hash) => self.source.receipt_logs(hash).await?,
+                Err(_) => None,
+            };
+            let Some(logs) = logs else {
+                warn!("No receipt for {}, leaving its swaps for the next run", tx_hash);
+                stats.pending += 1;
+                continue;
+            };
+
+            for log in self.decode_logs(logs).await? {
+                let PoolManagerEvent::Swap(event) = &log.event else {
+                    continue;
+                };
+                if backfill_swap_log_index(db_pool, &swap_from_log(&log, event)).await? {
+                    stats.recovered += 1;
+                }
+            }
+
+            let discarded = discard_unindexed_swaps(db_pool, &tx_hash).await?;
+            if discarded > 0 {
+                warn!("Dropped {} unmatched swaps of {} for re-ingestion", discarded, tx_hash);
+                stats.discarded += discarded;
+            }
+        }
+
+        info!("Swap log index backfill complete: {:?}", stats);
+        Ok(stats)
+    }
+
+    /// PositionManager token id for liquidity owned by the PositionManager (salt = tokenId)
+    fn token_id(&self, sender: Address, salt: B256) -> Option<U256> {
+        (Some(sender) == self.position_manager).then(|| U256::from_be_bytes(salt.0))
+    }
+
+    /// Make sure a pool row exists, looking up its Initialize log if it predates the sync
+    ///
+    /// `block` is the block of the event that references the pool; the Initialize log is
+    /// searched for between the start block and it. Resolved pools are cached.
+    async fn ensure_pool(&self, db_pool: &PgPool, pool_id: B256, block: u64) -> Result<()> {
+        if self.resolved_pools.lock().unwrap().contains(&pool_id) {
+            return Ok(());
+        }
+
+        // Pools first seen through the subgraph still need their Initialize data
+        let pool = get_pool_by_id(db_pool, &pool_id.to_string()).await?;
+        if pool.as_ref().is_some_and(|p| p.initial_sqrt_price_x96.is_some()) {
+            self.resolved_pools.lock().unwrap().insert(pool_id);
+            return Ok(());
+        }
+
+        let Some(log) = self.find_initialize_log(pool_id, block).await? else {
+            if pool.is_none() {
+                bail!("Initialize log not found for pool {}", pool_id);
+            }
+            warn!(
+                "Initialize log for pool {} predates block {}; keeping its subgraph data",
+                pool_id, self.start_block
+            );
+            self.resolved_pools.lock().unwrap().insert(pool_id);
+            return Ok(());
+        };
+
+        let event = IPoolManager::Initialize::decode_log_data(log.data(), true)?;
+        let block_number = log.block_number.ok_or_else(|| anyhow!("Log without block"))?;
+        let timestamp = match log.block_timestamp {
+            Some(timestamp) => timestamp,
+            None => {
+                self.source
+                    .block(block_number)
+                    .await?
+                    .ok_or_else(|| anyhow!("Block {} not found", block_number))?
+                    .timestamp
+            }
+        };
+        let created_at = DateTime::from_timestamp(timestamp as i64, 0)
+            .ok_or_else(|| anyhow!("Invalid timestamp for block {}", block_number))?;
+
+        insert_pool(db_pool, &pool_from_initialize(&event, block_number, created_at)).await?;
+        self.resolved_pools.lock().unwrap().insert(pool_id);
+        Ok(())
+    }
+
+    /// Find a pool's Initialize log at or before `block`
+    ///
+    /// Walks back to the start block one chunk at a time, so no `eth_getLogs` call spans
+    /// more than `chunk_size` blocks.
+    async fn find_initialize_log(&self, pool_id: B256, block: u64) -> Result<Option<Log>> {
+        let mut to = block;
+        while to >= self.start_block {
+            let from = to.saturating_sub(self.chunk_size - 1).max(self.start_block);
+            let filter = Filter::new()
+                .address(self.pool_manager)
+                .event_signature(IPoolManager::Initialize::SIGNATURE_HASH)
+                .topic1(pool_id)
+                .from_block(from)
+                .to_block(to);
+            if let Some(log) = self.source.logs(&filter).await?.into_iter().next() {
+                return Ok(Some(log));
+            }
+            if from == 0 {
+                break;
+            }
+            to = from - 1;
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../fixtures/pool_manager_logs.json");
+
+    fn pool_manager() -> Address {
+        "0x00b036b58a818b1bc34d502d3fe730db729e62ac".parse().unwrap()
+    }
+
+    fn position_manager() -> Address {
+        "0xf969aee60879c54baaed9f3ed26147db216fd664".parse().unwrap()
+    }
+
+    fn fixture_indexer() -> LogIndexer<RecordedLogs> {
+        let source = RecordedLogs::from_json(FIXTURE).unwrap();
+        LogIndexer::new(source, pool_manager(), Some(position_manager()), 0)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_decoded_fixture_in_chain_order() {
+        let indexer = fixture_indexer();
+        let decoded = indexer.fetch_decoded(0, 100).await.unwrap();
+
+        let kinds: Vec<&str> = decoded
+            .iter()
+            .map(|d| match d.event {
+                PoolManagerEvent::Initialize(_) => "initialize",
+                PoolManagerEvent::ModifyLiquidity(_) => "modify_liquidity",
+                PoolManagerEvent::Swap(_) => "swap",
+                PoolManagerEvent::Donate(_) => "donate",
+                PoolManagerEvent::Transfer(_) => "transfer",
+            })
+            .collect();
+        assert_eq!(kinds, ["initialize", "transfer", "modify_liquidity", "swap", "donate"]);
+        assert!(decoded.windows(2).all(|w| w[0].block_number <= w[1].block_number));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_decoded_respects_block_range() {
+        let indexer = fixture_indexer();
+        let latest = indexer.source().latest_block().await.unwrap();
+        let decoded = indexer.fetch_decoded(latest, latest).await.unwrap();
+
+        assert!(decoded.iter().all(|d| d.block_number == latest));
+    }
+
+    #[tokio::test]
+    async fn test_fixture_converts_to_rows() {
+        let indexer = fixture_indexer();
+        let decoded = indexer.fetch_decoded(0, 100).await.unwrap();
+
+        for log in &decoded {
+            match &log.event {
+                PoolManagerEvent::Initialize(event) => {
+                    let pool = pool_from_initialize(event, log.block_number, log.timestamp);
+                    assert_eq!(pool.fee_tier, 3000);
+                    assert_eq!(pool.tick_spacing, 60);
+                    assert_eq!(pool.token0, pool.token0.to_lowercase());
+                    assert_eq!(pool.hooks, Some(hex_address(event.hooks)));
+                    assert!(!pool.dynamic_fee);
+                    assert_eq!(pool.hook_flags, Some(0));
+                    assert_eq!(pool.created_block, Some(log.block_number as i64));
+                    assert_eq!(pool.initial_sqrt_price_x96, Some(U256::from(event.sqrtPriceX96)));
+                    assert_eq!(pool.initial_tick, Some(event.tick.as_i32()));
+                }
+                PoolManagerEvent::ModifyLiquidity(event) => {
+                    let liquidity_event = liquidity_event_from_log(log, event);
+                    assert_eq!(liquidity_event.liquidity_delta, I256::try_from(1_000_000).unwrap());
+                    assert_eq!(liquidity_event.event_id, log.event_id());
+                    assert_eq!(liquidity_event.owner, hex_address(position_manager()));
+                    assert_eq!(indexer.token_id(event.sender, event.salt), Some(U256::from(1u64)));
+                }
+                PoolManagerEvent::Swap(event) => {
+                    // The swapper paid token0 and received token1
+                    let swap = swap_from_log(log, event);
+                    assert!(swap.amount0 > I256::ZERO);
+                    assert!(swap.amount1 < I256::ZERO);
+                    assert_eq!(swap.log_index, log.log_index as i64);
+                    assert_eq!(swap.sqrt_price_x96, Some(U256::from(event.sqrtPriceX96)));
+                    assert_eq!(swap.tick, Some(event.tick.as_i32()));
+                    assert_eq!(swap.liquidity, Some(U256::from(event.liquidity)));
+                    assert_eq!(swap.fee, Some(3000));
+                }
+                PoolManagerEvent::Donate(event) => {
+                    let donation = donation_from_log(log, event);
+                    assert_eq!(donation.amount0, U256::from(500u64));
+                }
+                PoolManagerEvent::Transfer(event) => {
+                    assert!(event.from.is_zero());
+                }
+            }
+        }
+    }
+
+    fn tracked_fixture_blocks(indexer: &LogIndexer<RecordedLogs>) -> Vec<IndexedBlock> {
+        let mut tracked: Vec<IndexedBlock> =
+            indexer.source().blocks.iter().map(|b| b.to_indexed().unwrap()).collect();
+        tracked.reverse();
+        tracked
+    }
+
+    #[tokio::test]
+    async fn test_find_fork_on_canonical_chain() {
+        let indexer = fixture_indexer();
+        let tracked = tracked_fixture_blocks(&indexer);
+
+        assert!(indexer.find_fork(&tracked).await.unwrap().is_none());
+        assert!(indexer.find_fork(&[]).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_find_fork_after_reorg() {
+        let indexer = fixture_indexer();
+        let mut tracked = tracked_fixture_blocks(&indexer);
+
+        // Block 3 was ingested from a branch that has since been replaced
+        tracked[0].block_hash = B256::repeat_byte(0xff).to_string();
+
+        let fork = indexer.find_fork(&tracked).await.unwrap().unwrap();
+        assert_eq!(fork.block_number, 2);
+    }
+
+    #[tokio::test]
+    async fn test_find_fork_deeper_than_tracked_window() {
+        let indexer = fixture_indexer();
+        let mut tracked = tracked_fixture_blocks(&indexer);
+        for block in &mut tracked {
+            block.block_hash = B256::repeat_byte(0xff).to_string();
+        }
+
+        assert!(indexer.find_fork(&tracked).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_initialize_log_in_bounded_chunks() {
+        let indexer = fixture_indexer().with_chunk_size(1);
+        let latest = indexer.source().latest_block().await.unwrap();
+        let decoded = indexer.fetch_decoded(0, latest).await.unwrap();
+        let (init_block, pool_id) = decoded
+            .iter()
+            .find_map(|d| match &d.event {
+                PoolManagerEvent::Initialize(event) => Some((d.block_number, event.id)),
+                _ => None,
+            })
+            .unwrap();
+
+        let log = indexer.find_initialize_log(pool_id, latest).await.unwrap().unwrap();
+        assert_eq!(log.block_number, Some(init_block));
+
+        // Pools initialized before the start block are not searched for
+        let late = LogIndexer::new(indexer.source().clone(), pool_manager(), None, init_block + 1);
+        assert!(late.find_initialize_log(pool_id, latest).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_receipt_logs_from_recording() {
+        let indexer = fixture_indexer();
+        let tx_hash = indexer.source().logs[0].transaction_hash.unwrap();
+
+        let logs = indexer.source().receipt_logs(tx_hash).await.unwrap().unwrap();
+        assert!(logs.iter().all(|log| log.transaction_hash == Some(tx_hash)));
+        assert!(indexer.source().receipt_logs(B256::ZERO).await.unwrap().is_none());
+    }
+
+    /// Backfills swaps stored without a log index in the database in `DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires a scratch Postgres database in DATABASE_URL"]
+    async fn test_backfill_swap_log_indexes_into_database() {
+        let db_pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let indexer = fixture_indexer();
+        let decoded = indexer.fetch_decoded(0, 100).await.unwrap();
+
+        let mut swap = None;
+        for log in &decoded {
+            match &log.event {
+                PoolManagerEvent::Initialize(event) => {
+                    let pool = pool_from_initialize(event, log.block_number, log.timestamp);
+                    insert_pool(&db_pool, &pool).await.unwrap();
+                }
+                PoolManagerEvent::Swap(event) => swap = Some(swap_from_log(log, event)),
+                _ => {}
+            }
+        }
+        let swap = swap.unwrap();
+
+        // One swap its receipt still has, one its receipt does not have, and one from a
+        // transaction without a receipt
+        let swapless_tx = decoded
+            .iter()
+            .find(|log| matches!(log.event, PoolManagerEvent::Initialize(_)))
+            .map(|log| log.tx_hash.clone())
+            .unwrap();
+        let unknown_tx = B256::repeat_byte(0xee).to_string();
+        for tx_hash in [&swap.tx_hash, &swapless_tx, &unknown_tx] {
+            sqlx::query("DELETE FROM swaps WHERE tx_hash = $1")
+                .bind(tx_hash)
+                .execute(&db_pool)
+                .await
+                .unwrap();
+            sqlx::query(
+                "INSERT INTO swaps (tx_hash, pool_id, amount0, amount1, timestamp) \
+                 VALUES ($1, $2, $3::numeric, $4::numeric, $5)",
+            )
+            .bind(tx_hash)
+            .bind(&swap.pool_id)
+            .bind(swap.amount0.to_string())
+            .bind(swap.amount1.to_string())
+            .bind(swap.timestamp)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        }
+
+        let stats = indexer.backfill_swap_log_indexes(&db_pool).await.unwrap();
+        assert_eq!(stats, SwapBackfillStats { recovered: 1, discarded: 1, pending: 1 });
+
+        let since = DateTime::from_timestamp(0, 0).unwrap();
+        let swaps = stillwater_db::get_swaps_for_pool(&db_pool, &swap.pool_id, since).await.unwrap();
+        let backfilled = swaps.iter().find(|s| s.tx_hash == swap.tx_hash).unwrap();
+        assert_eq!(backfilled.log_index, swap.log_index);
+        assert_eq!(backfilled.liquidity, swap.liquidity);
+        assert!(swaps.iter().all(|s| s.tx_hash != swapless_tx));
+        assert!(get_unindexed_swap_txs(&db_pool).await.unwrap().contains(&unknown_tx));
+
+        let checkpoint = get_sync_state(&db_pool, SyncEntity::Swaps, &swap.pool_id).await.unwrap();
+        assert!(checkpoint.is_some_and(|c| c.last_timestamp <= swap.timestamp));
+    }
+
+    /// Rolls back NFT transfers and pool creations in the database in `DATABASE_URL`
+    #[tokio::test]
+    #[ignore = "requires a scratch Postgres database in DATABASE_URL"]
+    async fn test_rollback_restores_owners_and_removes_pools() {
+        let db_pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let decoded = fixture_indexer().fetch_decoded(0, 100).await.unwrap();
+        let initialize = decoded
+            .iter()
+            .find_map(|log| match &log.event {
+                PoolManagerEvent::Initialize(event) => Some(event),
+                _ => None,
+            })
+            .unwrap();
+
+        // Blocks far above the fixture, so the rollback leaves other tests' rows alone
+        let fork_block = 900_000_200;
+        let timestamp = Utc::now();
+        let mut kept = pool_from_initialize(initialize, fork_block as u64 - 100, timestamp);
+        kept.pool_id = B256::repeat_byte(0xa1).to_string();
+        let mut orphaned = pool_from_initialize(initialize, fork_block as u64 + 100, timestamp);
+        orphaned.pool_id = B256::repeat_byte(0xa2).to_string();
+        insert_pool(&db_pool, &kept).await.unwrap();
+        insert_pool(&db_pool, &orphaned).await.unwrap();
+
+        let nft_id = "900000001";
+        let minter = hex_address(Address::repeat_byte(0x01));
+        let event = LiquidityEvent {
+            id: 0,
+            event_id: format!("{}-0", B256::repeat_byte(0xa3)),
+            tx_hash: Some(B256::repeat_byte(0xa3).to_string()),
+            block_number: Some(fork_block - 50),
+            pool_id: kept.pool_id.clone(),
+            owner: hex_address(position_manager()),
+            tick_lower: -600,
+            tick_upper: 600,
+            salt: B256::from(U256::from(900_000_001u64)).to_string(),
+            liquidity_delta: I256::try_from(1_000_000).unwrap(),
+            timestamp,
+            nft_id: Some(nft_id.to_string()),
+        };
+        apply_liquidity_event(&db_pool, &event, &minter).await.unwrap();
+
+        let buyer = hex_address(Address::repeat_byte(0x02));
+        let reseller = hex_address(Address::repeat_byte(0x03));
+        transfer_position_owner(&db_pool, nft_id, &buyer, fork_block - 10).await.unwrap();
+        transfer_position_owner(&db_pool, nft_id, &reseller, fork_block + 10).await.unwrap();
+        transfer_position_owner(&db_pool, nft_id, &minter, fork_block + 20).await.unwrap();
+
+        let fork = IndexedBlock {
+            block_number: fork_block,
+            block_hash: B256::repeat_byte(0xa4).to_string(),
+            parent_hash: B256::repeat_byte(0xa5).to_string(),
+            timestamp,
+        };
+        rollback_above_block(&db_pool, &fork).await.unwrap();
+
+        let position = stillwater_db::get_position_by_nft(&db_pool, nft_id).await.unwrap();
+        assert_eq!(position.unwrap().owner, buyer);
+        assert!(get_pool_by_id(&db_pool, &kept.pool_id).await.unwrap().is_some());
+        assert!(get_pool_by_id(&db_pool, &orphaned.pool_id).await.unwrap().is_none());
+
+        prune_indexed_blocks(&db_pool, fork_block + 1).await.unwrap();
+        let changes: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM position_owner_changes WHERE nft_id = $1")
+                .bind(nft_id)
+                .fetch_one(&db_pool)
+                .await
+                .unwrap();
+        assert_eq!(changes, 0);
+    }
+
+    #[test]
+    fn test_decode_log_ignores_other_contracts() {
+        let source = RecordedLogs::from_json(FIXTURE).unwrap();
+        let mut log = source.logs[0].clone();
+        log.inner.address = Address::repeat_byte(0x11);
+
+        assert!(decode_log(&log, pool_manager(), None).unwrap().is_none());
+    }
+}
@@ -1,11 +1,17 @@
+//! GraphQL queries for the Uniswap v4 subgraph.
+//!
+//! Every list query takes `$first` and `$lastId` and orders by `id` ascending so
+//! callers can walk the full result set with an `id_gt` cursor (The Graph caps
+//! `first` at 1000 and `skip` at 5000, so offset pagination does not scale).
+
 /// GraphQL query to fetch modify liquidity events by origin (owner)
-pub const POSITIONS_BY_OWNER: &str = r#"
-query ModifyLiquidityByOrigin($owner: String!) {
+pub const MODIFY_LIQUIDITIES_BY_OWNER: &str = r#"
+query ModifyLiquidityByOrigin($owner: String!, $first: Int!, $lastId: String!) {
   modifyLiquidities(
-    where: { origin: $owner, amount_gt: "0" }
-    orderBy: timestamp
-    orderDirection: desc
-    first: 100
+    where: { origin: $owner, id_gt: $lastId }
+    orderBy: id
+    orderDirection: asc
+    first: $first
   ) {
     id
     timestamp
@@ -13,29 +19,44 @@ query ModifyLiquidityByOrigin($owner: String!) {
       id
       token0 {
         id
+        symbol
+        name
+        decimals
       }
       token1 {
         id
+        symbol
+        name
+        decimals
       }
       feeTier
       tickSpacing
+      hooks
+      createdAtTimestamp
+      createdAtBlockNumber
     }
     tickLower
     tickUpper
     amount
+    sender
     origin
+    transaction {
+      id
+      blockNumber
+      timestamp
+    }
   }
 }
 "#;
 
 /// GraphQL query to fetch modify liquidity events by pool ID
-pub const POSITIONS_BY_POOL: &str = r#"
-query ModifyLiquidityByPool($poolId: String!) {
+pub const MODIFY_LIQUIDITIES_BY_POOL: &str = r#"
+query ModifyLiquidityByPool($poolId: String!, $first: Int!, $lastId: String!) {
   modifyLiquidities(
-    where: { pool: $poolId, amount_gt: "0" }
-    orderBy: timestamp
-    orderDirection: desc
-    first: 100
+    where: { pool: $poolId, id_gt: $lastId }
+    orderBy: id
+    orderDirection: asc
+    first: $first
   ) {
     id
     timestamp
@@ -43,51 +64,102 @@ query ModifyLiquidityByPool($poolId: String!) {
       id
       token0 {
         id
+        symbol
+        name
+        decimals
       }
       token1 {
         id
+        symbol
+        name
+        decimals
       }
       feeTier
       tickSpacing
+      hooks
+      createdAtTimestamp
+      createdAtBlockNumber
     }
     tickLower
     tickUpper
     amount
+    sender
     origin
+    transaction {
+      id
+      blockNumber
+      timestamp
+    }
   }
 }
 "#;
 
 /// GraphQL query to fetch recent swaps for a pool
 pub const RECENT_SWAPS: &str = r#"
-query RecentSwaps($poolId: String!, $timestamp: BigInt!) {
+query RecentSwaps($poolId: String!, $timestamp: BigInt!, $first: Int!, $lastId: String!) {
+  swaps(
+    where: { pool: $poolId, timestamp_gte: $timestamp, id_gt: $lastId }
+    orderBy: id
+    orderDirection: asc
+    first: $first
+  ) {
+    id
+    transaction {
+      id
+      blockNumber
+      timestamp
+    }
+    pool {
+      id
+      feeTier
+    }
+    sender
+    amount0
+    amount1
+    sqrtPriceX96
+    tick
+    logIndex
+  }
+}
+"#;
+
+/// GraphQL query to fetch the swaps of a set of transactions
+pub const SWAPS_BY_TRANSACTIONS: &str = r#"
+query SwapsByTransactions($transactions: [String!]!, $first: Int!, $lastId: String!) {
   swaps(
-    where: { pool: $poolId, timestamp_gte: $timestamp }
-    orderBy: timestamp
+    where: { transaction_in: $transactions, id_gt: $lastId }
+    orderBy: id
     orderDirection: asc
+    first: $first
   ) {
     id
     transaction {
       id
+      blockNumber
       timestamp
     }
     pool {
       id
+      feeTier
     }
+    sender
     amount0
     amount1
+    sqrtPriceX96
+    tick
+    logIndex
   }
 }
 "#;
 
 /// GraphQL query to fetch all recent modify liquidity events (for polling)
-pub const RECENT_POSITIONS: &str = r#"
-query RecentModifyLiquidity($timestamp: BigInt!) {
+pub const RECENT_MODIFY_LIQUIDITIES: &str = r#"
+query RecentModifyLiquidity($timestamp: BigInt!, $first: Int!, $lastId: String!) {
   modifyLiquidities(
-    where: { timestamp_gte: $timestamp, amount_gt: "0" }
-    orderBy: timestamp
-    orderDirection: desc
-    first: 100
+    where: { timestamp_gte: $timestamp, id_gt: $lastId }
+    orderBy: id
+    orderDirection: asc
+    first: $first
   ) {
     id
     timestamp
@@ -95,17 +167,80 @@ query RecentModifyLiquidity($timestamp: BigInt!) {
       id
       token0 {
         id
+        symbol
+        name
+        decimals
       }
       token1 {
         id
+        symbol
+        name
+        decimals
       }
       feeTier
       tickSpacing
+      hooks
+      createdAtTimestamp
+      createdAtBlockNumber
     }
     tickLower
     tickUpper
     amount
+    sender
+    origin
+    transaction {
+      id
+      blockNumber
+      timestamp
+    }
+  }
+}
+"#;
+
+/// GraphQL query to fetch PositionManager positions (NFTs) by token ID
+pub const POSITIONS_BY_TOKEN_IDS: &str = r#"
+query PositionsByTokenIds($tokenIds: [BigInt!]!, $first: Int!, $lastId: String!) {
+  positions(
+    where: { tokenId_in: $tokenIds, id_gt: $lastId }
+    orderBy: id
+    orderDirection: asc
+    first: $first
+  ) {
+    id
+    tokenId
+    owner
     origin
+    createdAtTimestamp
+  }
+}
+"#;
+
+/// GraphQL query to fetch all recent PositionManager NFT transfers (including mints)
+pub const RECENT_TRANSFERS: &str = r#"
+query RecentTransfers($timestamp: BigInt!, $first: Int!, $lastId: String!) {
+  transfers(
+    where: { timestamp_gte: $timestamp, id_gt: $lastId }
+    orderBy: id
+    orderDirection: asc
+    first: $first
+  ) {
+    id
+    tokenId
+    from
+    to
+    timestamp
+  }
+}
+"#;
+
+/// GraphQL query for the subgraph's indexing status (not paginated)
+pub const META: &str = r#"
+query Meta {
+  _meta {
+    block {
+      number
+    }
+    hasIndexingErrors
   }
 }
 "#;
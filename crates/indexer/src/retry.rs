@@ -0,0 +1,311 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::fmt;
+use std::time::Duration;
+
+/// Timeouts and retry policy for subgraph requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// Timeout for a whole request, including reading the response
+    pub timeout: Duration,
+    /// Timeout for establishing a connection
+    pub connect_timeout: Duration,
+    /// Retries after the first attempt before giving up
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles on every further attempt
+    pub base_delay: Duration,
+    /// Upper bound for backoff delays, including a server's `Retry-After`
+    pub max_delay: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+            max_retries: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Load the retry policy from environment variables, falling back to the defaults
+    ///
+    /// Reads `GRAPH_REQUEST_TIMEOUT_SECS`, `GRAPH_MAX_RETRIES`, `GRAPH_RETRY_BASE_DELAY_MS`
+    /// and `GRAPH_RETRY_MAX_DELAY_SECS`.
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        Ok(Self {
+            timeout: env_u64("GRAPH_REQUEST_TIMEOUT_SECS")?
+                .map_or(defaults.timeout, Duration::from_secs),
+            connect_timeout: defaults.connect_timeout,
+            max_retries: env_u64("GRAPH_MAX_RETRIES")?.map_or(defaults.max_retries, |r| r as u32),
+            base_delay: env_u64("GRAPH_RETRY_BASE_DELAY_MS")?
+                .map_or(defaults.base_delay, Duration::from_millis),
+            max_delay: env_u64("GRAPH_RETRY_MAX_DELAY_SECS")?
+                .map_or(defaults.max_delay, Duration::from_secs),
+        })
+    }
+
+    /// Delay before retry number `attempt` (starting at 1)
+    ///
+    /// A `Retry-After` from the server takes precedence over [`backoff_delay`]; both are
+    /// capped at `max_delay`.
+    pub fn backoff(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        match retry_after {
+            Some(retry_after) => retry_after.min(self.max_delay),
+            None => backoff_delay(self.base_delay, self.max_delay, attempt),
+        }
+    }
+}
+
+/// Delay before retry number `attempt` (starting at 1)
+///
+/// Exponential backoff from `base` capped at `max`, with jitter over the upper half of the
+/// delay.
+pub fn backoff_delay(base: Duration, max: Duration, attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(16);
+    let delay = base.saturating_mul(1 << exponent).min(max);
+
+    let half = delay / 2;
+    let jitter = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+    half + Duration::from_millis(jitter)
+}
+
+/// Read an optional integer environment variable
+pub fn env_u64(name: &str) -> Result<Option<u64>> {
+    std::env::var(name)
+        .ok()
+        .map(|v| v.parse::<u64>().with_context(|| format!("Invalid {}", name)))
+        .transpose()
+}
+
+/// Failure of a single subgraph request, classified by whether retrying can help
+#[derive(Debug)]
+pub enum QueryError {
+    /// HTTP 429; the server may say when to come back
+    RateLimited { retry_after: Option<Duration> },
+    /// HTTP 5xx from the gateway or graph-node
+    Server { status: u16, body: String, retry_after: Option<Duration> },
+    /// Any other non-success HTTP status
+    Client { status: u16, body: String },
+    /// Connection failures, timeouts and interrupted responses
+    Transport(reqwest::Error),
+    /// The subgraph has not indexed the requested block yet
+    IndexingLag(String),
+    /// No indexer could serve the query right now (gateway routing, overload)
+    Unavailable(String),
+    /// The query itself was rejected (syntax, unknown fields, bad variables)
+    Query(String),
+    /// The response was not a valid GraphQL response
+    InvalidResponse(String),
+}
+
+impl QueryError {
+    /// Whether the request may succeed if retried
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            QueryError::RateLimited { .. }
+            | QueryError::Server { .. }
+            | QueryError::Transport(_)
+            | QueryError::IndexingLag(_)
+            | QueryError::Unavailable(_) => true,
+            QueryError::Client { .. } | QueryError::Query(_) | QueryError::InvalidResponse(_) => {
+                false
+            }
+        }
+    }
+
+    /// How long the server asked us to wait, if it did
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            QueryError::RateLimited { retry_after } => *retry_after,
+            QueryError::Server { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Classify a non-success HTTP response
+    pub fn from_status(status: u16, headers: &HeaderMap, body: String) -> Self {
+        let retry_after = parse_retry_after(headers, Utc::now());
+        match status {
+            429 => QueryError::RateLimited { retry_after },
+            500..=599 => QueryError::Server { status, body, retry_after },
+            _ => QueryError::Client { status, body },
+        }
+    }
+
+    /// Classify the `errors` of a GraphQL response
+    ///
+    /// Lag and availability errors win over query errors, since graph-node and the gateway
+    /// report them alongside whatever else went wrong.
+    pub fn from_graphql_errors(messages: &[String]) -> Self {
+        let joined = messages.join(", ");
+        let lowered = joined.to_lowercase();
+
+        const LAG: &[&str] = &["has only indexed up to", "not yet available", "block not found"];
+        const UNAVAILABLE: &[&str] = &[
+            "bad indexers",
+            "no indexers",
+            "indexer not available",
+            "unavailable",
+            "timeout",
+            "timed out",
+            "too many requests",
+            "rate limit",
+            "store error",
+            "database unavailable",
+        ];
+
+        if LAG.iter().any(|p| lowered.contains(p)) {
+            QueryError::IndexingLag(joined)
+        } else if UNAVAILABLE.iter().any(|p| lowered.contains(p)) {
+            QueryError::Unavailable(joined)
+        } else {
+            QueryError::Query(joined)
+        }
+    }
+}
+
+impl From<reqwest::Error> for QueryError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_decode() {
+            QueryError::InvalidResponse(error.to_string())
+        } else {
+            QueryError::Transport(error)
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::RateLimited { retry_after } => {
+                write!(f, "GraphQL request rate limited (retry after {:?})", retry_after)
+            }
+            QueryError::Server { status, body, .. } | QueryError::Client { status, body } => {
+                write!(f, "GraphQL request failed with status {}: {}", status, body)
+            }
+            QueryError::Transport(e) => write!(f, "Failed to send GraphQL request: {}", e),
+            QueryError::IndexingLag(m) => write!(f, "Subgraph indexing lag: {}", m),
+            QueryError::Unavailable(m) => write!(f, "Subgraph unavailable: {}", m),
+            QueryError::Query(m) => write!(f, "GraphQL errors: {}", m),
+            QueryError::InvalidResponse(m) => write!(f, "Failed to parse GraphQL response: {}", m),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            QueryError::Transport(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a `Retry-After` header (delay in seconds or an HTTP date)
+fn parse_retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((date - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(parse_retry_after(&headers("120"), now), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after(&headers("Wed, 21 Oct 2015 07:28:30 GMT"), now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after(&headers("Wed, 21 Oct 2015 07:27:00 GMT"), now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after(&headers("soon"), now), None);
+        assert_eq!(parse_retry_after(&HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn test_classify_status() {
+        let error = QueryError::from_status(429, &headers("7"), String::new());
+        assert!(error.is_retryable());
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(7)));
+
+        assert!(QueryError::from_status(502, &HeaderMap::new(), String::new()).is_retryable());
+        assert!(!QueryError::from_status(400, &HeaderMap::new(), String::new()).is_retryable());
+        assert!(!QueryError::from_status(401, &HeaderMap::new(), String::new()).is_retryable());
+    }
+
+    #[test]
+    fn test_classify_graphql_errors() {
+        let lag = QueryError::from_graphql_errors(&[
+            "Failed to decode `block.number` value: `subgraph QmX has only indexed up to block number 100 and data for block number 120 is therefore not yet available`".to_string(),
+        ]);
+        assert!(matches!(lag, QueryError::IndexingLag(_)));
+        assert!(lag.is_retryable());
+
+        let unavailable = QueryError::from_graphql_errors(&[
+            "bad indexers: {0xabc: Unavailable(too behind)}".to_string(),
+        ]);
+        assert!(matches!(unavailable, QueryError::Unavailable(_)));
+        assert!(unavailable.is_retryable());
+
+        let query = QueryError::from_graphql_errors(&[
+            "Type `Query` has no field `modifyLiquidity`".to_string(),
+        ]);
+        assert!(matches!(query, QueryError::Query(_)));
+        assert!(!query.is_retryable());
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let (base, max) = (Duration::from_secs(1), Duration::from_secs(10));
+
+        for _ in 0..20 {
+            let first = backoff_delay(base, max, 1);
+            assert!(first >= Duration::from_millis(500) && first <= Duration::from_secs(1));
+
+            let third = backoff_delay(base, max, 3);
+            assert!(third >= Duration::from_secs(2) && third <= Duration::from_secs(4));
+
+            let capped = backoff_delay(base, max, 30);
+            assert!(capped >= Duration::from_secs(5) && capped <= Duration::from_secs(10));
+        }
+    }
+
+    #[test]
+    fn test_backoff_honors_capped_retry_after() {
+        let config = RetryConfig { max_delay: Duration::from_secs(60), ..RetryConfig::default() };
+
+        let server = config.backoff(1, Some(Duration::from_secs(30)));
+        assert_eq!(server, Duration::from_secs(30));
+
+        let hostile = config.backoff(1, Some(Duration::from_secs(86_400)));
+        assert_eq!(hostile, Duration::from_secs(60));
+    }
+}
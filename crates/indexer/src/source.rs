@@ -0,0 +1,315 @@
+use anyhow::{anyhow, Context, Result};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::retry::{QueryError, RetryConfig};
+use crate::types::GraphQLResponse;
+
+/// Backend that executes subgraph GraphQL queries
+pub trait IndexerSource {
+    /// Execute a query and return the response's `data` object
+    fn execute(&self, query: &str, variables: Value) -> impl Future<Output = Result<Value>> + Send;
+}
+
+/// Subgraph reached over HTTP (The Graph gateway or a hosted endpoint)
+///
+/// Rate limits, server errors, transport failures and indexing lag are retried with
+/// exponential backoff; query errors fail immediately.
+pub struct GraphClient {
+    client: Client,
+    graph_url: String,
+    retry: RetryConfig,
+}
+
+impl GraphClient {
+    /// Create a new client for a subgraph URL with the default retry policy
+    pub fn new(graph_url: String) -> Self {
+        Self::with_retry_config(graph_url, RetryConfig::default())
+    }
+
+    /// Create a new client for a subgraph URL with a custom retry policy
+    pub fn with_retry_config(graph_url: String, retry: RetryConfig) -> Self {
+        let client = Client::builder()
+            .timeout(retry.timeout)
+            .connect_timeout(retry.connect_timeout)
+            .build()
+            .expect("Failed to build HTTP client");
+        Self { client, graph_url, retry }
+    }
+
+    /// Send a query once and classify any failure
+    async fn try_execute(&self, body: &Value) -> std::result::Result<Value, QueryError> {
+        let response = self.client.post(&self.graph_url).json(body).send().await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let text = response.text().await.unwrap_or_default();
+            return Err(QueryError::from_status(status.as_u16(), &headers, text));
+        }
+
+        let result: GraphQLResponse<Value> = response.json().await?;
+
+        if let Some(errors) = result.errors.filter(|errors| !errors.is_empty()) {
+            let messages: Vec<String> = errors.into_iter().map(|e| e.message).collect();
+            return Err(QueryError::from_graphql_errors(&messages));
+        }
+
+        result
+            .data
+            .ok_or_else(|| QueryError::InvalidResponse("No data in GraphQL response".to_string()))
+    }
+}
+
+impl IndexerSource for GraphClient {
+    async fn execute(&self, query: &str, variables: Value) -> Result<Value> {
+        let body = json!({
+            "query": query,
+            "variables": variables
+        });
+
+        info!("Sending GraphQL query to {}", self.graph_url);
+        debug!("Query variables: {:?}", variables);
+
+        let mut attempt = 0;
+        loop {
+            match self.try_execute(&body).await {
+                Ok(data) => return Ok(data),
+                Err(e) if e.is_retryable() && attempt < self.retry.max_retries => {
+                    attempt += 1;
+                    let delay = self.retry.backoff(attempt, e.retry_after());
+                    warn!(
+                        "GraphQL request failed ({}), retry {}/{} in {:?}",
+                        e, attempt, self.retry.max_retries, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// In-memory subgraph that replays canned entities (tests and offline runs)
+///
+/// Each query is answered from the entity list named by its root field, following the
+/// `$first`/`$lastId` cursor like The Graph does. Other `where` filters are not applied,
+/// so fixtures should only contain the entities a scenario expects back.
+#[derive(Default)]
+pub struct FixtureSource {
+    entities: HashMap<String, Vec<Value>>,
+    meta: Option<Value>,
+    requests: Mutex<Vec<Value>>,
+}
+
+impl FixtureSource {
+    /// Create an empty fixture source
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load entities from JSON shaped like a subgraph `data` object
+    /// (e.g. `{ "swaps": [...], "modifyLiquidities": [...] }`)
+    pub fn from_json(json: &str) -> Result<Self> {
+        let data: HashMap<String, Vec<Value>> =
+            serde_json::from_str(json).context("Failed to parse subgraph fixture")?;
+
+        Ok(data
+            .into_iter()
+            .fold(Self::new(), |source, (field, entities)| source.with_entities(&field, entities)))
+    }
+
+    /// Serve `entities` for queries on the `field` root field
+    pub fn with_entities(mut self, field: &str, mut entities: Vec<Value>) -> Self {
+        entities.sort_by(|a, b| entity_id(a).cmp(entity_id(b)));
+        self.entities.insert(field.to_string(), entities);
+        self
+    }
+
+    /// Answer `_meta` queries with an indexing status
+    pub fn with_meta(mut self, block_number: u64, has_indexing_errors: bool) -> Self {
+        self.meta = Some(json!({
+            "block": { "number": block_number },
+            "hasIndexingErrors": has_indexing_errors
+        }));
+        self
+    }
+
+    /// Variables of every query executed so far, in order
+    pub fn requests(&self) -> Vec<Value> {
+        self.requests.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+}
+
+impl IndexerSource for FixtureSource {
+    async fn execute(&self, query: &str, variables: Value) -> Result<Value> {
+        let field = root_field(query).ok_or_else(|| anyhow!("Query has no root field"))?;
+        if field == "_meta" {
+            let meta = self.meta.clone().ok_or_else(|| anyhow!("Fixture has no _meta"))?;
+            return Ok(json!({ "_meta": meta }));
+        }
+
+        let first = variables["first"].as_u64().map(|f| f as usize).unwrap_or(usize::MAX);
+        let last_id = variables["lastId"].as_str().unwrap_or_default();
+
+        let page: Vec<&Value> = self
+            .entities
+            .get(field)
+            .map(|entities| {
+                entities.iter().filter(|e| entity_id(e) > last_id).take(first).collect()
+            })
+            .unwrap_or_default();
+        let data = json!({ field: page });
+
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.push(variables);
+        }
+
+        Ok(data)
+    }
+}
+
+fn entity_id(entity: &Value) -> &str {
+    entity["id"].as_str().unwrap_or_default()
+}
+
+/// Name of the first field selected by a query (`modifyLiquidities`, `swaps`, ...)
+fn root_field(query: &str) -> Option<&str> {
+    let selection = &query[query.find('{')? + 1..];
+    let selection = selection.trim_start();
+    let end = selection.find(|c: char| !c.is_alphanumeric() && c != '_')?;
+    Some(&selection[..end]).filter(|field| !field.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queries;
+    use std::time::Duration;
+
+    #[test]
+    fn test_root_field() {
+        assert_eq!(root_field(queries::RECENT_SWAPS), Some("swaps"));
+        assert_eq!(root_field(queries::RECENT_MODIFY_LIQUIDITIES), Some("modifyLiquidities"));
+        assert_eq!(root_field(queries::POSITIONS_BY_TOKEN_IDS), Some("positions"));
+        assert_eq!(root_field(queries::META), Some("_meta"));
+        assert_eq!(root_field("query Broken"), None);
+    }
+
+    /// Serve canned HTTP responses in order, one per connection
+    async fn serve(responses: Vec<String>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 64 * 1024];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        url
+    }
+
+    fn http_response(status: &str, extra_headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+            status,
+            body.len(),
+            extra_headers,
+            body
+        )
+    }
+
+    fn fast_retries() -> RetryConfig {
+        RetryConfig {
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            ..RetryConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_graph_client_retries_transient_failures() {
+        let url = serve(vec![
+            http_response("503 Service Unavailable", "", "upstream error"),
+            http_response("429 Too Many Requests", "Retry-After: 0\r\n", ""),
+            http_response(
+                "200 OK",
+                "",
+                r#"{"errors":[{"message":"bad indexers: {0xabc: Unavailable(too behind)}"}]}"#,
+            ),
+            http_response("200 OK", "", r#"{"data":{"swaps":[]}}"#),
+        ])
+        .await;
+
+        let client = GraphClient::with_retry_config(url, fast_retries());
+        let data = client.execute(queries::RECENT_SWAPS, json!({})).await.unwrap();
+
+        assert_eq!(data, json!({ "swaps": [] }));
+    }
+
+    #[tokio::test]
+    async fn test_graph_client_does_not_retry_query_errors() {
+        let url = serve(vec![
+            http_response(
+                "200 OK",
+                "",
+                r#"{"errors":[{"message":"Type `Query` has no field `swap`"}]}"#,
+            ),
+            http_response("200 OK", "", r#"{"data":{"swaps":[]}}"#),
+        ])
+        .await;
+
+        let client = GraphClient::with_retry_config(url, fast_retries());
+        let error = client.execute(queries::RECENT_SWAPS, json!({})).await.unwrap_err();
+
+        assert!(matches!(error.downcast_ref::<QueryError>(), Some(QueryError::Query(_))));
+    }
+
+    #[tokio::test]
+    async fn test_graph_client_gives_up_after_max_retries() {
+        let responses = (0..3).map(|_| http_response("502 Bad Gateway", "", "")).collect();
+        let url = serve(responses).await;
+
+        let config = RetryConfig { max_retries: 2, ..fast_retries() };
+        let client = GraphClient::with_retry_config(url, config);
+        let error = client.execute(queries::RECENT_SWAPS, json!({})).await.unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<QueryError>(),
+            Some(QueryError::Server { status: 502, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fixture_source_follows_cursor() {
+        let source = FixtureSource::new().with_entities(
+            "swaps",
+            vec![json!({ "id": "0xc" }), json!({ "id": "0xa" }), json!({ "id": "0xb" })],
+        );
+
+        let data = source
+            .execute(queries::RECENT_SWAPS, json!({ "first": 2, "lastId": "" }))
+            .await
+            .unwrap();
+        assert_eq!(data["swaps"], json!([{ "id": "0xa" }, { "id": "0xb" }]));
+
+        let data = source
+            .execute(queries::RECENT_SWAPS, json!({ "first": 2, "lastId": "0xb" }))
+            .await
+            .unwrap();
+        assert_eq!(data["swaps"], json!([{ "id": "0xc" }]));
+
+        assert_eq!(source.requests().len(), 2);
+    }
+}
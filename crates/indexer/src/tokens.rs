@@ -0,0 +1,49 @@
+use alloy::primitives::Address;
+use anyhow::{Context, Result};
+use sqlx::PgPool;
+use stillwater_db::{get_token, get_unknown_pool_tokens, insert_token};
+use stillwater_models::{BlockchainService, Token};
+use tracing::{info, warn};
+
+/// Get a token's metadata from the registry, reading it from the chain if unknown
+pub async fn get_or_fetch_token(
+    db_pool: &PgPool,
+    blockchain: &BlockchainService,
+    address: &str,
+) -> Result<Token> {
+    if let Some(token) = get_token(db_pool, address).await? {
+        return Ok(token);
+    }
+
+    let parsed = address.parse::<Address>().context("Failed to parse token address")?;
+    let token = blockchain.get_token_metadata(parsed).await?;
+    insert_token(db_pool, &token).await?;
+
+    Ok(token)
+}
+
+/// Fill in metadata for pool tokens the subgraph did not provide, via ERC20 calls
+///
+/// Returns the number of tokens added. Tokens whose metadata cannot be read are skipped
+/// and retried on the next run.
+pub async fn sync_token_metadata(db_pool: &PgPool, blockchain: &BlockchainService) -> Result<usize> {
+    let unknown = get_unknown_pool_tokens(db_pool).await?;
+    if unknown.is_empty() {
+        return Ok(0);
+    }
+
+    info!("Fetching metadata for {} tokens", unknown.len());
+
+    let mut added = 0;
+    for address in unknown {
+        match get_or_fetch_token(db_pool, blockchain, &address).await {
+            Ok(token) => {
+                info!("Registered token {} ({} decimals)", token.symbol, token.decimals);
+                added += 1;
+            }
+            Err(e) => warn!("Failed to fetch metadata for token {}: {:#}", address, e),
+        }
+    }
+
+    Ok(added)
+}
@@ -1,3 +1,4 @@
+use alloy::primitives::Address;
 use serde::{Deserialize, Serialize};
 
 /// GraphQL response wrapper
@@ -13,11 +14,23 @@ pub struct GraphQLError {
     pub message: String,
 }
 
-/// Response data for positions query (v4: modifyLiquidities)
+/// Entity that can be walked with an `id_gt` cursor
+pub trait Paginated {
+    /// The entity id used as the cursor for the next page
+    fn cursor(&self) -> &str;
+}
+
+/// Response data for modifyLiquidities queries
 #[derive(Debug, Deserialize)]
-pub struct PositionsData {
+pub struct ModifyLiquiditiesData {
     #[serde(rename = "modifyLiquidities")]
-    pub positions: Vec<PositionResponse>,
+    pub modify_liquidities: Vec<ModifyLiquidityResponse>,
+}
+
+impl From<ModifyLiquiditiesData> for Vec<ModifyLiquidityResponse> {
+    fn from(data: ModifyLiquiditiesData) -> Self {
+        data.modify_liquidities
+    }
 }
 
 /// Response data for swaps query
@@ -26,9 +39,15 @@ pub struct SwapsData {
     pub swaps: Vec<SwapResponse>,
 }
 
-/// Position from The Graph (v4: ModifyLiquidity event)
+impl From<SwapsData> for Vec<SwapResponse> {
+    fn from(data: SwapsData) -> Self {
+        data.swaps
+    }
+}
+
+/// ModifyLiquidity event from The Graph (v4: one liquidity add or removal)
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PositionResponse {
+pub struct ModifyLiquidityResponse {
     pub id: String,
     /// In v4, this is the origin address (position owner)
     #[serde(rename = "origin")]
@@ -38,11 +57,105 @@ pub struct PositionResponse {
     pub tick_lower: String,
     #[serde(rename = "tickUpper")]
     pub tick_upper: String,
-    /// In v4, this is the amount field (liquidity delta)
-    #[serde(rename = "amount")]
-    pub liquidity: String,
+    /// Liquidity delta (negative for removals)
+    pub amount: String,
+    /// Caller of the PoolManager (the PositionManager for NFT positions)
+    #[serde(default)]
+    pub sender: Option<String>,
     /// In v4, timestamp is a direct field
     pub timestamp: String,
+    pub transaction: TransactionResponse,
+}
+
+impl Paginated for ModifyLiquidityResponse {
+    fn cursor(&self) -> &str {
+        &self.id
+    }
+}
+
+impl ModifyLiquidityResponse {
+    /// Log index of the event, parsed from its `txHash-logIndex` id
+    pub fn log_index(&self) -> Option<u64> {
+        split_event_id(&self.id).map(|(_, log_index)| log_index)
+    }
+}
+
+/// Response data for PositionManager positions query
+#[derive(Debug, Deserialize)]
+pub struct PositionsData {
+    pub positions: Vec<PositionResponse>,
+}
+
+impl From<PositionsData> for Vec<PositionResponse> {
+    fn from(data: PositionsData) -> Self {
+        data.positions
+    }
+}
+
+/// PositionManager position (NFT) from The Graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionResponse {
+    pub id: String,
+    #[serde(rename = "tokenId")]
+    pub token_id: String,
+    /// Current NFT holder
+    pub owner: String,
+    pub origin: String,
+    #[serde(rename = "createdAtTimestamp")]
+    pub created_at_timestamp: String,
+}
+
+impl Paginated for PositionResponse {
+    fn cursor(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Response data for transfers query
+#[derive(Debug, Deserialize)]
+pub struct TransfersData {
+    pub transfers: Vec<TransferResponse>,
+}
+
+impl From<TransfersData> for Vec<TransferResponse> {
+    fn from(data: TransfersData) -> Self {
+        data.transfers
+    }
+}
+
+/// PositionManager NFT transfer from The Graph (mints have `from` = zero address)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferResponse {
+    pub id: String,
+    #[serde(rename = "tokenId")]
+    pub token_id: String,
+    pub from: String,
+    pub to: String,
+    pub timestamp: String,
+}
+
+impl TransferResponse {
+    /// Whether this transfer mints a new position
+    pub fn is_mint(&self) -> bool {
+        self.from.parse::<Address>().is_ok_and(|from| from.is_zero())
+    }
+
+    /// Transaction hash and log index, parsed from the `txHash-logIndex` id
+    pub fn tx_and_log_index(&self) -> Option<(&str, u64)> {
+        split_event_id(&self.id)
+    }
+}
+
+impl Paginated for TransferResponse {
+    fn cursor(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Split a subgraph event id of the form `txHash-logIndex`
+pub fn split_event_id(id: &str) -> Option<(&str, u64)> {
+    let (tx_hash, log_index) = id.split_once('-')?;
+    Some((tx_hash, log_index.parse().ok()?))
 }
 
 /// Pool information from The Graph
@@ -56,12 +169,24 @@ pub struct PoolResponse {
     pub fee: String,
     #[serde(rename = "tickSpacing")]
     pub tick_spacing: String,
+    #[serde(default)]
+    pub hooks: Option<String>,
+    #[serde(rename = "createdAtTimestamp", default)]
+    pub created_at_timestamp: Option<String>,
+    #[serde(rename = "createdAtBlockNumber", default)]
+    pub created_at_block_number: Option<String>,
 }
 
 /// Token information from The Graph
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenResponse {
     pub id: String,
+    #[serde(default)]
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub decimals: Option<String>,
 }
 
 /// Transaction information from The Graph
@@ -69,21 +194,66 @@ pub struct TokenResponse {
 pub struct TransactionResponse {
     #[serde(default)]
     pub id: Option<String>,
+    #[serde(rename = "blockNumber", default)]
+    pub block_number: Option<String>,
     pub timestamp: String,
 }
 
 /// Swap from The Graph
+///
+/// The subgraph does not index the swap's active liquidity, nor the LP fee applied by
+/// dynamic-fee pools; static-fee pools charge their `feeTier`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SwapResponse {
     pub id: String,
     pub transaction: TransactionResponse,
     pub pool: PoolIdResponse,
+    #[serde(default)]
+    pub sender: Option<String>,
     pub amount0: String,
     pub amount1: String,
+    /// Pool price after the swap (Q64.96)
+    #[serde(rename = "sqrtPriceX96", default)]
+    pub sqrt_price_x96: Option<String>,
+    /// Pool tick after the swap
+    #[serde(default)]
+    pub tick: Option<String>,
+    #[serde(rename = "logIndex", default)]
+    pub log_index: Option<String>,
+}
+
+impl Paginated for SwapResponse {
+    fn cursor(&self) -> &str {
+        &self.id
+    }
 }
 
 /// Simple pool ID response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PoolIdResponse {
     pub id: String,
+    /// PoolKey fee: the LP fee of static-fee pools, or the dynamic-fee flag
+    #[serde(rename = "feeTier", default)]
+    pub fee: Option<String>,
+}
+
+/// Response data for the `_meta` query
+#[derive(Debug, Deserialize)]
+pub struct MetaData {
+    #[serde(rename = "_meta")]
+    pub meta: MetaResponse,
+}
+
+/// Subgraph indexing status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetaResponse {
+    pub block: MetaBlockResponse,
+    #[serde(rename = "hasIndexingErrors")]
+    pub has_indexing_errors: bool,
+}
+
+/// Latest block indexed by the subgraph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetaBlockResponse {
+    pub number: u64,
 }
@@ -1,10 +1,19 @@
+use alloy::eips::BlockId;
+use alloy::primitives::{aliases::I24, Address, B256, U256};
 use alloy::providers::{Provider, ProviderBuilder, RootProvider};
 use alloy::transports::http::{Client, Http};
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::contracts::{IERC20Metadata, IPositionManager, IStateView, PoolKey, PositionInfo};
+use crate::pool_state::{FeeGrowth, PoolState, PositionState, Slot0, TickInfo};
+use crate::position::NftPosition;
+use crate::token::Token;
 
 /// Blockchain service for interacting with Ethereum and Uniswap v4
 pub struct BlockchainService {
     provider: RootProvider<Http<Client>>,
+    state_view: Option<Address>,
+    position_manager: Option<Address>,
 }
 
 impl BlockchainService {
@@ -13,7 +22,19 @@ impl BlockchainService {
         let provider = ProviderBuilder::new()
             .on_http(rpc_url.parse()?);
 
-        Ok(Self { provider })
+        Ok(Self { provider, state_view: None, position_manager: None })
+    }
+
+    /// Read live pool state through the StateView lens deployed at `address`
+    pub fn with_state_view(mut self, address: Address) -> Self {
+        self.state_view = Some(address);
+        self
+    }
+
+    /// Read NFT positions from the PositionManager deployed at `address`
+    pub fn with_position_manager(mut self, address: Address) -> Self {
+        self.position_manager = Some(address);
+        self
     }
 
     /// Get the current provider
@@ -26,12 +47,213 @@ impl BlockchainService {
         let block_number = self.provider.get_block_number().await?;
         Ok(block_number)
     }
+
+    /// Read a token's metadata with ERC20 `decimals()`, `symbol()` and `name()` calls
+    ///
+    /// The native currency (address(0)) has no contract and gets `Token::native()`.
+    /// `symbol` and `name` are optional in ERC20 (and some tokens return `bytes32`), so
+    /// they fall back to empty strings; `decimals` is required.
+    pub async fn get_token_metadata(&self, address: Address) -> Result<Token> {
+        if address.is_zero() {
+            return Ok(Token::native());
+        }
+
+        let token = IERC20Metadata::new(address, &self.provider);
+        let decimals = token
+            .decimals()
+            .call()
+            .await
+            .with_context(|| format!("Failed to get decimals of token {:#x}", address))?
+            ._0;
+        let symbol = token.symbol().call().await.map(|r| r._0).unwrap_or_default();
+        let name = token.name().call().await.map(|r| r._0).unwrap_or_default();
+
+        Ok(Token { address: format!("{:#x}", address), symbol, name, decimals })
+    }
+
+    /// StateView contract instance, if an address is configured
+    fn state_view(
+        &self,
+    ) -> Result<IStateView::IStateViewInstance<Http<Client>, &RootProvider<Http<Client>>>> {
+        let address =
+            self.state_view.ok_or_else(|| anyhow!("StateView address is not configured"))?;
+        Ok(IStateView::new(address, &self.provider))
+    }
+
+    /// Current sqrt price, tick, protocol fee and LP fee of a pool
+    pub async fn get_slot0(&self, pool_id: B256) -> Result<Slot0> {
+        let slot0 = self
+            .state_view()?
+            .getSlot0(pool_id)
+            .call()
+            .await
+            .with_context(|| format!("Failed to get slot0 of pool {}", pool_id))?;
+        Ok(slot0.into())
+    }
+
+    /// Active (in-range) liquidity of a pool
+    pub async fn get_liquidity(&self, pool_id: B256) -> Result<u128> {
+        let liquidity = self
+            .state_view()?
+            .getLiquidity(pool_id)
+            .call()
+            .await
+            .with_context(|| format!("Failed to get liquidity of pool {}", pool_id))?;
+        Ok(liquidity.liquidity)
+    }
+
+    /// Current price, tick, fees and active liquidity of a pool
+    pub async fn get_pool_state(&self, pool_id: B256) -> Result<PoolState> {
+        let slot0 = self.get_slot0(pool_id).await?;
+        let liquidity = self.get_liquidity(pool_id).await?;
+        Ok(PoolState { slot0, liquidity })
+    }
+
+    /// Global fee growth of a pool for both tokens
+    pub async fn get_fee_growth_globals(&self, pool_id: B256) -> Result<FeeGrowth> {
+        let growth = self
+            .state_view()?
+            .getFeeGrowthGlobals(pool_id)
+            .call()
+            .await
+            .with_context(|| format!("Failed to get fee growth globals of pool {}", pool_id))?;
+        Ok(growth.into())
+    }
+
+    /// Fee growth inside a tick range, as of the pool's tick at `block`
+    pub async fn get_fee_growth_inside(
+        &self,
+        pool_id: B256,
+        tick_lower: i32,
+        tick_upper: i32,
+        block: BlockId,
+    ) -> Result<FeeGrowth> {
+        let growth = self
+            .state_view()?
+            .getFeeGrowthInside(pool_id, to_i24(tick_lower)?, to_i24(tick_upper)?)
+            .block(block)
+            .call()
+            .await
+            .with_context(|| format!("Failed to get fee growth inside pool {}", pool_id))?;
+        Ok(growth.into())
+    }
+
+    /// Liquidity and fee growth outside an initialized tick
+    pub async fn get_tick_info(&self, pool_id: B256, tick: i32) -> Result<TickInfo> {
+        let info = self
+            .state_view()?
+            .getTickInfo(pool_id, to_i24(tick)?)
+            .call()
+            .await
+            .with_context(|| format!("Failed to get tick {} of pool {}", tick, pool_id))?;
+        Ok(info.into())
+    }
+
+    /// Liquidity and last fee growth of a position at `block`, keyed by its PoolManager
+    /// owner and salt
+    ///
+    /// For NFT positions the owner is the PositionManager and the salt is the token id.
+    pub async fn get_position_info(
+        &self,
+        pool_id: B256,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        salt: B256,
+        block: BlockId,
+    ) -> Result<PositionState> {
+        let info = self
+            .state_view()?
+            .getPositionInfo(pool_id, owner, to_i24(tick_lower)?, to_i24(tick_upper)?, salt)
+            .block(block)
+            .call()
+            .await
+            .with_context(|| format!("Failed to get position info in pool {}", pool_id))?;
+        Ok(info.into())
+    }
+
+    /// PositionManager contract instance, if an address is configured
+    fn position_manager(
+        &self,
+    ) -> Result<IPositionManager::IPositionManagerInstance<Http<Client>, &RootProvider<Http<Client>>>>
+    {
+        let address = self
+            .position_manager
+            .ok_or_else(|| anyhow!("PositionManager address is not configured"))?;
+        Ok(IPositionManager::new(address, &self.provider))
+    }
+
+    /// Pool key and packed range of a position NFT
+    pub async fn get_pool_and_position_info(
+        &self,
+        token_id: U256,
+    ) -> Result<(PoolKey, PositionInfo)> {
+        let result = self
+            .position_manager()?
+            .getPoolAndPositionInfo(token_id)
+            .call()
+            .await
+            .with_context(|| format!("Failed to get pool and info of position {}", token_id))?;
+        Ok((result.poolKey, PositionInfo::decode(result.info)))
+    }
+
+    /// Current liquidity of a position NFT
+    pub async fn get_position_liquidity(&self, token_id: U256) -> Result<u128> {
+        let result = self
+            .position_manager()?
+            .getPositionLiquidity(token_id)
+            .call()
+            .await
+            .with_context(|| format!("Failed to get liquidity of position {}", token_id))?;
+        Ok(result.liquidity)
+    }
+
+    /// Current holder of a position NFT (reverts for burned or unminted tokens)
+    pub async fn get_position_owner(&self, token_id: U256) -> Result<Address> {
+        let result = self
+            .position_manager()?
+            .ownerOf(token_id)
+            .call()
+            .await
+            .with_context(|| format!("Failed to get owner of position {}", token_id))?;
+        Ok(result.owner)
+    }
+
+    /// Token id the next minted position will get
+    pub async fn get_next_token_id(&self) -> Result<U256> {
+        let result = self
+            .position_manager()?
+            .nextTokenId()
+            .call()
+            .await
+            .context("Failed to get next position token id")?;
+        Ok(result._0)
+    }
+
+    /// Reconstruct a position from its NFT token id
+    pub async fn get_nft_position(&self, token_id: U256) -> Result<NftPosition> {
+        let owner = self.get_position_owner(token_id).await?;
+        let (pool_key, info) = self.get_pool_and_position_info(token_id).await?;
+        let liquidity = self.get_position_liquidity(token_id).await?;
+
+        if !info.matches_pool(pool_key.to_id()) {
+            bail!("Position {} info does not match its pool key", token_id);
+        }
+
+        Ok(NftPosition { token_id, owner, pool_key, info, liquidity })
+    }
+}
+
+fn to_i24(tick: i32) -> Result<I24> {
+    I24::try_from(tick).with_context(|| format!("Tick {} does not fit in int24", tick))
 }
 
 impl Clone for BlockchainService {
     fn clone(&self) -> Self {
         Self {
             provider: self.provider.clone(),
+            state_view: self.state_view,
+            position_manager: self.position_manager,
         }
     }
 }
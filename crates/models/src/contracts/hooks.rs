@@ -0,0 +1,134 @@
+use alloy::primitives::Address;
+
+use super::IHooks::Permissions;
+
+// Permission flags encoded in the low 14 bits of a hooks address (v4-core `Hooks.sol`)
+pub const BEFORE_INITIALIZE_FLAG: u16 = 1 << 13;
+pub const AFTER_INITIALIZE_FLAG: u16 = 1 << 12;
+pub const BEFORE_ADD_LIQUIDITY_FLAG: u16 = 1 << 11;
+pub const AFTER_ADD_LIQUIDITY_FLAG: u16 = 1 << 10;
+pub const BEFORE_REMOVE_LIQUIDITY_FLAG: u16 = 1 << 9;
+pub const AFTER_REMOVE_LIQUIDITY_FLAG: u16 = 1 << 8;
+pub const BEFORE_SWAP_FLAG: u16 = 1 << 7;
+pub const AFTER_SWAP_FLAG: u16 = 1 << 6;
+pub const BEFORE_DONATE_FLAG: u16 = 1 << 5;
+pub const AFTER_DONATE_FLAG: u16 = 1 << 4;
+pub const BEFORE_SWAP_RETURNS_DELTA_FLAG: u16 = 1 << 3;
+pub const AFTER_SWAP_RETURNS_DELTA_FLAG: u16 = 1 << 2;
+pub const AFTER_ADD_LIQUIDITY_RETURNS_DELTA_FLAG: u16 = 1 << 1;
+pub const AFTER_REMOVE_LIQUIDITY_RETURNS_DELTA_FLAG: u16 = 1 << 0;
+
+/// Mask of all hook permission bits
+pub const ALL_HOOK_MASK: u16 = (1 << 14) - 1;
+
+/// Flags that let a hook take or give token deltas (custom accounting)
+pub const RETURNS_DELTA_MASK: u16 = BEFORE_SWAP_RETURNS_DELTA_FLAG
+    | AFTER_SWAP_RETURNS_DELTA_FLAG
+    | AFTER_ADD_LIQUIDITY_RETURNS_DELTA_FLAG
+    | AFTER_REMOVE_LIQUIDITY_RETURNS_DELTA_FLAG;
+
+impl Permissions {
+    /// Decode the permissions the PoolManager grants a hooks address
+    pub fn from_hooks(hooks: Address) -> Self {
+        Self::from_flags(u16::from_be_bytes([hooks[18], hooks[19]]))
+    }
+
+    /// Decode permissions from hook flag bits (bits above the 14th are ignored)
+    pub fn from_flags(flags: u16) -> Self {
+        let has = |flag: u16| flags & flag != 0;
+        Self {
+            beforeInitialize: has(BEFORE_INITIALIZE_FLAG),
+            afterInitialize: has(AFTER_INITIALIZE_FLAG),
+            beforeAddLiquidity: has(BEFORE_ADD_LIQUIDITY_FLAG),
+            afterAddLiquidity: has(AFTER_ADD_LIQUIDITY_FLAG),
+            beforeRemoveLiquidity: has(BEFORE_REMOVE_LIQUIDITY_FLAG),
+            afterRemoveLiquidity: has(AFTER_REMOVE_LIQUIDITY_FLAG),
+            beforeSwap: has(BEFORE_SWAP_FLAG),
+            afterSwap: has(AFTER_SWAP_FLAG),
+            beforeDonate: has(BEFORE_DONATE_FLAG),
+            afterDonate: has(AFTER_DONATE_FLAG),
+            beforeSwapReturnDelta: has(BEFORE_SWAP_RETURNS_DELTA_FLAG),
+            afterSwapReturnDelta: has(AFTER_SWAP_RETURNS_DELTA_FLAG),
+            afterAddLiquidityReturnDelta: has(AFTER_ADD_LIQUIDITY_RETURNS_DELTA_FLAG),
+            afterRemoveLiquidityReturnDelta: has(AFTER_REMOVE_LIQUIDITY_RETURNS_DELTA_FLAG),
+        }
+    }
+
+    /// Encode the permissions back into hook flag bits
+    pub fn flags(&self) -> u16 {
+        [
+            (self.beforeInitialize, BEFORE_INITIALIZE_FLAG),
+            (self.afterInitialize, AFTER_INITIALIZE_FLAG),
+            (self.beforeAddLiquidity, BEFORE_ADD_LIQUIDITY_FLAG),
+            (self.afterAddLiquidity, AFTER_ADD_LIQUIDITY_FLAG),
+            (self.beforeRemoveLiquidity, BEFORE_REMOVE_LIQUIDITY_FLAG),
+            (self.afterRemoveLiquidity, AFTER_REMOVE_LIQUIDITY_FLAG),
+            (self.beforeSwap, BEFORE_SWAP_FLAG),
+            (self.afterSwap, AFTER_SWAP_FLAG),
+            (self.beforeDonate, BEFORE_DONATE_FLAG),
+            (self.afterDonate, AFTER_DONATE_FLAG),
+            (self.beforeSwapReturnDelta, BEFORE_SWAP_RETURNS_DELTA_FLAG),
+            (self.afterSwapReturnDelta, AFTER_SWAP_RETURNS_DELTA_FLAG),
+            (self.afterAddLiquidityReturnDelta, AFTER_ADD_LIQUIDITY_RETURNS_DELTA_FLAG),
+            (self.afterRemoveLiquidityReturnDelta, AFTER_REMOVE_LIQUIDITY_RETURNS_DELTA_FLAG),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .fold(0, |flags, (_, flag)| flags | flag)
+    }
+
+    /// Whether the hook can modify swap amounts
+    pub fn returns_swap_delta(&self) -> bool {
+        self.beforeSwapReturnDelta || self.afterSwapReturnDelta
+    }
+
+    /// Whether the hook can modify amounts added or removed by LPs
+    pub fn returns_liquidity_delta(&self) -> bool {
+        self.afterAddLiquidityReturnDelta || self.afterRemoveLiquidityReturnDelta
+    }
+
+    /// Whether the hook can take or give any deltas (custom accounting)
+    pub fn has_return_delta(&self) -> bool {
+        self.flags() & RETURNS_DELTA_MASK != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::address;
+
+    #[test]
+    fn test_no_hooks_has_no_permissions() {
+        let permissions = Permissions::from_hooks(Address::ZERO);
+
+        assert_eq!(permissions, Permissions::default());
+        assert_eq!(permissions.flags(), 0);
+        assert!(!permissions.has_return_delta());
+    }
+
+    #[test]
+    fn test_decode_hook_address() {
+        // ...0xa0c0: beforeInitialize, beforeSwap and afterSwap (0x20c0), plus bit 15, which
+        // is above the hook flags and ignored
+        let hooks = address!("000000000000000000000000000000000000a0c0");
+        let permissions = Permissions::from_hooks(hooks);
+
+        assert!(permissions.beforeInitialize && permissions.beforeSwap && permissions.afterSwap);
+        assert!(!permissions.afterInitialize && !permissions.beforeDonate);
+        assert_eq!(permissions.flags(), BEFORE_INITIALIZE_FLAG | BEFORE_SWAP_FLAG | AFTER_SWAP_FLAG);
+        assert!(!permissions.has_return_delta());
+    }
+
+    #[test]
+    fn test_return_delta_flags() {
+        let swap = Permissions::from_flags(BEFORE_SWAP_FLAG | BEFORE_SWAP_RETURNS_DELTA_FLAG);
+        assert!(swap.has_return_delta() && swap.returns_swap_delta());
+        assert!(!swap.returns_liquidity_delta());
+
+        let liquidity = Permissions::from_flags(AFTER_REMOVE_LIQUIDITY_RETURNS_DELTA_FLAG);
+        assert!(liquidity.has_return_delta() && liquidity.returns_liquidity_delta());
+
+        assert_eq!(Permissions::from_flags(u16::MAX).flags(), ALL_HOOK_MASK);
+    }
+}
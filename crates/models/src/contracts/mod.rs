@@ -1,9 +1,19 @@
 use alloy::sol;
 
+mod hooks;
+mod pool_key;
+mod position_info;
+
+pub use hooks::*;
+pub use pool_key::{MAX_LP_FEE, MAX_TICK_SPACING, MIN_TICK_SPACING};
+pub use position_info::PositionInfo;
+
 // Core Uniswap v4 types and interfaces
 // These are inline Solidity definitions for the key contracts
 
 sol! {
+    #![sol(all_derives)]
+
     #[allow(missing_docs)]
     #[sol(rpc)]
     interface IPoolManager {
@@ -28,14 +38,35 @@ sol! {
             uint160 sqrtPriceLimitX96;
         }
 
+        // PoolId, Currency and IHooks are user-defined types wrapping bytes32 and address
+        event Initialize(bytes32 indexed id, address indexed currency0, address indexed currency1, uint24 fee, int24 tickSpacing, address hooks, uint160 sqrtPriceX96, int24 tick);
+        event ModifyLiquidity(bytes32 indexed id, address indexed sender, int24 tickLower, int24 tickUpper, int256 liquidityDelta, bytes32 salt);
+        event Swap(bytes32 indexed id, address indexed sender, int128 amount0, int128 amount1, uint160 sqrtPriceX96, uint128 liquidity, int24 tick, uint24 fee);
+        event Donate(bytes32 indexed id, address indexed sender, uint256 amount0, uint256 amount1);
+
         function initialize(PoolKey memory key, uint160 sqrtPriceX96) external returns (int24 tick);
         function modifyLiquidity(PoolKey memory key, ModifyLiquidityParams memory params, bytes calldata hookData) external returns (int256, int256);
         function swap(PoolKey memory key, SwapParams memory params, bytes calldata hookData) external returns (int256, int256);
         function updateDynamicLPFee(PoolKey memory key, uint24 newDynamicLPFee) external;
     }
+
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IPositionManager {
+        /// @notice ERC721 transfer of a position NFT (mints have `from` = address(0))
+        event Transfer(address indexed from, address indexed to, uint256 indexed id);
+
+        /// @notice PositionInfo is a packed uint256, see `PositionInfo::decode`
+        function getPoolAndPositionInfo(uint256 tokenId) external view returns (IPoolManager.PoolKey memory poolKey, uint256 info);
+        function getPositionLiquidity(uint256 tokenId) external view returns (uint128 liquidity);
+        function ownerOf(uint256 id) external view returns (address owner);
+        function nextTokenId() external view returns (uint256);
+    }
 }
 
 sol! {
+    #![sol(all_derives)]
+
     #[allow(missing_docs)]
     #[sol(rpc)]
     interface IHooks {
@@ -82,6 +113,16 @@ sol! {
     }
 }
 
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IERC20Metadata {
+        function decimals() external view returns (uint8);
+        function symbol() external view returns (string memory);
+        function name() external view returns (string memory);
+    }
+}
+
 sol! {
     #[allow(missing_docs)]
     #[sol(rpc)]
@@ -92,6 +133,20 @@ sol! {
     }
 }
 
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IStateView {
+        /// @notice Read-only lens over PoolManager storage (PoolId is bytes32)
+        function getSlot0(bytes32 poolId) external view returns (uint160 sqrtPriceX96, int24 tick, uint24 protocolFee, uint24 lpFee);
+        function getLiquidity(bytes32 poolId) external view returns (uint128 liquidity);
+        function getFeeGrowthGlobals(bytes32 poolId) external view returns (uint256 feeGrowthGlobal0, uint256 feeGrowthGlobal1);
+        function getFeeGrowthInside(bytes32 poolId, int24 tickLower, int24 tickUpper) external view returns (uint256 feeGrowthInside0X128, uint256 feeGrowthInside1X128);
+        function getTickInfo(bytes32 poolId, int24 tick) external view returns (uint128 liquidityGross, int128 liquidityNet, uint256 feeGrowthOutside0X128, uint256 feeGrowthOutside1X128);
+        function getPositionInfo(bytes32 poolId, address owner, int24 tickLower, int24 tickUpper, bytes32 salt) external view returns (uint128 liquidity, uint256 feeGrowthInside0LastX128, uint256 feeGrowthInside1LastX128);
+    }
+}
+
 // Re-export the generated types
 // Note: Some function names overlap between interfaces (e.g., transfer, balanceOf)
 // This is intentional as they represent different contract interfaces
@@ -104,4 +159,10 @@ pub use IUnlockCallback::*;
 #[allow(ambiguous_glob_reexports)]
 pub use IERC20Minimal::*;
 #[allow(ambiguous_glob_reexports)]
+pub use IERC20Metadata::*;
+#[allow(ambiguous_glob_reexports)]
 pub use IERC6909Claims::*;
+#[allow(ambiguous_glob_reexports)]
+pub use IStateView::*;
+#[allow(ambiguous_glob_reexports)]
+pub use IPositionManager::*;
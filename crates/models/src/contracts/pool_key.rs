@@ -0,0 +1,136 @@
+use alloy::primitives::{aliases::{I24, U24}, keccak256, Address, B256};
+use alloy::sol_types::SolValue;
+use anyhow::{bail, Context, Result};
+
+use super::IPoolManager::PoolKey;
+use crate::pool::DYNAMIC_FEE_FLAG;
+
+/// Smallest tick spacing a v4 pool can be initialized with
+pub const MIN_TICK_SPACING: i32 = 1;
+
+/// Largest tick spacing a v4 pool can be initialized with (`type(int16).max`)
+pub const MAX_TICK_SPACING: i32 = i16::MAX as i32;
+
+/// Largest static LP fee, in hundredths of a bip (100%)
+pub const MAX_LP_FEE: u32 = 1_000_000;
+
+impl PoolKey {
+    /// Build a key from its string and integer parts, as stored or sent over the API
+    pub fn from_parts(
+        currency0: &str,
+        currency1: &str,
+        fee: i32,
+        tick_spacing: i32,
+        hooks: &str,
+    ) -> Result<Self> {
+        Ok(Self {
+            currency0: currency0.parse::<Address>().context("Failed to parse currency0")?,
+            currency1: currency1.parse::<Address>().context("Failed to parse currency1")?,
+            fee: U24::try_from(fee).context("Failed to convert fee")?,
+            tickSpacing: I24::try_from(tick_spacing).context("Failed to convert tick spacing")?,
+            hooks: hooks.parse::<Address>().context("Failed to parse hooks")?,
+        })
+    }
+
+    /// Canonical v4 `PoolId`: keccak256 of the ABI-encoded key
+    pub fn to_id(&self) -> B256 {
+        keccak256(self.abi_encode())
+    }
+
+    /// Check the key against the PoolManager's `initialize` rules
+    ///
+    /// Currencies must be sorted and distinct, the tick spacing within
+    /// [`MIN_TICK_SPACING`, `MAX_TICK_SPACING`], and a static fee at most `MAX_LP_FEE`.
+    pub fn validate(&self) -> Result<()> {
+        if self.currency0 >= self.currency1 {
+            bail!(
+                "Currencies out of order or equal: {:#x} must be below {:#x}",
+                self.currency0,
+                self.currency1
+            );
+        }
+
+        let tick_spacing = self.tickSpacing.as_i32();
+        if !(MIN_TICK_SPACING..=MAX_TICK_SPACING).contains(&tick_spacing) {
+            bail!(
+                "Tick spacing {} outside [{}, {}]",
+                tick_spacing,
+                MIN_TICK_SPACING,
+                MAX_TICK_SPACING
+            );
+        }
+
+        let fee = self.fee.to::<u32>();
+        if fee != DYNAMIC_FEE_FLAG as u32 && fee > MAX_LP_FEE {
+            bail!("LP fee {} exceeds the maximum of {}", fee, MAX_LP_FEE);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::address;
+
+    const UNI: Address = address!("1f9840a85d5af5bf1d1762f925bdaddc4201f984");
+    const WETH: Address = address!("c02aaa39b223fe8d0a0e5c4f27ead083c756cc2a");
+
+    fn key(currency0: Address, currency1: Address, fee: u32, tick_spacing: i32) -> PoolKey {
+        PoolKey {
+            currency0,
+            currency1,
+            fee: U24::from(fee),
+            tickSpacing: I24::try_from(tick_spacing).unwrap(),
+            hooks: Address::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_pool_id_matches_mainnet() {
+        // ETH/USDC 0.05% pool on Ethereum mainnet
+        let usdc = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let id = key(Address::ZERO, usdc, 500, 10).to_id();
+
+        assert_eq!(
+            id.to_string(),
+            "0x21c67e77068de97969ba93d4aab21826d33ca12bb9f565d8496e8fda8a82ca27"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(key(UNI, WETH, 3000, 60).validate().is_ok());
+        assert!(key(UNI, WETH, DYNAMIC_FEE_FLAG as u32, 60).validate().is_ok());
+
+        assert!(key(WETH, UNI, 3000, 60).validate().is_err());
+        assert!(key(UNI, UNI, 3000, 60).validate().is_err());
+        assert!(key(UNI, WETH, 3000, 0).validate().is_err());
+        assert!(key(UNI, WETH, 3000, MAX_TICK_SPACING + 1).validate().is_err());
+        assert!(key(UNI, WETH, MAX_LP_FEE + 1, 60).validate().is_err());
+    }
+
+    #[test]
+    fn test_from_parts() {
+        let parsed = PoolKey::from_parts(
+            "0x1f9840a85d5af5bf1d1762f925bdaddc4201f984",
+            "0xc02aaa39b223fe8d0a0e5c4f27ead083c756cc2a",
+            3000,
+            60,
+            "0x0000000000000000000000000000000000000000",
+        )
+        .unwrap();
+        assert_eq!(parsed.to_id(), key(UNI, WETH, 3000, 60).to_id());
+
+        assert!(PoolKey::from_parts("not-an-address", "0x00", 3000, 60, "0x00").is_err());
+        assert!(PoolKey::from_parts(
+            "0x1f9840a85d5af5bf1d1762f925bdaddc4201f984",
+            "0xc02aaa39b223fe8d0a0e5c4f27ead083c756cc2a",
+            -1,
+            60,
+            "0x0000000000000000000000000000000000000000",
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use alloy::primitives::{aliases::{I24, U24}, FixedBytes, B256, U256};
+
+/// PositionManager's packed `PositionInfo` (a `uint256`)
+///
+/// Layout from the most significant bit: 200 bits of the PoolId, 24 bits `tickUpper`,
+/// 24 bits `tickLower`, 8 bits `hasSubscriber`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionInfo {
+    /// First 25 bytes of the pool's `PoolId`
+    pub pool_id_prefix: FixedBytes<25>,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub has_subscriber: bool,
+}
+
+const TICK_LOWER_OFFSET: usize = 8;
+const TICK_UPPER_OFFSET: usize = 32;
+
+impl PositionInfo {
+    /// Unpack a `PositionInfo` word
+    pub fn decode(packed: U256) -> Self {
+        let bytes = B256::from(packed);
+        let tick = |offset: usize| {
+            let raw = ((packed >> offset) & U256::from(0xff_ffffu32)).to::<u32>();
+            I24::from_raw(U24::from(raw)).as_i32()
+        };
+
+        Self {
+            pool_id_prefix: FixedBytes::from_slice(&bytes[..25]),
+            tick_lower: tick(TICK_LOWER_OFFSET),
+            tick_upper: tick(TICK_UPPER_OFFSET),
+            has_subscriber: packed.byte(0) != 0,
+        }
+    }
+
+    /// Whether this position belongs to the pool with the given id
+    pub fn matches_pool(&self, pool_id: B256) -> bool {
+        pool_id[..25] == self.pool_id_prefix[..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::b256;
+
+    const POOL_ID: B256 =
+        b256!("807ab56201b8c73b925c16b0e9a54cc15619cffa1c88dc513250a3a9c8b42dae");
+
+    /// Pack the way `PositionInfoLibrary.initialize` does
+    fn pack(pool_id: B256, tick_lower: i32, tick_upper: i32, has_subscriber: bool) -> U256 {
+        let upper_200_bits = U256::MAX << 56;
+        (U256::from_be_bytes(pool_id.0) & upper_200_bits)
+            | (U256::from(tick_upper as u32 & 0xff_ffff) << TICK_UPPER_OFFSET)
+            | (U256::from(tick_lower as u32 & 0xff_ffff) << TICK_LOWER_OFFSET)
+            | U256::from(has_subscriber as u8)
+    }
+
+    #[test]
+    fn test_decode_position_info() {
+        let info = PositionInfo::decode(pack(POOL_ID, -887220, 887220, true));
+
+        assert_eq!(info.tick_lower, -887220);
+        assert_eq!(info.tick_upper, 887220);
+        assert!(info.has_subscriber);
+        assert!(info.matches_pool(POOL_ID));
+        assert!(!info.matches_pool(B256::ZERO));
+
+        let info = PositionInfo::decode(pack(POOL_ID, -60, 0, false));
+        assert_eq!((info.tick_lower, info.tick_upper, info.has_subscriber), (-60, 0, false));
+    }
+}
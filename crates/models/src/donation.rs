@@ -0,0 +1,40 @@
+use alloy::primitives::U256;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Donate event: tokens paid directly to a pool's in-range LPs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Donation {
+    pub id: i64,
+    pub tx_hash: String,
+    pub log_index: i64,
+    pub block_number: i64,
+    pub pool_id: String,
+    pub sender: String,
+    #[serde(with = "u256_serde")]
+    pub amount0: U256,
+    #[serde(with = "u256_serde")]
+    pub amount1: U256,
+    pub timestamp: DateTime<Utc>,
+}
+
+// Custom serialization for U256
+mod u256_serde {
+    use alloy::primitives::U256;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &U256, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<U256, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        U256::from_str_radix(&s, 10).map_err(serde::de::Error::custom)
+    }
+}
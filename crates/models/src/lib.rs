@@ -4,16 +4,26 @@ pub mod contracts;
 
 // Domain models
 pub mod pool;
+pub mod pool_state;
 pub mod position;
+pub mod liquidity_event;
 pub mod swap;
+pub mod donation;
 pub mod snapshot;
 pub mod pnl;
+pub mod sync_state;
+pub mod token;
 
 // Re-export commonly used types
 pub use blockchain::BlockchainService;
 pub use contracts::*;
-pub use pool::Pool;
-pub use position::Position;
+pub use pool::{Pool, DYNAMIC_FEE_FLAG};
+pub use pool_state::{FeeGrowth, PoolState, PositionState, Slot0, TickInfo};
+pub use position::{NftPosition, Position, DEFAULT_SALT};
+pub use liquidity_event::LiquidityEvent;
 pub use swap::Swap;
+pub use donation::Donation;
 pub use snapshot::PositionSnapshot;
 pub use pnl::{PositionPnL, HealthStatus};
+pub use sync_state::{IndexedBlock, SyncEntity, SyncState};
+pub use token::{Token, NATIVE_CURRENCY};
@@ -0,0 +1,52 @@
+use alloy::primitives::I256;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// ModifyLiquidity event: a liquidity add (positive delta) or removal (negative delta)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidityEvent {
+    pub id: i64,
+    /// Source event identifier (subgraph entity id or `tx_hash-log_index`)
+    pub event_id: String,
+    pub tx_hash: Option<String>,
+    pub block_number: Option<i64>,
+    pub pool_id: String,
+    pub owner: String,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub salt: String,
+    #[serde(with = "i256_serde")]
+    pub liquidity_delta: I256,
+    pub timestamp: DateTime<Utc>,
+    /// PositionManager token id, when the event is linked to an NFT position
+    pub nft_id: Option<String>,
+}
+
+impl LiquidityEvent {
+    /// Log index within the block, parsed from a `txHash-logIndex` event id
+    pub fn log_index(&self) -> Option<i64> {
+        let (_, index) = self.event_id.rsplit_once('-')?;
+        index.parse().ok()
+    }
+}
+
+// Custom serialization for I256
+mod i256_serde {
+    use alloy::primitives::I256;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &I256, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<I256, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse::<I256>().map_err(serde::de::Error::custom)
+    }
+}
@@ -2,12 +2,22 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 /// P&L breakdown for a position
+///
+/// Fees and net P&L are `None` when the position's uncollected fees are unknown.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PositionPnL {
-    pub fees_earned: Decimal,
+    /// Uncollected fees valued in token1 units
+    pub fees_earned: Option<Decimal>,
+    /// Uncollected token0 fees, in token units
+    pub fees0: Option<Decimal>,
+    /// Uncollected token1 fees, in token units
+    pub fees1: Option<Decimal>,
+    /// Value lost against holding the entry amounts, in token1 units
     pub impermanent_loss: Decimal,
+    /// Impermanent loss as a percentage of the HODL value
+    pub impermanent_loss_percent: Decimal,
     pub gas_spent: Decimal,
-    pub net_pnl: Decimal,
+    pub net_pnl: Option<Decimal>,
 }
 
 /// Health status of a position
@@ -1,14 +1,82 @@
+use alloy::primitives::U256;
+use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+
+use crate::contracts::{Permissions, PoolKey};
+
+/// Fee value in a `PoolKey` marking a pool whose LP fee is set by its hook
+pub const DYNAMIC_FEE_FLAG: i32 = 0x800000;
 
 /// Uniswap v4 pool information
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+///
+/// Initialization data is `None` for pools first seen before it was tracked, until the
+/// pool's `Initialize` event is ingested.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Pool {
     pub pool_id: String,
     pub token0: String,
     pub token1: String,
     pub fee_tier: i32,
     pub tick_spacing: i32,
+    /// Hooks contract of the pool's key (the zero address for pools without hooks)
+    pub hooks: Option<String>,
+    /// Hook permission bits of the hooks address (`None` while the hooks are unknown)
+    pub hook_flags: Option<i32>,
+    /// Whether the hooks can take or give swap/liquidity deltas (custom accounting)
+    pub hook_return_delta: bool,
+    /// Whether the LP fee is set by the hook rather than fixed by `fee_tier`
+    pub dynamic_fee: bool,
+    /// Block of the pool's `Initialize` event
+    pub created_block: Option<i64>,
+    /// Price the pool was initialized at, as sqrt(token1/token0) in Q64.96
+    #[serde(with = "option_u256_serde")]
+    pub initial_sqrt_price_x96: Option<U256>,
+    /// Tick the pool was initialized at
+    pub initial_tick: Option<i32>,
     pub created_at: DateTime<Utc>,
 }
+
+impl Pool {
+    /// Whether a `PoolKey` fee marks a dynamic-fee pool
+    pub fn is_dynamic_fee(fee: i32) -> bool {
+        fee == DYNAMIC_FEE_FLAG
+    }
+
+    /// Permissions of the pool's hooks, if known
+    pub fn hook_permissions(&self) -> Option<Permissions> {
+        self.hook_flags.map(|flags| Permissions::from_flags(flags as u16))
+    }
+
+    /// Rebuild the pool's `PoolKey` (requires a known hooks address)
+    pub fn key(&self) -> Result<PoolKey> {
+        let hooks = self.hooks.as_deref().ok_or_else(|| anyhow!("Pool hooks are unknown"))?;
+
+        PoolKey::from_parts(&self.token0, &self.token1, self.fee_tier, self.tick_spacing, hooks)
+    }
+}
+
+// Custom serialization for optional U256
+mod option_u256_serde {
+    use alloy::primitives::U256;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &Option<U256>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(value) => serializer.serialize_str(&value.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<U256>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| U256::from_str_radix(&s, 10).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
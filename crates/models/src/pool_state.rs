@@ -0,0 +1,127 @@
+use alloy::primitives::U256;
+
+use crate::contracts::IStateView::{
+    getFeeGrowthGlobalsReturn, getFeeGrowthInsideReturn, getPositionInfoReturn, getSlot0Return,
+    getTickInfoReturn,
+};
+
+/// Live `slot0` of a pool, read through StateView
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slot0 {
+    /// Current price (Q64.96)
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    /// Protocol fee for both swap directions (12 bits each), in hundredths of a bip
+    pub protocol_fee: u32,
+    /// LP fee currently charged, in hundredths of a bip
+    pub lp_fee: u32,
+}
+
+/// Current price, tick, fees and active liquidity of a pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolState {
+    pub slot0: Slot0,
+    pub liquidity: u128,
+}
+
+/// Fee growth per unit of liquidity for both tokens (Q128.128)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeGrowth {
+    pub fee_growth0_x128: U256,
+    pub fee_growth1_x128: U256,
+}
+
+/// State of an initialized tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickInfo {
+    pub liquidity_gross: u128,
+    pub liquidity_net: i128,
+    pub fee_growth_outside0_x128: U256,
+    pub fee_growth_outside1_x128: U256,
+}
+
+/// PoolManager-side state of a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionState {
+    pub liquidity: u128,
+    /// Fee growth inside the range when the position was last touched
+    pub fee_growth_inside_last: FeeGrowth,
+}
+
+impl From<getSlot0Return> for Slot0 {
+    fn from(r: getSlot0Return) -> Self {
+        Self {
+            sqrt_price_x96: U256::from(r.sqrtPriceX96),
+            tick: r.tick.as_i32(),
+            protocol_fee: r.protocolFee.to(),
+            lp_fee: r.lpFee.to(),
+        }
+    }
+}
+
+impl From<getFeeGrowthGlobalsReturn> for FeeGrowth {
+    fn from(r: getFeeGrowthGlobalsReturn) -> Self {
+        Self { fee_growth0_x128: r.feeGrowthGlobal0, fee_growth1_x128: r.feeGrowthGlobal1 }
+    }
+}
+
+impl From<getFeeGrowthInsideReturn> for FeeGrowth {
+    fn from(r: getFeeGrowthInsideReturn) -> Self {
+        Self { fee_growth0_x128: r.feeGrowthInside0X128, fee_growth1_x128: r.feeGrowthInside1X128 }
+    }
+}
+
+impl From<getTickInfoReturn> for TickInfo {
+    fn from(r: getTickInfoReturn) -> Self {
+        Self {
+            liquidity_gross: r.liquidityGross,
+            liquidity_net: r.liquidityNet,
+            fee_growth_outside0_x128: r.feeGrowthOutside0X128,
+            fee_growth_outside1_x128: r.feeGrowthOutside1X128,
+        }
+    }
+}
+
+impl From<getPositionInfoReturn> for PositionState {
+    fn from(r: getPositionInfoReturn) -> Self {
+        Self {
+            liquidity: r.liquidity,
+            fee_growth_inside_last: FeeGrowth {
+                fee_growth0_x128: r.feeGrowthInside0LastX128,
+                fee_growth1_x128: r.feeGrowthInside1LastX128,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{aliases::{I24, U160, U24}, uint};
+    use alloy::sol_types::SolCall;
+
+    use crate::contracts::IStateView::getSlot0Call;
+
+    #[test]
+    fn test_slot0_from_return_data() {
+        // sqrtPriceX96 = MIN_SQRT_PRICE, tick = MIN_TICK, protocolFee = 0, lpFee = 3000
+        let data = getSlot0Return {
+            sqrtPriceX96: U160::from(4295128739u64),
+            tick: I24::try_from(-887272).unwrap(),
+            protocolFee: U24::ZERO,
+            lpFee: U24::from(3000),
+        };
+        let encoded = alloy::sol_types::SolValue::abi_encode(&(
+            data.sqrtPriceX96,
+            data.tick,
+            data.protocolFee,
+            data.lpFee,
+        ));
+
+        let slot0 = Slot0::from(getSlot0Call::abi_decode_returns(&encoded, true).unwrap());
+        assert_eq!(slot0.sqrt_price_x96, uint!(4295128739_U256));
+        assert_eq!(slot0.tick, -887272);
+        assert_eq!(slot0.protocol_fee, 0);
+        assert_eq!(slot0.lp_fee, 3000);
+    }
+}
@@ -1,21 +1,57 @@
-use alloy::primitives::U256;
+use alloy::primitives::{Address, B256, U256};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-/// LP position NFT
+use crate::contracts::{PoolKey, PositionInfo};
+
+/// Salt used when the data source does not expose one (bytes32 zero)
+pub const DEFAULT_SALT: &str =
+    "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+/// LP position, derived by summing liquidity deltas per (manager, pool, range, salt)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
     pub id: i64,
+    /// PositionManager token id, or the first liquidity event id for non-NFT positions
     pub nft_id: String,
+    /// Current NFT holder (or the liquidity owner for non-NFT positions)
     pub owner: String,
+    /// PoolManager-level owner of the liquidity (the PositionManager for NFT positions)
+    pub manager: String,
     pub pool_id: String,
     pub tick_lower: i32,
     pub tick_upper: i32,
+    pub salt: String,
     #[serde(with = "u256_serde")]
     pub liquidity: U256,
+    /// True once all liquidity has been withdrawn
+    pub closed: bool,
     pub created_at: DateTime<Utc>,
 }
 
+/// A PositionManager position read from chain by its NFT token id
+#[derive(Debug, Clone)]
+pub struct NftPosition {
+    pub token_id: U256,
+    /// Current NFT holder
+    pub owner: Address,
+    pub pool_key: PoolKey,
+    pub info: PositionInfo,
+    pub liquidity: u128,
+}
+
+impl NftPosition {
+    /// PoolId of the position's pool
+    pub fn pool_id(&self) -> B256 {
+        self.pool_key.to_id()
+    }
+
+    /// PoolManager salt of the position (`bytes32(tokenId)`)
+    pub fn salt(&self) -> B256 {
+        B256::from(self.token_id)
+    }
+}
+
 // Custom serialization for U256
 mod u256_serde {
     use alloy::primitives::U256;
@@ -1,17 +1,37 @@
-use alloy::primitives::I256;
+use alloy::primitives::{I256, U256};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 /// Swap event for fee calculations
+///
+/// The pool state fields hold the values emitted with the swap, i.e. the state after it.
+/// They are `None` for rows ingested before they were tracked. `liquidity` is only
+/// available from PoolManager logs, and the subgraph only provides `fee` for static-fee
+/// pools (dynamic fees are not indexed).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Swap {
     pub id: i64,
     pub tx_hash: String,
+    /// Log index of the Swap event within its block; with `tx_hash` it identifies the swap
+    pub log_index: i64,
+    pub block_number: Option<i64>,
     pub pool_id: String,
+    /// Caller of the PoolManager (usually a router)
+    pub sender: Option<String>,
     #[serde(with = "i256_serde")]
     pub amount0: I256,
     #[serde(with = "i256_serde")]
     pub amount1: I256,
+    /// Pool price after the swap, as sqrt(token1/token0) in Q64.96
+    #[serde(with = "option_u256_serde")]
+    pub sqrt_price_x96: Option<U256>,
+    /// Pool tick after the swap
+    pub tick: Option<i32>,
+    /// In-range liquidity after the swap
+    #[serde(with = "option_u256_serde")]
+    pub liquidity: Option<U256>,
+    /// LP fee applied to the swap in hundredths of a bip (may vary with dynamic fees)
+    pub fee: Option<i32>,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -35,3 +55,28 @@ mod i256_serde {
         s.parse::<I256>().map_err(serde::de::Error::custom)
     }
 }
+
+// Custom serialization for optional U256
+mod option_u256_serde {
+    use alloy::primitives::U256;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &Option<U256>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(value) => serializer.serialize_str(&value.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<U256>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| U256::from_str_radix(&s, 10).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
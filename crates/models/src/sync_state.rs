@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Entity types tracked by the indexer's sync checkpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncEntity {
+    Positions,
+    Swaps,
+    /// PoolManager logs ingested directly from RPC (block-based checkpoint)
+    Logs,
+}
+
+impl SyncEntity {
+    /// Get the identifier stored in the `sync_state.entity_type` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncEntity::Positions => "positions",
+            SyncEntity::Swaps => "swaps",
+            SyncEntity::Logs => "logs",
+        }
+    }
+}
+
+/// Sync checkpoint for an entity type, optionally scoped to a pool
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SyncState {
+    pub entity_type: String,
+    /// Empty string for syncs that are not scoped to a single pool
+    pub pool_id: String,
+    pub last_block: Option<i64>,
+    pub last_timestamp: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Block ingested by the log indexer that may still be reorganized away
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct IndexedBlock {
+    pub block_number: i64,
+    pub block_hash: String,
+    pub parent_hash: String,
+    pub timestamp: DateTime<Utc>,
+}
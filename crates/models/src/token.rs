@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Currency address of the chain's native token in v4 pools (address(0))
+pub const NATIVE_CURRENCY: &str = "0x0000000000000000000000000000000000000000";
+
+/// ERC20 (or native) token metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct Token {
+    /// Lowercase token address (`NATIVE_CURRENCY` for the native token)
+    pub address: String,
+    pub symbol: String,
+    pub name: String,
+    #[sqlx(try_from = "i16")]
+    pub decimals: u8,
+}
+
+impl Token {
+    /// Metadata of the native currency, which has no contract to query
+    pub fn native() -> Self {
+        Self {
+            address: NATIVE_CURRENCY.to_string(),
+            symbol: "ETH".to_string(),
+            name: "Ether".to_string(),
+            decimals: 18,
+        }
+    }
+
+    /// Whether an address is the native currency
+    pub fn is_native(address: &str) -> bool {
+        address.eq_ignore_ascii_case(NATIVE_CURRENCY)
+    }
+}
@@ -0,0 +1,9 @@
+-- Sync state table: per-entity checkpoints so the indexer resumes incrementally
+CREATE TABLE sync_state (
+    entity_type VARCHAR(32) NOT NULL,         -- Synced entity (positions, swaps, ...)
+    pool_id VARCHAR(66) NOT NULL DEFAULT '',  -- Pool scope ('' for global syncs)
+    last_block BIGINT,                        -- Last processed block number (if known)
+    last_timestamp TIMESTAMPTZ NOT NULL,      -- Last processed event timestamp
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (entity_type, pool_id)
+);
@@ -0,0 +1,73 @@
+-- Liquidity events table: every ModifyLiquidity add/remove delta
+CREATE TABLE liquidity_events (
+    id BIGSERIAL PRIMARY KEY,
+    event_id VARCHAR(140) NOT NULL UNIQUE,  -- Source event identifier (subgraph id or tx_hash-log_index)
+    tx_hash VARCHAR(66),                    -- Transaction hash (if known)
+    block_number BIGINT,                    -- Block number (if known)
+    pool_id VARCHAR(66) NOT NULL REFERENCES pools(pool_id) ON DELETE CASCADE,
+    owner VARCHAR(42) NOT NULL,             -- Position owner
+    tick_lower INTEGER NOT NULL,            -- Lower tick boundary (int24)
+    tick_upper INTEGER NOT NULL,            -- Upper tick boundary (int24)
+    salt VARCHAR(66) NOT NULL,              -- Position salt (bytes32)
+    liquidity_delta NUMERIC(78, 0) NOT NULL, -- Liquidity delta (int256, negative for removals)
+    timestamp TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_liquidity_events_position
+    ON liquidity_events(owner, pool_id, tick_lower, tick_upper, salt);
+CREATE INDEX idx_liquidity_events_timestamp ON liquidity_events(timestamp);
+
+-- Positions are now derived state: one row per (owner, pool, range, salt)
+ALTER TABLE positions
+    ADD COLUMN salt VARCHAR(66) NOT NULL
+        DEFAULT '0x0000000000000000000000000000000000000000000000000000000000000000',
+    ADD COLUMN closed BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Previously each ModifyLiquidity add was stored as its own position; keep those deltas
+INSERT INTO liquidity_events (event_id, pool_id, owner, tick_lower, tick_upper, salt, liquidity_delta, timestamp)
+SELECT nft_id, pool_id, owner, tick_lower, tick_upper, salt, liquidity, created_at
+FROM positions
+ON CONFLICT (event_id) DO NOTHING;
+
+-- Collapse duplicate positions onto the earliest row for each position key, moving
+-- their snapshots over first (snapshots cascade on delete)
+UPDATE position_snapshots s
+SET position_id = keep.survivor_id
+FROM (
+    SELECT id,
+           FIRST_VALUE(id) OVER (
+               PARTITION BY owner, pool_id, tick_lower, tick_upper, salt
+               ORDER BY created_at, id
+           ) AS survivor_id
+    FROM positions
+) keep
+WHERE s.position_id = keep.id
+  AND keep.id <> keep.survivor_id;
+
+DELETE FROM positions p
+USING positions earlier
+WHERE p.owner = earlier.owner
+  AND p.pool_id = earlier.pool_id
+  AND p.tick_lower = earlier.tick_lower
+  AND p.tick_upper = earlier.tick_upper
+  AND p.salt = earlier.salt
+  AND (earlier.created_at, earlier.id) < (p.created_at, p.id);
+
+UPDATE positions p
+SET liquidity = GREATEST(e.total, 0),
+    closed = e.total <= 0
+FROM (
+    SELECT owner, pool_id, tick_lower, tick_upper, salt, SUM(liquidity_delta) AS total
+    FROM liquidity_events
+    GROUP BY owner, pool_id, tick_lower, tick_upper, salt
+) e
+WHERE p.owner = e.owner
+  AND p.pool_id = e.pool_id
+  AND p.tick_lower = e.tick_lower
+  AND p.tick_upper = e.tick_upper
+  AND p.salt = e.salt;
+
+CREATE UNIQUE INDEX idx_positions_key ON positions(owner, pool_id, tick_lower, tick_upper, salt);
+
+-- Removals were never fetched before; resync liquidity events from scratch
+DELETE FROM sync_state WHERE entity_type = 'positions';
@@ -0,0 +1,24 @@
+-- Positions minted through the PositionManager are owned in the PoolManager by the
+-- PositionManager itself (salt = tokenId), while `owner` is the current NFT holder.
+-- `manager` keeps the PoolManager-level owner that liquidity events are keyed by.
+ALTER TABLE positions ADD COLUMN manager VARCHAR(42);
+UPDATE positions SET manager = owner;
+ALTER TABLE positions ALTER COLUMN manager SET NOT NULL;
+
+DROP INDEX idx_positions_key;
+CREATE UNIQUE INDEX idx_positions_key ON positions(manager, pool_id, tick_lower, tick_upper, salt);
+
+COMMENT ON COLUMN liquidity_events.owner IS 'PoolManager-level position owner (positions.manager)';
+
+-- PositionManager token id of the NFT position an event belongs to (NULL when unlinked)
+ALTER TABLE liquidity_events ADD COLUMN nft_id VARCHAR(78);
+
+-- Existing positions use event ids as nft_id and tx origins as owner. Keep them (their
+-- snapshots hold P&L history that cannot be re-indexed) and rewind the positions
+-- checkpoint to the first stored event: the re-sync links those events to their NFTs
+-- and re-keys the positions in place.
+DELETE FROM sync_state WHERE entity_type = 'positions';
+INSERT INTO sync_state (entity_type, pool_id, last_block, last_timestamp)
+SELECT 'positions', '', MIN(block_number), MIN(timestamp)
+FROM liquidity_events
+HAVING COUNT(*) > 0;
@@ -0,0 +1,15 @@
+-- Donations table: PoolManager Donate events (fees paid directly to in-range LPs)
+CREATE TABLE donations (
+    id BIGSERIAL PRIMARY KEY,
+    tx_hash VARCHAR(66) NOT NULL,         -- Transaction hash
+    log_index BIGINT NOT NULL,            -- Log index within the block
+    block_number BIGINT NOT NULL,
+    pool_id VARCHAR(66) NOT NULL REFERENCES pools(pool_id) ON DELETE CASCADE,
+    sender VARCHAR(42) NOT NULL,          -- Donor
+    amount0 NUMERIC(78, 0) NOT NULL,      -- Token0 amount (uint256)
+    amount1 NUMERIC(78, 0) NOT NULL,      -- Token1 amount (uint256)
+    timestamp TIMESTAMPTZ NOT NULL,
+    UNIQUE(tx_hash, log_index)
+);
+
+CREATE INDEX idx_donations_pool_id ON donations(pool_id, timestamp);
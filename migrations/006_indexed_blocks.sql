@@ -0,0 +1,15 @@
+-- Indexed blocks: hashes of recently ingested blocks, used by the log indexer to
+-- detect chain reorganizations. Blocks deeper than the confirmation depth are pruned.
+CREATE TABLE indexed_blocks (
+    block_number BIGINT PRIMARY KEY,
+    block_hash VARCHAR(66) NOT NULL,
+    parent_hash VARCHAR(66) NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL
+);
+
+-- Swaps record their block so rows from orphaned blocks can be rolled back
+ALTER TABLE swaps ADD COLUMN block_number BIGINT;
+
+CREATE INDEX idx_swaps_block_number ON swaps(block_number);
+CREATE INDEX idx_liquidity_events_block_number ON liquidity_events(block_number);
+CREATE INDEX idx_donations_block_number ON donations(block_number);
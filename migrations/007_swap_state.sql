@@ -0,0 +1,8 @@
+-- Full swap state: the pool state emitted with each v4 Swap event, plus the event's
+-- position in the chain. Existing rows keep NULLs until they are re-ingested.
+ALTER TABLE swaps ADD COLUMN log_index BIGINT;
+ALTER TABLE swaps ADD COLUMN sender VARCHAR(42);
+ALTER TABLE swaps ADD COLUMN sqrt_price_x96 NUMERIC(49, 0);  -- uint160, Q64.96
+ALTER TABLE swaps ADD COLUMN tick INTEGER;                   -- int24
+ALTER TABLE swaps ADD COLUMN liquidity NUMERIC(39, 0);       -- uint128
+ALTER TABLE swaps ADD COLUMN fee INTEGER;                    -- uint24, hundredths of a bip
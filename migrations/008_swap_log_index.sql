@@ -0,0 +1,12 @@
+-- Key swaps by their log instead of (tx_hash, pool_id): a transaction can swap through
+-- the same pool several times (routers, MEV bundles, multi-hop routes).
+ALTER TABLE swaps DROP CONSTRAINT swaps_tx_hash_pool_id_key;
+
+-- Rows ingested before log indexes were stored are kept and backfilled in place by the
+-- indexers: from the subgraph's `txHash#logIndex` entity ids first
+-- (`GraphIndexer::backfill_swap_log_indexes`), then from their transaction receipts
+-- (`LogIndexer::backfill_swap_log_indexes`). Only swaps neither can match are dropped,
+-- with their checkpoints rewound for re-ingestion. Until then they are left out of swap
+-- queries.
+ALTER TABLE swaps ADD CONSTRAINT swaps_tx_hash_log_index_key UNIQUE (tx_hash, log_index);
+CREATE INDEX idx_swaps_unindexed ON swaps(tx_hash) WHERE log_index IS NULL;
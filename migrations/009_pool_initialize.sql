@@ -0,0 +1,10 @@
+-- Pool initialization data from the PoolManager Initialize event. Pools first seen
+-- through the subgraph keep NULLs until their Initialize event is ingested.
+ALTER TABLE pools ADD COLUMN hooks VARCHAR(42);                     -- Hooks contract (zero address if none)
+ALTER TABLE pools ADD COLUMN dynamic_fee BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE pools ADD COLUMN created_block BIGINT;                  -- Block of the Initialize event
+ALTER TABLE pools ADD COLUMN initial_sqrt_price_x96 NUMERIC(49, 0); -- uint160, Q64.96
+ALTER TABLE pools ADD COLUMN initial_tick INTEGER;                  -- int24
+
+-- A PoolKey fee of 0x800000 marks a dynamic-fee pool
+UPDATE pools SET dynamic_fee = TRUE WHERE fee_tier = 8388608;
@@ -0,0 +1,11 @@
+-- Token metadata registry, filled from the subgraph or from ERC20 metadata calls.
+-- v4 pools use address(0) for the chain's native currency.
+CREATE TABLE tokens (
+    address VARCHAR(42) PRIMARY KEY,      -- Lowercase token address
+    symbol VARCHAR(64) NOT NULL,
+    name VARCHAR(256) NOT NULL,
+    decimals SMALLINT NOT NULL CHECK (decimals BETWEEN 0 AND 255)
+);
+
+INSERT INTO tokens (address, symbol, name, decimals)
+VALUES ('0x0000000000000000000000000000000000000000', 'ETH', 'Ether', 18);
@@ -0,0 +1,11 @@
+-- Hook permissions, decoded from the low 14 bits of the hooks address. NULL while the
+-- pool's hooks are unknown.
+ALTER TABLE pools ADD COLUMN hook_flags INTEGER CHECK (hook_flags BETWEEN 0 AND 16383);
+-- Hooks can take or give swap/liquidity deltas (any *ReturnDelta flag, bits 0-3)
+ALTER TABLE pools ADD COLUMN hook_return_delta BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE pools
+SET hook_flags = ('x' || right(hooks, 4))::bit(16)::integer & 16383
+WHERE hooks IS NOT NULL;
+
+UPDATE pools SET hook_return_delta = TRUE WHERE hook_flags & 15 <> 0;
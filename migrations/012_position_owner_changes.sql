@@ -0,0 +1,11 @@
+-- Position owner changes applied from PositionManager Transfer logs, kept until their block
+-- is final so a reorg can restore the holder an orphaned transfer replaced
+CREATE TABLE position_owner_changes (
+    id BIGSERIAL PRIMARY KEY,
+    nft_id VARCHAR(78) NOT NULL,
+    block_number BIGINT NOT NULL,
+    previous_owner VARCHAR(42) NOT NULL,
+    owner VARCHAR(42) NOT NULL
+);
+
+CREATE INDEX idx_position_owner_changes_block ON position_owner_changes(block_number);